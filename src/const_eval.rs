@@ -0,0 +1,77 @@
+// Evaluation of the constants and statics of a program. Their initializers only
+// refer to literals and constants, which the type checker makes sure of, so their
+// values are known before the program runs. The type checker evaluates them once,
+// see Program::global_values.
+use std::collections::HashMap;
+
+use crate::{
+  errors::runtime_error::RuntimeError,
+  types::{context::Context, global_dec::GlobalDec, scope::Scope, variable::Variable},
+};
+
+// An initializer can still fail while evaluated, dividing by a constant that is zero,
+// the error comes along with the name of the global it initializes
+pub fn eval_globals(
+  globals: &Vec<GlobalDec>,
+) -> Result<Scope<Variable>, (String, RuntimeError)> {
+  let mut values = Scope::new();
+  for global in globals.iter() {
+    match eval_global(global, globals, &mut values) {
+      Ok(()) => (),
+      Err(e) => return Err(e),
+    }
+  }
+  Ok(values)
+}
+
+// The constants an initializer refers to are evaluated before it, regardless of
+// the order they are declared in
+fn eval_global(
+  global: &GlobalDec,
+  globals: &Vec<GlobalDec>,
+  values: &mut Scope<Variable>,
+) -> Result<(), (String, RuntimeError)> {
+  if values.elements.contains_key(&global.name) {
+    return Ok(());
+  }
+  for name in global.expr.free_variables() {
+    if let Some(dependency) = globals.iter().find(|global| global.name == name) {
+      if let Err(e) = eval_global(dependency, globals, values) {
+        return Err(e);
+      }
+    }
+  }
+
+  let initializer = global.initializer();
+  let mut context = Context::from(&initializer);
+  context.globals = std::mem::replace(values, Scope::new());
+  let value = initializer.execute(&vec![], &HashMap::new(), &mut context);
+  *values = context.globals;
+  let value = match value {
+    Ok(value) => value,
+    Err(e) => return Err((global.name.clone(), e)),
+  };
+  values.elements.insert(
+    global.name.clone(),
+    Variable {
+      name: global.name.clone(),
+      value: value,
+    },
+  );
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::eval_globals;
+  use crate::types::{program::Program, value::Value};
+  use std::{convert::TryFrom, path::Path};
+
+  #[test]
+  fn constants_are_evaluated_before_their_uses() {
+    let program = Program::try_from(Path::new("tests/samples/globals.rs")).unwrap();
+    let values = eval_globals(&program.globals).unwrap();
+    assert_eq!(values.elements["LIMIT"].value, Value::Int(12));
+    assert_eq!(values.elements["AREA"].value, Value::Int(144));
+  }
+}
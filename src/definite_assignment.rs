@@ -0,0 +1,317 @@
+// Definite assignment analysis of the variables declared without a value, let x:
+// i32;. Such a variable may only be read where it is assigned a value on every
+// path leading there, and if it is immutable it may only be assigned once. The
+// analysis follows the control flow of a function body: the branches of an if or a
+// match are analyzed from the state before them and their states merged after, the
+// body of a loop is analyzed a second time from the state after an iteration.
+use crate::{
+  errors::type_error::TypeError,
+  types::{func::Func, node::Node},
+};
+
+// Whether a variable declared without a value has been assigned one
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Assigned {
+  No,
+  // On some paths only
+  Maybe,
+  Yes,
+}
+
+impl Assigned {
+  fn merge(self, other: Assigned) -> Assigned {
+    match (self, other) {
+      (Assigned::Yes, Assigned::Yes) => Assigned::Yes,
+      (Assigned::No, Assigned::No) => Assigned::No,
+      _ => Assigned::Maybe,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+struct State {
+  // The variables in scope by block, innermost last, along with whether they have
+  // been assigned and are mutable. Variables declared with a value have no state.
+  scopes: Vec<Vec<(String, Option<(Assigned, bool)>)>>,
+  // Whether the code analyzed next can be reached, it can not after a return
+  reachable: bool,
+}
+
+impl State {
+  // Variables declared in the branches have gone out of scope when states merge
+  fn merge(self, other: State) -> State {
+    if !self.reachable {
+      return other;
+    }
+    if !other.reachable {
+      return self;
+    }
+    let scopes = self
+      .scopes
+      .into_iter()
+      .zip(other.scopes)
+      .map(|(scope, other)| {
+        scope
+          .into_iter()
+          .zip(other)
+          .map(|((name, slot), (_, other))| match (slot, other) {
+            (Some((assigned, mutable)), Some((other, _))) => {
+              (name, Some((assigned.merge(other), mutable)))
+            }
+            (slot, _) => (name, slot),
+          })
+          .collect()
+      })
+      .collect();
+    State {
+      scopes: scopes,
+      reachable: true,
+    }
+  }
+
+  fn declare(&mut self, name: &str, slot: Option<(Assigned, bool)>) {
+    self
+      .scopes
+      .last_mut()
+      .unwrap()
+      .push((name.to_string(), slot));
+  }
+
+  // The state of the variable a name refers to, None unless it is declared without
+  // a value
+  fn lookup(&mut self, name: &str) -> Option<&mut (Assigned, bool)> {
+    self
+      .scopes
+      .iter_mut()
+      .rev()
+      .flat_map(|scope| scope.iter_mut().rev())
+      .find(|(declared, _)| declared == name)
+      .and_then(|(_, slot)| slot.as_mut())
+  }
+}
+
+struct Analysis {
+  errors: Vec<TypeError>,
+  // Errors are not reported during the first analysis of a loop body
+  reporting: bool,
+}
+
+pub fn check_definite_assignment(func: &Func) -> Vec<Box<dyn std::error::Error>> {
+  let mut analysis = Analysis {
+    errors: vec![],
+    reporting: true,
+  };
+  let mut state = State {
+    scopes: vec![],
+    reachable: true,
+  };
+  analysis.block(&func.body_start, &mut state);
+  analysis
+    .errors
+    .into_iter()
+    .map(|error| Box::new(error) as Box<dyn std::error::Error>)
+    .collect()
+}
+
+impl Analysis {
+  fn report(&mut self, state: &State, error: TypeError) {
+    let reported = self
+      .errors
+      .iter()
+      .any(|other| other.to_string() == error.to_string());
+    if self.reporting && state.reachable && !reported {
+      self.errors.push(error);
+    }
+  }
+
+  fn read(&mut self, name: &str, state: &mut State) {
+    let error = match state.lookup(name) {
+      Some((Assigned::No, _)) => TypeError::UninitializedVariable {
+        name: name.to_string(),
+        possibly: false,
+      },
+      Some((Assigned::Maybe, _)) => TypeError::UninitializedVariable {
+        name: name.to_string(),
+        possibly: true,
+      },
+      _ => return,
+    };
+    self.report(state, error);
+  }
+
+  fn assign(&mut self, name: &str, state: &mut State) {
+    let reassigned = match state.lookup(name) {
+      Some(slot) => {
+        let reassigned = !slot.1 && slot.0 != Assigned::No;
+        slot.0 = Assigned::Yes;
+        reassigned
+      }
+      None => false,
+    };
+    if reassigned {
+      let error = TypeError::ReassignedImmutable {
+        name: name.to_string(),
+      };
+      self.report(state, error);
+    }
+  }
+
+  // Assigning to an element or field reads the variable holding it
+  fn assign_part(&mut self, place: &Node, state: &mut State) {
+    match place {
+      Node::Var(name) => {
+        self.read(name, state);
+        if let Some((_, false)) = state.lookup(name) {
+          let error = TypeError::ImmutableAssignment { var: name.clone() };
+          self.report(state, error);
+        }
+      }
+      Node::Index(array, index) => {
+        self.expr(index, state);
+        self.assign_part(array, state);
+      }
+      Node::Field(r#struct, _) => self.assign_part(r#struct, state),
+      place => self.expr(place, state),
+    }
+  }
+
+  fn block(&mut self, body: &Node, state: &mut State) {
+    state.scopes.push(vec![]);
+    self.statements(body, state);
+    state.scopes.pop();
+  }
+
+  fn statements(&mut self, node: &Node, state: &mut State) {
+    match node {
+      Node::Let(name, _, _, expr, _) => {
+        self.expr(expr, state);
+        state.declare(name, None);
+      }
+      Node::Declare(name, _, mutable, _) => {
+        state.declare(name, Some((Assigned::No, *mutable)))
+      }
+      Node::LetTuple(names, _, expr, _) => {
+        self.expr(expr, state);
+        for (name, _) in names.iter() {
+          state.declare(name, None);
+        }
+      }
+      Node::Assign(name, expr, _) => {
+        self.expr(expr, state);
+        self.assign(name, state);
+      }
+      Node::IndexAssign(array, index, expr, _) => {
+        self.expr(expr, state);
+        self.expr(index, state);
+        self.assign_part(array, state);
+      }
+      Node::FieldAssign(r#struct, _, expr, _) => {
+        self.expr(expr, state);
+        self.assign_part(r#struct, state);
+      }
+      Node::If(condition, then_body, else_body, _) => {
+        self.expr(condition, state);
+        let mut then_state = state.clone();
+        self.block(then_body, &mut then_state);
+        let mut else_state = state.clone();
+        if let Some(else_body) = else_body {
+          self.block(else_body, &mut else_state);
+        }
+        *state = then_state.merge(else_state);
+      }
+      Node::While(condition, body, _) => {
+        self.expr(condition, state);
+        // The first iteration shows what the body assigns, the body is analyzed
+        // again from the state after it
+        let reporting = self.reporting;
+        self.reporting = false;
+        let mut iteration = state.clone();
+        self.block(body, &mut iteration);
+        self.reporting = reporting;
+
+        let mut iteration = state.clone().merge(iteration);
+        self.expr(condition, &mut iteration);
+        self.block(body, &mut iteration);
+        *state = state.clone().merge(iteration);
+      }
+      Node::Match(..) => self.expr(node, state),
+      Node::Return(expr, _) => {
+        self.expr(expr, state);
+        state.reachable = false;
+      }
+      Node::FuncCall(_, args, _) | Node::Print(_, args, _, _) => {
+        args.iter().for_each(|arg| self.expr(arg, state))
+      }
+      Node::Panic(_, args, _, _) => {
+        args.iter().for_each(|arg| self.expr(arg, state));
+        state.reachable = false;
+      }
+      Node::Check(condition, ..) => self.expr(condition, state),
+      Node::DebugContext(_) | Node::FuncDec(..) => (),
+      Node::Empty => return,
+      expr => return self.expr(expr, state),
+    }
+
+    if let Some(next) = node.get_next_instruction() {
+      self.statements(next, state);
+    }
+  }
+
+  fn expr(&mut self, node: &Node, state: &mut State) {
+    match node {
+      Node::Var(name) => self.read(name, state),
+      Node::Array(elements) | Node::Tuple(elements) | Node::Variant(_, _, elements) => {
+        elements
+          .iter()
+          .for_each(|element| self.expr(element, state))
+      }
+      Node::ArrayRepeat(expr, _) | Node::TupleField(expr, _) | Node::Field(expr, _) => {
+        self.expr(expr, state)
+      }
+      Node::Index(left, right) | Node::Op(left, _, right) => {
+        self.expr(left, state);
+        self.expr(right, state);
+      }
+      Node::Struct(_, fields) => {
+        fields.iter().for_each(|(_, expr)| self.expr(expr, state))
+      }
+      Node::MethodCall(receiver, _, args) => {
+        self.expr(receiver, state);
+        args.iter().for_each(|arg| self.expr(arg, state));
+      }
+      Node::FuncCall(_, args, None) => args.iter().for_each(|arg| self.expr(arg, state)),
+      // The variables a closure captures are read when it is created
+      Node::Closure(params, _, body, _) => {
+        let mut body_state = state.clone();
+        body_state.scopes.push(vec![]);
+        for param in params.iter() {
+          body_state.declare(&param.name, None);
+        }
+        self.statements(body, &mut body_state);
+      }
+      // The arms of a match are analyzed like the branches of an if
+      Node::Match(scrutinee, arms, _) => {
+        self.expr(scrutinee, state);
+        let mut after = State {
+          scopes: state.scopes.clone(),
+          reachable: false,
+        };
+        for (pattern, guard, body) in arms.iter() {
+          let mut arm_state = state.clone();
+          arm_state.scopes.push(vec![]);
+          for name in pattern.bindings() {
+            arm_state.declare(&name, None);
+          }
+          if let Some(guard) = guard {
+            self.expr(guard, &mut arm_state);
+          }
+          self.block(body, &mut arm_state);
+          arm_state.scopes.pop();
+          after = after.merge(arm_state);
+        }
+        *state = after;
+      }
+      _ => (),
+    }
+  }
+}
@@ -1,4 +1,8 @@
 pub mod type_error;
+pub mod type_warning;
 pub mod unknown_func_error;
 pub mod unknown_var_error;
 pub mod parse_error;
+pub mod parse_warning;
+pub mod resolve_error;
+pub mod runtime_error;
@@ -13,6 +13,30 @@ pub enum ParseError {
     line: String,
     line_num: usize,
   },
+  Invalid {
+    location: usize,
+    line: String,
+    line_num: usize,
+    message: String,
+  },
+  // An error expanding a macro, marked under the invocation
+  Expansion {
+    start: usize,
+    end: usize,
+    line: String,
+    line_num: usize,
+    message: String,
+  },
+  // Module declared with mod name; and the file it was expected in
+  MissingModule {
+    name: String,
+    path: String,
+  },
+  // An error in a module file other than the root file of the program
+  InFile {
+    file: String,
+    error: Box<ParseError>,
+  },
 }
 
 const MARKER: &'static str = "^";
@@ -79,6 +103,33 @@ impl std::fmt::Display for ParseError {
 
         write!(f, "{}", l1 + &l2)
       }
+      ParseError::Invalid{location, line, line_num, message} => {
+        let l1 = format!(
+          "{} on line {}, character {}: {}",
+          message, line_num, location, line
+        );
+
+        //Place the marker
+        let mut l2: String = String::from("\n");
+        for _ in 0..(location + l1.len() - line.len()) {
+          l2 += " ";
+        }
+        l2 += "^";
+
+        write!(f, "{}", l1 + &l2)
+      }
+      ParseError::Expansion{start, end, line, line_num, message} => {
+        let mut s = format!("{} on line {}: ", message, line_num);
+        let marker_start = s.len();
+        s += &line;
+        let marker_line = get_marker_range(marker_start + start, marker_start + end);
+        write!(f, "{}\n{}", s, marker_line)
+      }
+      ParseError::MissingModule{name, path} => {
+        write!(f, "Module {} not found, expected it in {}", name, path)
+      }
+      // The file is named on a line of its own to keep markers under their line
+      ParseError::InFile{file, error} => write!(f, "In {}:\n{}", file, error),
     }
   }
 }
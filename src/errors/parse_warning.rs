@@ -0,0 +1,29 @@
+#[derive(Debug)]
+pub enum ParseWarning {
+  // A parameter written as `name: mut T`, along with the byte offsets of the start
+  // of the parameter, the start of its type and its end
+  DeprecatedMutParam {
+    name: String,
+    start: usize,
+    type_start: usize,
+    end: usize,
+  },
+  // A warning about a module file other than the root file of the program
+  InFile {
+    file: String,
+    warning: Box<ParseWarning>,
+  },
+}
+
+impl std::fmt::Display for ParseWarning {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    let message = match self {
+      ParseWarning::DeprecatedMutParam { name, .. } => format!(
+        "Parameter {} is declared as `{}: mut T`, which is deprecated and will be removed in the next release. Declare it as `mut {}: T` or rewrite the file with --migrate",
+        name, name, name
+      ),
+      ParseWarning::InFile { file, warning } => format!("{}: {}", file, warning),
+    };
+    write!(f, "{}", message)
+  }
+}
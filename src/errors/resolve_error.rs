@@ -0,0 +1,36 @@
+use std::error;
+
+// Errors found when resolving the paths of a program split into modules
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveError {
+  // Path of an item that is not public, used outside of its module
+  PrivateItem { name: String },
+  // Path of a use declaration that does not name an item or module
+  UnresolvedImport { path: String },
+}
+
+impl std::fmt::Display for ResolveError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    let message = match self {
+      ResolveError::PrivateItem { name } => {
+        format!(
+          "{} is private and can not be used outside of its module",
+          name
+        )
+      }
+      ResolveError::UnresolvedImport { path } => {
+        format!(
+          "Unresolved import {}, no item or module has that path",
+          path
+        )
+      }
+    };
+    write!(f, "{}", message)
+  }
+}
+
+impl error::Error for ResolveError {
+  fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+    None
+  }
+}
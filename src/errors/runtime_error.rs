@@ -0,0 +1,81 @@
+use std::error;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RuntimeErrorKind {
+  // panic! and failed assertions
+  Panic,
+  // A requires, ensures or invariant that does not hold
+  ContractViolation,
+  DivisionByZero,
+  // Arithmetic whose result does not fit in an i32, including i32::MIN / -1
+  Overflow,
+  IndexOutOfBounds,
+  UndefinedVariable,
+  UndefinedFunction,
+  // A function called with more or fewer arguments than it takes
+  ArgumentMismatch,
+  // A value the type checker rules out, a bug of the interpreter
+  Internal,
+}
+
+// An error of the program being interpreted, which stops it the way a panic stops
+// a Rust program
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+  pub kind: RuntimeErrorKind,
+  pub message: String,
+  // Line of the source the panic comes from, known for panic! and contracts
+  pub line: Option<usize>,
+  // The functions being called when the error occurred, innermost first
+  pub stack: Vec<String>,
+}
+
+impl RuntimeError {
+  pub fn new(kind: RuntimeErrorKind, message: String) -> RuntimeError {
+    RuntimeError {
+      kind: kind,
+      message: message,
+      line: None,
+      stack: vec![],
+    }
+  }
+
+  // Rust's message for an out of bounds array access, reported by the interpreter
+  // and by compiled code
+  pub fn index_out_of_bounds(len: usize, index: i32) -> RuntimeError {
+    RuntimeError::new(
+      RuntimeErrorKind::IndexOutOfBounds,
+      format!(
+        "index out of bounds: the len is {} but the index is {}",
+        len, index
+      ),
+    )
+  }
+
+  pub fn at_line(mut self, line: usize) -> RuntimeError {
+    self.line = Some(line);
+    self
+  }
+}
+
+// The way the interpreter, compiled code and the test runner report an error,
+// after the name of the thread it happens on, e.g. thread 'main' panicked at
+// attempt to divide by zero, in ratio <- main
+impl std::fmt::Display for RuntimeError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    let mut text = format!("panicked at {}", self.message);
+    if let Some(line) = self.line {
+      text.push_str(&format!(", line {}", line));
+    }
+    if self.stack.len() != 0 {
+      text.push_str(&format!(", in {}", self.stack.join(" <- ")));
+    }
+    write!(f, "{}", text)
+  }
+}
+
+impl error::Error for RuntimeError {
+  fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+    None
+  }
+}
@@ -1,5 +1,7 @@
+use crate::errors::runtime_error::RuntimeError;
 use crate::types::{
-  _type::Type, func::Func, func_param::FuncParam, node::Node, opcode::Opcode,
+  _type::Type, func::Func, func_param::FuncParam, location::Location, node::Node,
+  opcode::Opcode, symbol_table::SymbolKind,
 };
 use std::error;
 
@@ -8,11 +10,11 @@ pub enum TypeError {
   OperatorMissmatch {
     expr: Node,
     op: Opcode,
-    type_left: Option<Type>,
-    type_right: Option<Type>,
+    type_left: Type,
+    type_right: Type,
   },
   ArgMissmatch {
-    arg_type: Option<Type>,
+    arg_type: Type,
     param: FuncParam,
   },
   TooManyArgs {
@@ -24,7 +26,6 @@ pub enum TypeError {
     func: String,
     missing: Vec<FuncParam>,
   },
-  NonTypeExpression,
   InvalidReturnType {
     func: Func,
     expr_type: Type,
@@ -44,10 +45,196 @@ pub enum TypeError {
   MissingReturn {
     func_name: String,
     ret_type: Type,
+    // The conditions along a path through the body that does not return
+    path: Vec<String>,
   },
   NonBooleanExpr {
     expr: Node,
-    r#type: Option<Type>,
+    r#type: Type,
+  },
+  ArrayElementMissmatch {
+    expr: Node,
+    expected: Type,
+    found: Type,
+  },
+  NonArrayIndex {
+    expr: Node,
+    r#type: Type,
+  },
+  NonIntegerIndex {
+    expr: Node,
+    r#type: Type,
+  },
+  UnknownMethod {
+    r#type: Type,
+    method: String,
+  },
+  UnknownTupleField {
+    expr: Node,
+    field: usize,
+    r#type: Type,
+  },
+  TupleArityMissmatch {
+    expected: usize,
+    r#type: Type,
+  },
+  UnknownType {
+    name: String,
+  },
+  RecursiveType {
+    name: String,
+  },
+  UnknownField {
+    r#type: Type,
+    field: String,
+  },
+  DuplicateField {
+    r#struct: String,
+    field: String,
+  },
+  MissingFields {
+    r#struct: String,
+    missing: Vec<String>,
+  },
+  FieldMissmatch {
+    r#struct: String,
+    field: String,
+    expected: Type,
+    found: Type,
+  },
+  UnknownVariant {
+    r#enum: String,
+    variant: String,
+  },
+  VariantArityMissmatch {
+    r#enum: String,
+    variant: String,
+    expected: usize,
+    found: usize,
+  },
+  VariantFieldMissmatch {
+    r#enum: String,
+    variant: String,
+    expected: Type,
+    found: Type,
+  },
+  PatternMissmatch {
+    pattern: String,
+    r#type: Type,
+  },
+  DuplicateBinding {
+    name: String,
+  },
+  NonExhaustiveMatch {
+    missing: String,
+  },
+  NotPrintable {
+    expr: Node,
+    r#type: Type,
+  },
+  EmptyRange {
+    start: i32,
+    end: i32,
+  },
+  AlternativeBindingMissmatch {
+    pattern: String,
+  },
+  MatchArmMissmatch {
+    expr: Node,
+    expected: Type,
+    found: Type,
+  },
+  UninferableTypeParam {
+    func: String,
+    param: String,
+  },
+  TypeParamMissmatch {
+    func: String,
+    param: String,
+    first: Type,
+    second: Type,
+  },
+  UnknownTrait {
+    name: String,
+  },
+  MissingImpl {
+    r#trait: String,
+    r#type: Type,
+  },
+  DuplicateImpl {
+    r#trait: String,
+    r#type: Type,
+  },
+  MissingTraitMethod {
+    r#trait: String,
+    r#type: Type,
+    method: String,
+  },
+  UnknownTraitMethod {
+    r#trait: String,
+    method: String,
+  },
+  MethodSignatureMissmatch {
+    r#trait: String,
+    r#type: Type,
+    method: String,
+  },
+  AmbiguousMethod {
+    r#type: Type,
+    method: String,
+    traits: Vec<String>,
+  },
+  NotCallable {
+    name: String,
+    r#type: Type,
+  },
+  NotConstant {
+    expr: Node,
+  },
+  GlobalMissmatch {
+    name: String,
+    r#type: Type,
+    expr_type: Type,
+  },
+  RecursiveConstant {
+    name: String,
+  },
+  FailingInitializer {
+    name: String,
+    error: RuntimeError,
+  },
+  RecursiveAlias {
+    name: String,
+  },
+  InvalidTest {
+    name: String,
+  },
+  // A variable declared without a value read where it may not have been assigned
+  UninitializedVariable {
+    name: String,
+    possibly: bool,
+  },
+  ReassignedImmutable {
+    name: String,
+  },
+  // An item or parameter declared twice, along with both locations
+  DuplicateItem {
+    kind: SymbolKind,
+    name: String,
+    first: Location,
+    second: Location,
+  },
+  // The function is None for the parameters of a closure
+  DuplicateParam {
+    name: String,
+    func: Option<String>,
+    first: Location,
+    second: Location,
+  },
+  // An error in an item declared in a module file other than the root file
+  InFile {
+    file: String,
+    error: Box<dyn error::Error>,
   },
 }
 
@@ -62,34 +249,21 @@ impl std::fmt::Display for TypeError {
       } => format!(
         "Invalid types for operand {} (left: {}, right: {}) in expression: \n\t{}",
         op.to_str(),
-        match &type_left {
-          Some(r#type) => r#type.to_str(),
-          None => "void",
-        },
-        match &type_right {
-          Some(r#type) => r#type.to_str(),
-          None => "void",
-        },
+        type_left.to_str(),
+        type_right.to_str(),
         expr.expr_into_string()
       ),
       TypeError::ArgMissmatch { arg_type, param } => format!(
         "Argument type ({}) does not match parameter {}'s type ({})",
-        match arg_type {
-          Some(r#type) => r#type.to_str(),
-          None => "void",
-        },
+        arg_type.to_str(),
         param.name,
         param._type.to_str()
       ),
-      TypeError::NonTypeExpression => "NonTypeExpression".to_string(),
       TypeError::InvalidReturnType { func, expr_type } => format!(
         "Type of returned expression ({}) does not match function {}'s signature ({})",
         expr_type.to_str(),
         func.name,
-        match &func.ret_type {
-          Some(r#type) => r#type.to_str(),
-          None => "void",
-        }
+        func.ret_type.to_str()
       ),
       TypeError::LetMissmatch { r#type, expr_type } => format!(
         "Let statement expected type {} because of declaration but received {}",
@@ -110,11 +284,22 @@ impl std::fmt::Display for TypeError {
       TypeError::MissingReturn {
         func_name,
         ret_type,
-      } => format!(
+        path,
+      } if path.len() == 0 => format!(
         "Missing return statement in function {}, expected to return type {}",
         func_name,
         ret_type.to_str()
       ),
+      TypeError::MissingReturn {
+        func_name,
+        ret_type,
+        path,
+      } => format!(
+        "Missing return statement in function {}, expected to return type {}: no value is returned when {}",
+        func_name,
+        ret_type.to_str(),
+        path.join(" and ")
+      ),
       TypeError::TooManyArgs {
         func,
         expected,
@@ -129,7 +314,7 @@ impl std::fmt::Display for TypeError {
         for (i, param) in missing.iter().enumerate() {
           missing_string += &(param.name.clone()
             + ": "
-            + param._type.to_str()
+            + &param._type.to_str()
             + if i != missing_length - 1 { "," } else { "" });
         }
         format!(
@@ -138,7 +323,261 @@ impl std::fmt::Display for TypeError {
         )
       }
       TypeError::NonBooleanExpr{ expr, r#type} => {
-          format!("Expression \n\t{}\nin conditional does not evaluate to a boolean (evaluated to {}", expr.expr_into_string(), if let Some(r#type) = r#type { r#type.to_str() } else{"void"})}
+          format!("Expression \n\t{}\nin conditional does not evaluate to a boolean (evaluated to {}", expr.expr_into_string(), r#type.to_str())}
+      TypeError::ArrayElementMissmatch {
+        expr,
+        expected,
+        found,
+      } => format!(
+        "Array element {} is of type {} but the array has elements of type {}",
+        expr.expr_into_string(),
+        found.to_str(),
+        expected.to_str()
+      ),
+      TypeError::NonArrayIndex { expr, r#type } => format!(
+        "Cannot index into {} of type {} as it is not an array",
+        expr.expr_into_string(),
+        r#type.to_str()
+      ),
+      TypeError::NonIntegerIndex { expr, r#type } => format!(
+        "Array index {} is of type {} but must be of type i32",
+        expr.expr_into_string(),
+        r#type.to_str()
+      ),
+      TypeError::UnknownMethod { r#type, method } => {
+        format!("No method {} found for type {}", method, r#type.to_str())
+      }
+      TypeError::UnknownTupleField {
+        expr,
+        field,
+        r#type,
+      } => format!(
+        "No field {} on {} of type {}",
+        field,
+        expr.expr_into_string(),
+        r#type.to_str()
+      ),
+      TypeError::TupleArityMissmatch { expected, r#type } => format!(
+        "Tuple pattern with {} elements does not match expression of type {}",
+        expected,
+        r#type.to_str()
+      ),
+      TypeError::UnknownType { name } => format!("No type {} found", name),
+      TypeError::RecursiveType { name } => {
+        format!("Type {} contains itself and would have infinite size", name)
+      }
+      TypeError::UnknownField { r#type, field } => {
+        format!("No field {} on type {}", field, r#type.to_str())
+      }
+      TypeError::DuplicateField { r#struct, field } => format!(
+        "Field {} of struct {} is initialized more than once",
+        field, r#struct
+      ),
+      TypeError::MissingFields { r#struct, missing } => format!(
+        "Missing fields {} in initializer of struct {}",
+        missing.join(", "),
+        r#struct
+      ),
+      TypeError::FieldMissmatch {
+        r#struct,
+        field,
+        expected,
+        found,
+      } => format!(
+        "Field {} of struct {} is of type {} but was initialized with type {}",
+        field,
+        r#struct,
+        expected.to_str(),
+        found.to_str()
+      ),
+      TypeError::UnknownVariant { r#enum, variant } => {
+        format!("No variant {} found on enum {}", variant, r#enum)
+      }
+      TypeError::VariantArityMissmatch {
+        r#enum,
+        variant,
+        expected,
+        found,
+      } => format!(
+        "Variant {}::{} has {} fields but {} were given",
+        r#enum, variant, expected, found
+      ),
+      TypeError::VariantFieldMissmatch {
+        r#enum,
+        variant,
+        expected,
+        found,
+      } => format!(
+        "Field of variant {}::{} is of type {} but was given type {}",
+        r#enum,
+        variant,
+        expected.to_str(),
+        found.to_str()
+      ),
+      TypeError::PatternMissmatch { pattern, r#type } => format!(
+        "Pattern {} can not match values of type {}",
+        pattern,
+        r#type.to_str()
+      ),
+      TypeError::DuplicateBinding { name } => {
+        format!("Variable {} is bound more than once in the same pattern", name)
+      }
+      TypeError::NonExhaustiveMatch { missing } => {
+        format!("Non-exhaustive match, pattern {} is not covered", missing)
+      }
+      TypeError::NotPrintable { expr, r#type } => format!(
+        "Expression {} of type {} can not be printed",
+        expr.expr_into_string(),
+        r#type.to_str()
+      ),
+      TypeError::EmptyRange { start, end } => format!(
+        "Range pattern {}..={} is empty, the start must not be greater than the end",
+        start, end
+      ),
+      TypeError::AlternativeBindingMissmatch { pattern } => format!(
+        "The alternatives of pattern {} must bind the same variables with the same types",
+        pattern
+      ),
+      TypeError::MatchArmMissmatch {
+        expr,
+        expected,
+        found,
+      } => format!(
+        "Match arm {} is of type {} but the previous arms are of type {}",
+        expr.expr_into_string(),
+        found.to_str(),
+        expected.to_str()
+      ),
+      TypeError::UninferableTypeParam { func, param } => format!(
+        "Type parameter {} of function {} can not be inferred from the arguments",
+        param, func
+      ),
+      TypeError::TypeParamMissmatch {
+        func,
+        param,
+        first,
+        second,
+      } => format!(
+        "Type parameter {} of function {} is inferred as both {} and {}",
+        param,
+        func,
+        first.to_str(),
+        second.to_str()
+      ),
+      TypeError::UnknownTrait { name } => format!("No trait {} found", name),
+      TypeError::MissingImpl { r#trait, r#type } => format!(
+        "Trait {} is not implemented for type {}",
+        r#trait,
+        r#type.to_str()
+      ),
+      TypeError::DuplicateImpl { r#trait, r#type } => format!(
+        "Trait {} is implemented more than once for type {}",
+        r#trait,
+        r#type.to_str()
+      ),
+      TypeError::MissingTraitMethod {
+        r#trait,
+        r#type,
+        method,
+      } => format!(
+        "Method {} of trait {} is missing in its impl for type {}",
+        method,
+        r#trait,
+        r#type.to_str()
+      ),
+      TypeError::UnknownTraitMethod { r#trait, method } => {
+        format!("Method {} is not a member of trait {}", method, r#trait)
+      }
+      TypeError::MethodSignatureMissmatch {
+        r#trait,
+        r#type,
+        method,
+      } => format!(
+        "Method {} in the impl of trait {} for type {} does not match its signature in the trait",
+        method,
+        r#trait,
+        r#type.to_str()
+      ),
+      TypeError::AmbiguousMethod {
+        r#type,
+        method,
+        traits,
+      } => format!(
+        "Method {} of type {} is provided by multiple traits: {}",
+        method,
+        r#type.to_str(),
+        traits.join(", ")
+      ),
+      TypeError::NotCallable { name, r#type } => format!(
+        "Variable {} of type {} is not a function and can not be called",
+        name,
+        r#type.to_str()
+      ),
+      TypeError::NotConstant { expr } => format!(
+        "Expression {} can not be evaluated at compile time",
+        expr.expr_into_string()
+      ),
+      TypeError::GlobalMissmatch {
+        name,
+        r#type,
+        expr_type,
+      } => format!(
+        "{} is declared with type {} but initialized with a value of type {}",
+        name,
+        r#type.to_str(),
+        expr_type.to_str()
+      ),
+      TypeError::RecursiveConstant { name } => {
+        format!("Constant {} depends on its own value", name)
+      }
+      TypeError::FailingInitializer { name, error } => format!(
+        "The initializer of {} can not be evaluated: {}",
+        name, error.message
+      ),
+      TypeError::RecursiveAlias { name } => {
+        format!("Type alias {} refers to itself", name)
+      }
+      TypeError::InvalidTest { name } => format!(
+        "Test function {} must not take parameters or return a value",
+        name
+      ),
+      TypeError::UninitializedVariable { name, possibly: false } => {
+        format!("Variable {} is used before being assigned a value", name)
+      }
+      TypeError::UninitializedVariable { name, possibly: true } => {
+        format!("Variable {} is possibly used before being assigned a value", name)
+      }
+      TypeError::ReassignedImmutable { name } => {
+        format!("Cannot assign twice to immutable variable {}", name)
+      }
+      TypeError::DuplicateItem {
+        kind,
+        name,
+        first,
+        second,
+      } => format!(
+        "{} {} is declared more than once, at {} and {}",
+        kind.to_str(),
+        name,
+        first,
+        second
+      ),
+      TypeError::DuplicateParam {
+        name,
+        func,
+        first,
+        second,
+      } => format!(
+        "Parameter {} of {} is declared more than once, at {} and {}",
+        name,
+        match func {
+          Some(func) => format!("function {}", func),
+          None => "closure".to_string(),
+        },
+        first,
+        second
+      ),
+      TypeError::InFile { file, error } => format!("{}: {}", file, error),
     };
     write!(f, "{}", message)
   }
@@ -0,0 +1,19 @@
+#[derive(Debug)]
+pub enum TypeWarning {
+  UnreachablePattern { pattern: String },
+  // A warning about an item declared in a module file other than the root file
+  InFile { file: String, warning: Box<TypeWarning> },
+}
+
+impl std::fmt::Display for TypeWarning {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    let message = match self {
+      TypeWarning::UnreachablePattern { pattern } => format!(
+        "Unreachable pattern {}, it only matches values matched by earlier arms",
+        pattern
+      ),
+      TypeWarning::InFile { file, warning } => format!("{}: {}", file, warning),
+    };
+    write!(f, "{}", message)
+  }
+}
@@ -0,0 +1,291 @@
+// Exhaustiveness and usefulness checking of match arms, following "Warnings for
+// pattern matching" by Luc Maranget. A pattern is useful with respect to a list of
+// patterns if it matches a value that none of them match. A match is exhaustive if
+// the wildcard is not useful with respect to its arms, and an arm is unreachable if
+// it is not useful with respect to the arms above it. Integers are treated as having
+// infinitely many constructors, so only a wildcard makes a match on them exhaustive.
+use std::collections::HashMap;
+
+use crate::types::{_type::Type, enum_dec::EnumDec, pattern::Pattern};
+
+// The head constructor of a pattern that is not a wildcard
+#[derive(Debug, Clone, PartialEq)]
+enum Constructor {
+  Variant(String),
+  Bool(bool),
+  // Inclusive range of integers, a literal is a range of a single integer
+  Range(i32, i32),
+}
+
+impl Constructor {
+  // Whether all values built by the other constructor are built by this one
+  fn covers(&self, other: &Constructor) -> bool {
+    match (self, other) {
+      (Constructor::Range(start, end), Constructor::Range(other_start, other_end)) => {
+        start <= other_start && other_end <= end
+      }
+      _ => self == other,
+    }
+  }
+}
+
+// Alternatives are expanded into separate rows before taking the head constructor
+fn head_constructor(pattern: &Pattern) -> Option<Constructor> {
+  match pattern {
+    Pattern::Wildcard | Pattern::Binding(_) => None,
+    Pattern::Variant(_, variant, _) => Some(Constructor::Variant(variant.clone())),
+    Pattern::Bool(b) => Some(Constructor::Bool(*b)),
+    Pattern::Int(n) => Some(Constructor::Range(*n, *n)),
+    Pattern::Range(start, end) => Some(Constructor::Range(*start, *end)),
+    Pattern::Or(_) => unreachable!("Taking the constructor of alternatives"),
+  }
+}
+
+// Replaces the rows starting with alternatives by a row per alternative
+fn expand_alternatives(rows: &Vec<Vec<Pattern>>) -> Vec<Vec<Pattern>> {
+  let mut expanded = vec![];
+  for row in rows.iter() {
+    match &row[0] {
+      Pattern::Or(alternatives) => {
+        let alternative_rows = alternatives
+          .iter()
+          .map(|alternative| {
+            let mut row = row.clone();
+            row[0] = alternative.clone();
+            row
+          })
+          .collect();
+        expanded.append(&mut expand_alternatives(&alternative_rows));
+      }
+      _ => expanded.push(row.clone()),
+    }
+  }
+  expanded
+}
+
+// Splits a range at the bounds of the ranges starting the rows, so that each part
+// is either covered by a row or disjoint from it. Other constructors are kept whole.
+fn split(constructor: &Constructor, rows: &Vec<Vec<Pattern>>) -> Vec<Constructor> {
+  let (start, end) = match constructor {
+    Constructor::Range(start, end) => (*start as i64, *end as i64),
+    _ => return vec![constructor.clone()],
+  };
+  let mut bounds = vec![start, end + 1];
+  for head in rows.iter().filter_map(|row| head_constructor(&row[0])) {
+    if let Constructor::Range(row_start, row_end) = head {
+      for bound in vec![row_start as i64, row_end as i64 + 1] {
+        if start < bound && bound <= end {
+          bounds.push(bound);
+        }
+      }
+    }
+  }
+  bounds.sort();
+  bounds.dedup();
+  bounds
+    .windows(2)
+    .map(|part| Constructor::Range(part[0] as i32, (part[1] - 1) as i32))
+    .collect()
+}
+
+// Keeps the rows starting with the constructor (or a wildcard) with the head
+// replaced by the patterns of its fields.
+fn specialize(
+  row: &Vec<Pattern>,
+  constructor: &Constructor,
+  arity: usize,
+) -> Option<Vec<Pattern>> {
+  if let Some(head) = head_constructor(&row[0]) {
+    if !head.covers(constructor) {
+      return None;
+    }
+  }
+  let mut fields = match &row[0] {
+    Pattern::Wildcard | Pattern::Binding(_) => vec![Pattern::Wildcard; arity],
+    Pattern::Variant(_, _, fields) => fields.clone(),
+    _ => vec![],
+  };
+  fields.extend_from_slice(&row[1..]);
+  Some(fields)
+}
+
+// Keeps the rows starting with a wildcard, without their head
+fn default_rows(rows: &Vec<Vec<Pattern>>) -> Vec<Vec<Pattern>> {
+  rows
+    .iter()
+    .filter(|row| head_constructor(&row[0]).is_none())
+    .map(|row| row[1..].to_vec())
+    .collect()
+}
+
+pub struct MatchChecker<'a> {
+  enums: &'a HashMap<String, EnumDec>,
+}
+
+impl<'a> MatchChecker<'a> {
+  pub fn new(enums: &'a HashMap<String, EnumDec>) -> Self {
+    MatchChecker { enums: enums }
+  }
+
+  // Returns a pattern for values of the type that none of the patterns match
+  pub fn missing_pattern(&self, patterns: &Vec<Pattern>, r#type: &Type) -> Option<Pattern> {
+    let rows = patterns.iter().map(|pattern| vec![pattern.clone()]).collect();
+    self
+      .useful(&rows, &vec![Pattern::Wildcard], &vec![r#type.clone()])
+      .map(|mut witness| witness.remove(0))
+  }
+
+  // Returns the positions of the patterns that only match values matched by earlier
+  // patterns. Patterns of guarded arms, marked by true, do not cover later ones.
+  pub fn unreachable_patterns(&self, arms: &Vec<(Pattern, bool)>, r#type: &Type) -> Vec<usize> {
+    let types = vec![r#type.clone()];
+    let mut rows = vec![];
+    let mut unreachable = vec![];
+    for (i, (pattern, guarded)) in arms.iter().enumerate() {
+      let row = vec![pattern.clone()];
+      if self.useful(&rows, &row, &types).is_none() {
+        unreachable.push(i);
+      }
+      if !guarded {
+        rows.push(row);
+      }
+    }
+    unreachable
+  }
+
+  // All constructors of the type, or None if a wildcard is needed to match all of
+  // its values
+  fn all_constructors(&self, r#type: &Type) -> Option<Vec<Constructor>> {
+    match r#type {
+      Type::Named(name) => self.enums.get(name).map(|r#enum| {
+        r#enum
+          .variants
+          .iter()
+          .map(|(variant, _)| Constructor::Variant(variant.clone()))
+          .collect()
+      }),
+      Type::Bool => Some(vec![Constructor::Bool(true), Constructor::Bool(false)]),
+      _ => None,
+    }
+  }
+
+  fn field_types(&self, r#type: &Type, constructor: &Constructor) -> Vec<Type> {
+    match (r#type, constructor) {
+      (Type::Named(name), Constructor::Variant(variant)) => {
+        match self.enums[name].get_variant(variant) {
+          Some((_, types)) => types.clone(),
+          None => unreachable!("No variant {} found on enum {}", variant, name),
+        }
+      }
+      (_, Constructor::Bool(_)) | (_, Constructor::Range(..)) => vec![],
+      _ => unreachable!("Constructor {:?} of type {}", constructor, r#type.to_str()),
+    }
+  }
+
+  fn build(&self, r#type: &Type, constructor: &Constructor, fields: Vec<Pattern>) -> Pattern {
+    match constructor {
+      Constructor::Variant(variant) => {
+        Pattern::Variant(r#type.to_str(), variant.clone(), fields)
+      }
+      Constructor::Bool(b) => Pattern::Bool(*b),
+      Constructor::Range(start, end) if start == end => Pattern::Int(*start),
+      Constructor::Range(start, end) => Pattern::Range(*start, *end),
+    }
+  }
+
+  // Returns patterns, one per column, for values matched by row but not by any of
+  // the rows, or None if there are no such values.
+  fn useful(
+    &self,
+    rows: &Vec<Vec<Pattern>>,
+    row: &Vec<Pattern>,
+    types: &Vec<Type>,
+  ) -> Option<Vec<Pattern>> {
+    if row.len() == 0 {
+      return if rows.len() == 0 { Some(vec![]) } else { None };
+    }
+
+    // Alternatives are useful if any of them is useful
+    if let Pattern::Or(alternatives) = &row[0] {
+      return alternatives
+        .iter()
+        .filter_map(|alternative| {
+          let mut row = row.clone();
+          row[0] = alternative.clone();
+          self.useful(rows, &row, types)
+        })
+        .next();
+    }
+    let rows = &expand_alternatives(rows);
+
+    if let Some(constructor) = head_constructor(&row[0]) {
+      return split(&constructor, rows)
+        .iter()
+        .filter_map(|constructor| self.useful_specialized(rows, row, types, constructor))
+        .next();
+    }
+
+    let mut used: Vec<Constructor> = vec![];
+    for constructor in rows.iter().filter_map(|row| head_constructor(&row[0])) {
+      if !used.contains(&constructor) {
+        used.push(constructor);
+      }
+    }
+    match self.all_constructors(&types[0]) {
+      // The rows cover every constructor so the wildcard is useful if it is useful
+      // for one of them
+      Some(ref all) if all.iter().all(|constructor| used.contains(constructor)) => all
+        .iter()
+        .filter_map(|constructor| self.useful_specialized(rows, row, types, constructor))
+        .next(),
+      all => {
+        let mut witness =
+          match self.useful(&default_rows(rows), &row[1..].to_vec(), &types[1..].to_vec()) {
+            Some(witness) => witness,
+            None => return None,
+          };
+        // Name a constructor missing from the rows if there is one
+        let head = match all {
+          Some(all) if used.len() != 0 => {
+            let missing = all
+              .into_iter()
+              .find(|constructor| !used.contains(constructor))
+              .unwrap();
+            let arity = self.field_types(&types[0], &missing).len();
+            self.build(&types[0], &missing, vec![Pattern::Wildcard; arity])
+          }
+          _ => Pattern::Wildcard,
+        };
+        witness.insert(0, head);
+        Some(witness)
+      }
+    }
+  }
+
+  fn useful_specialized(
+    &self,
+    rows: &Vec<Vec<Pattern>>,
+    row: &Vec<Pattern>,
+    types: &Vec<Type>,
+    constructor: &Constructor,
+  ) -> Option<Vec<Pattern>> {
+    let mut field_types = self.field_types(&types[0], constructor);
+    let arity = field_types.len();
+    field_types.extend_from_slice(&types[1..]);
+
+    let rows = rows
+      .iter()
+      .filter_map(|row| specialize(row, constructor, arity))
+      .collect();
+    let row = specialize(row, constructor, arity).unwrap();
+    match self.useful(&rows, &row, &field_types) {
+      Some(mut witness) => {
+        let rest = witness.split_off(arity);
+        let mut witness = vec![self.build(&types[0], constructor, witness)];
+        witness.extend(rest);
+        Some(witness)
+      }
+      None => None,
+    }
+  }
+}
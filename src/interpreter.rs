@@ -1,82 +1,697 @@
 #![allow(dead_code)]
-use std::{collections::HashMap, convert::TryInto};
+use std::{
+  collections::HashMap,
+  convert::{TryFrom, TryInto},
+};
 
+use crate::errors::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::output;
 use crate::types::{
-  context::Context, func::Func, node::Node, opcode::Opcode, scope::Scope, value::Value,
-  variable::Variable,
+  _type::Type, context::Context, func::Func, impl_dec::find_method, node::Node,
+  opcode::Opcode, pattern::Pattern, scope::Scope, value::Value, variable::Variable,
 };
 
 macro_rules! eval_next_instr {
   ($next_instr:expr, $context:expr, $funcs:expr) => {
     match $next_instr {
       Some(instr) => eval(instr, $context, $funcs),
-      None => Node::Empty,
+      None => Ok(Node::Empty),
     }
   };
 }
 
-pub fn eval(
+// An error of the interpreter itself, the type checker rules out the values that
+// cause it
+fn internal(message: String) -> RuntimeError {
+  RuntimeError::new(RuntimeErrorKind::Internal, message)
+}
+
+// A scope holding the variables, the type checker makes sure their names differ
+pub fn bind(vars: Vec<Variable>) -> Result<Scope<Variable>, RuntimeError> {
+  Scope::try_from(vars).map_err(internal)
+}
+
+// Evaluates an expression to a value, which every expression that type checks has
+pub fn eval_value(
+  node: &Node,
+  context: &mut Context<Variable>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Value, RuntimeError> {
+  let expr = match eval_expr(node, context, funcs) {
+    Ok(expr) => expr,
+    Err(e) => return Err(e),
+  };
+  match expr.try_into() {
+    Ok(value) => Ok(value),
+    Err(e) => Err(internal(format!("{}: {:#?}", e, node))),
+  }
+}
+
+// Evaluates the arguments of a format string and puts them between its pieces, all
+// arguments are evaluated before the text is put together
+fn eval_format(
+  pieces: &Vec<String>,
+  args: &Vec<Node>,
+  context: &mut Context<Variable>,
+  funcs: &HashMap<String, Func>,
+) -> Result<String, RuntimeError> {
+  let mut values = vec![];
+  for arg in args.iter() {
+    match eval_value(arg, context, funcs) {
+      Ok(value) => values.push(String::from(&value)),
+      Err(e) => return Err(e),
+    }
+  }
+  let mut output = pieces[0].clone();
+  for (value, piece) in values.iter().zip(pieces[1..].iter()) {
+    output.push_str(value);
+    output.push_str(piece);
+  }
+  Ok(output)
+}
+
+// Fails with Rust's message if index is out of bounds for an array of length len,
+// otherwise returns the index as an offset into the array.
+fn check_bounds(len: usize, index: i32) -> Result<usize, RuntimeError> {
+  if index < 0 || index as usize >= len {
+    return Err(RuntimeError::index_out_of_bounds(len, index));
+  }
+  Ok(index as usize)
+}
+
+fn eval_index(
+  index: &Node,
+  context: &mut Context<Variable>,
+  funcs: &HashMap<String, Func>,
+) -> Result<i32, RuntimeError> {
+  match eval_expr(index, context, funcs) {
+    Ok(Node::Number(index)) => Ok(index),
+    Err(e) => Err(e),
+    Ok(_) => Err(internal(
+      "Array index does not evaluate to an integer".to_string(),
+    )),
+  }
+}
+
+// A step from a value into one of its parts
+enum Projection {
+  Index(i32),
+  Field(String),
+}
+
+// Evaluates the indices of a place such as a[i].x, returning the name of the
+// variable and the steps from its value to the part that is assigned to.
+fn eval_place(
   node: &Node,
   context: &mut Context<Variable>,
   funcs: &HashMap<String, Func>,
-) -> Node {
+) -> Result<(String, Vec<Projection>), RuntimeError> {
   match node {
-    Node::Var(var_name) => match context.get_variable(&var_name) {
-      Some(var) => match var.value {
-        Value::Bool(b) => Node::Bool(b),
-        Value::Int(n) => Node::Number(n),
-      },
-      None => panic!("Undefined variable {}", (*var_name)),
+    Node::Var(name) => Ok((name.clone(), vec![])),
+    Node::Index(array, index) => {
+      let (name, mut projections) = match eval_place(array, context, funcs) {
+        Ok(place) => place,
+        Err(e) => return Err(e),
+      };
+      match eval_index(index, context, funcs) {
+        Ok(index) => projections.push(Projection::Index(index)),
+        Err(e) => return Err(e),
+      }
+      Ok((name, projections))
+    }
+    Node::Field(r#struct, field) => {
+      let (name, mut projections) = match eval_place(r#struct, context, funcs) {
+        Ok(place) => place,
+        Err(e) => return Err(e),
+      };
+      projections.push(Projection::Field(field.clone()));
+      Ok((name, projections))
+    }
+    _ => Err(internal(format!("Cannot assign to {:#?}", node))),
+  }
+}
+
+// Assigns val to the part of variable id reached through the projections.
+fn assign_place(
+  id: &str,
+  projections: Vec<Projection>,
+  val: Value,
+  context: &mut Context<Variable>,
+) -> Result<(), RuntimeError> {
+  let mut part = match context.get_variable_mut(id) {
+    Some(var) => &mut var.value,
+    None => return Err(undefined_variable(id)),
+  };
+  for projection in projections {
+    part = match (part, projection) {
+      (Value::Array(elements), Projection::Index(index)) => {
+        let index = match check_bounds(elements.len(), index) {
+          Ok(index) => index,
+          Err(e) => return Err(e),
+        };
+        &mut elements[index]
+      }
+      (Value::Struct(_, fields), Projection::Field(field)) => {
+        match fields.iter_mut().find(|(name, _)| *name == field) {
+          Some((_, value)) => value,
+          None => return Err(internal(format!("No field {} found", field))),
+        }
+      }
+      _ => {
+        let message = "Assigning to a part of a value that does not exist";
+        return Err(internal(message.to_string()));
+      }
+    };
+  }
+  *part = val;
+  Ok(())
+}
+
+fn undefined_variable(name: &str) -> RuntimeError {
+  RuntimeError::new(
+    RuntimeErrorKind::UndefinedVariable,
+    format!("Undefined variable {}", name),
+  )
+}
+
+// Matches a value against a pattern, collecting the variables bound by the pattern.
+fn match_pattern(
+  pattern: &Pattern,
+  value: &Node,
+  bindings: &mut Vec<Variable>,
+) -> Result<bool, RuntimeError> {
+  match (pattern, value) {
+    (Pattern::Wildcard, _) => Ok(true),
+    (Pattern::Binding(name), _) => match value.clone().try_into() {
+      Ok(value) => {
+        bindings.push(Variable {
+          name: name.clone(),
+          value: value,
+        });
+        Ok(true)
+      }
+      Err(e) => Err(internal(e.to_string())),
     },
-    Node::Number(_) | Node::Bool(_) => node.clone(),
-    Node::Op(left_node, op, right_node) => {
-      let left = eval(left_node, context, funcs);
-      let right = eval(right_node, context, funcs);
-      match op {
-        Opcode::Add => left + right,
-        Opcode::Sub => left - right,
-        Opcode::Mul => left * right,
-        Opcode::Div => left / right,
-        Opcode::Geq => Node::Bool(left >= right),
-        Opcode::Leq => Node::Bool(left <= right),
-        Opcode::Gneq => Node::Bool(left > right),
-        Opcode::Lneq => Node::Bool(left < right),
-        Opcode::Eq => Node::Bool(left == right),
-        Opcode::Neq => Node::Bool(left != right),
-        Opcode::And => {
-          let b1 = match left {
-            Node::Bool(b) => b,
-            _ => panic!("Left side of logical operator && does not evaluate to boolean"),
-          };
-          let b2 = match right {
-            Node::Bool(b) => b,
-            _ => panic!("Right side of logical operator && does not evaluate to boolean"),
-          };
-          Node::Bool(b1 && b2)
+    (Pattern::Variant(_, variant, patterns), Node::Variant(_, value_variant, fields)) => {
+      if variant != value_variant {
+        return Ok(false);
+      }
+      for (pattern, field) in patterns.iter().zip(fields.iter()) {
+        match match_pattern(pattern, field, bindings) {
+          Ok(true) => (),
+          Ok(false) => return Ok(false),
+          Err(e) => return Err(e),
         }
-        Opcode::Or => {
-          let b1 = match left {
-            Node::Bool(b) => b,
-            _ => panic!("Left side of logical operator || does not evaluate to boolean"),
-          };
-          let b2 = match right {
-            Node::Bool(b) => b,
-            _ => panic!("Right side of logical operator || does not evaluate to boolean"),
+      }
+      Ok(true)
+    }
+    (Pattern::Int(n), Node::Number(value)) => Ok(n == value),
+    (Pattern::Bool(b), Node::Bool(value)) => Ok(b == value),
+    (Pattern::Range(start, end), Node::Number(value)) => {
+      Ok(start <= value && value <= end)
+    }
+    (Pattern::Or(alternatives), _) => {
+      // Only the variables bound by the matching alternative are kept
+      for alternative in alternatives.iter() {
+        let mut alternative_bindings = vec![];
+        match match_pattern(alternative, value, &mut alternative_bindings) {
+          Ok(true) => {
+            bindings.append(&mut alternative_bindings);
+            return Ok(true);
+          }
+          Ok(false) => (),
+          Err(e) => return Err(e),
+        }
+      }
+      Ok(false)
+    }
+    _ => {
+      let message = "Matching a value against a pattern of another type";
+      Err(internal(message.to_string()))
+    }
+  }
+}
+
+// Finds the first arm with a pattern matching the value and a guard that holds.
+// Pushes a scope with the variables bound by the pattern and returns the body of
+// the arm.
+fn select_arm<'a>(
+  arms: &'a Vec<(Pattern, Option<Node>, Node)>,
+  value: &Node,
+  context: &mut Context<Variable>,
+  funcs: &HashMap<String, Func>,
+) -> Result<&'a Node, RuntimeError> {
+  for (pattern, guard, body) in arms.iter() {
+    let mut bindings = vec![];
+    match match_pattern(pattern, value, &mut bindings) {
+      Ok(true) => (),
+      Ok(false) => continue,
+      Err(e) => return Err(e),
+    }
+    // The guard can refer to the variables bound by the pattern
+    match bind(bindings) {
+      Ok(scope) => context.push(scope),
+      Err(e) => return Err(e),
+    }
+    let guard = match guard {
+      Some(guard) => eval_expr(guard, context, funcs),
+      None => return Ok(body),
+    };
+    match guard {
+      Ok(Node::Bool(true)) => return Ok(body),
+      Ok(_) => context.pop(),
+      Err(e) => return Err(e),
+    }
+  }
+  let message = "No arm matches the value, the match is not exhaustive";
+  Err(internal(message.to_string()))
+}
+
+// Whether a value is of the type `declared`, the type parameters of a generic
+// function stand for any type.
+fn value_matches(declared: &Type, val: &Value, func: &Func) -> bool {
+  match (declared, val) {
+    (Type::Named(name), _) if func.is_type_param(name) => true,
+    (Type::Alias(_, declared), _) => value_matches(declared, val, func),
+    (Type::Array(declared, len), Value::Array(values)) => {
+      *len == values.len()
+        && values
+          .iter()
+          .all(|value| value_matches(declared, value, func))
+    }
+    (Type::Tuple(declared), Value::Tuple(values)) => {
+      declared.len() == values.len()
+        && declared
+          .iter()
+          .zip(values)
+          .all(|(declared, value)| value_matches(declared, value, func))
+    }
+    // The return type of a closure is only known to the type checker
+    (Type::Func(params, _), Value::Closure(closure_params, ..)) => {
+      params.len() == closure_params.len()
+        && params
+          .iter()
+          .zip(closure_params)
+          .all(|(r#type, param)| *r#type == param._type)
+    }
+    (_, Value::Closure(..)) => false,
+    _ => *declared == Type::from(val),
+  }
+}
+
+// Fails if the type specified in a let statement does not match the value.
+fn check_let_type(
+  r#type: &Option<Type>,
+  id: &str,
+  val: &Value,
+  func: &Func,
+) -> Result<(), RuntimeError> {
+  match r#type {
+    Some(r#type) if !value_matches(r#type, val, func) => Err(internal(format!(
+      "Specified type {} for variable {} does not match the value {}",
+      r#type.to_str(),
+      id,
+      String::from(val)
+    ))),
+    _ => Ok(()),
+  }
+}
+
+// Calls a closure with the values of the arguments, the body is evaluated in a
+// context holding only the globals, the captured variables and the parameters.
+// Errors in the body are traced through a frame named <closure>.
+fn call_closure(
+  closure: Value,
+  args: &Vec<Node>,
+  context: &mut Context<Variable>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Node, RuntimeError> {
+  let (params, body, captures) = match closure {
+    Value::Closure(params, body, captures) => (params, body, captures),
+    _ => {
+      return Err(internal(
+        "Calling a value that is not a closure".to_string(),
+      ))
+    }
+  };
+  let mut arg_vars = vec![];
+  for (arg, param) in args.iter().zip(params) {
+    let value = match eval_value(arg, context, funcs) {
+      Ok(value) => value,
+      Err(e) => return Err(e),
+    };
+    arg_vars.push(Variable {
+      name: param.name,
+      value: value,
+    });
+  }
+
+  let captures = match bind(
+    captures
+      .into_iter()
+      .map(|(name, value)| Variable {
+        name: name,
+        value: value,
+      })
+      .collect::<Vec<Variable>>(),
+  ) {
+    Ok(captures) => captures,
+    Err(e) => return Err(e),
+  };
+  let arg_vars = match bind(arg_vars) {
+    Ok(arg_vars) => arg_vars,
+    Err(e) => return Err(e),
+  };
+
+  let mut closure_context: Context<Variable> = Context::from(&context.current_func);
+  closure_context.globals = std::mem::replace(&mut context.globals, Scope::new());
+  closure_context.push(captures);
+  closure_context.push(arg_vars);
+  let value = eval_expr(&body, &mut closure_context, funcs);
+  context.globals = closure_context.globals;
+  value.map_err(|mut error| {
+    error.stack.push("<closure>".to_string());
+    error
+  })
+}
+
+// Applies an arithmetic operator, failing with Rust's message where a Rust program
+// built in debug mode panics
+fn eval_arithmetic(left: Node, op: &Opcode, right: Node) -> Result<Node, RuntimeError> {
+  let (n1, n2) = match (left, right) {
+    (Node::Number(n1), Node::Number(n2)) => (n1, n2),
+    _ => {
+      return Err(internal(format!(
+        "Operands of arithmetic operator {} do not evaluate to integers",
+        op.to_str()
+      )))
+    }
+  };
+  let (result, operation) = match op {
+    Opcode::Add => (n1.checked_add(n2), "add"),
+    Opcode::Sub => (n1.checked_sub(n2), "subtract"),
+    Opcode::Mul => (n1.checked_mul(n2), "multiply"),
+    _ if n2 == 0 => {
+      return Err(RuntimeError::new(
+        RuntimeErrorKind::DivisionByZero,
+        "attempt to divide by zero".to_string(),
+      ))
+    }
+    _ => (n1.checked_div(n2), "divide"),
+  };
+  match result {
+    Some(n) => Ok(Node::Number(n)),
+    None => Err(RuntimeError::new(
+      RuntimeErrorKind::Overflow,
+      format!("attempt to {} with overflow", operation),
+    )),
+  }
+}
+
+// Applies a logical operator, both sides of which are evaluated
+fn eval_logical(left: Node, op: &Opcode, right: Node) -> Result<Node, RuntimeError> {
+  let symbol = match op {
+    Opcode::And => "&&",
+    _ => "||",
+  };
+  let b1 = match left {
+    Node::Bool(b) => b,
+    _ => {
+      return Err(internal(format!(
+        "Left side of logical operator {} does not evaluate to boolean",
+        symbol
+      )))
+    }
+  };
+  let b2 = match right {
+    Node::Bool(b) => b,
+    _ => {
+      return Err(internal(format!(
+        "Right side of logical operator {} does not evaluate to boolean",
+        symbol
+      )))
+    }
+  };
+  match op {
+    Opcode::And => Ok(Node::Bool(b1 && b2)),
+    _ => Ok(Node::Bool(b1 || b2)),
+  }
+}
+
+// Evaluates an expression to the node holding its value.
+pub fn eval_expr(
+  node: &Node,
+  context: &mut Context<Variable>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Node, RuntimeError> {
+  match node {
+    Node::Var(var_name) => match (context.get_variable(&var_name), funcs.get(var_name)) {
+      (Some(var), _) => Ok(var.value.clone().into()),
+      // A function used as a value evaluates to a closure calling it
+      (None, Some(func)) => eval_expr(&func.as_closure(), context, funcs),
+      (None, None) => Err(undefined_variable(var_name)),
+    },
+    Node::Number(_) | Node::Bool(_) | Node::Char(_) | Node::Str(_) | Node::Unit => {
+      Ok(node.clone())
+    }
+    Node::Array(elements) => {
+      let mut values = vec![];
+      for element in elements.iter() {
+        match eval_expr(element, context, funcs) {
+          Ok(value) => values.push(value),
+          Err(e) => return Err(e),
+        }
+      }
+      Ok(Node::Array(values))
+    }
+    Node::ArrayRepeat(element, len) => match eval_expr(element, context, funcs) {
+      Ok(value) => Ok(Node::Array(vec![value; *len])),
+      Err(e) => Err(e),
+    },
+    Node::Index(array, index) => {
+      let mut elements = match eval_expr(array, context, funcs) {
+        Ok(Node::Array(elements)) => elements,
+        Err(e) => return Err(e),
+        Ok(_) => {
+          let message = "Indexing into a value that is not an array";
+          return Err(internal(message.to_string()));
+        }
+      };
+      let index = match eval_index(index, context, funcs) {
+        Ok(index) => index,
+        Err(e) => return Err(e),
+      };
+      match check_bounds(elements.len(), index) {
+        Ok(index) => Ok(elements.swap_remove(index)),
+        Err(e) => Err(e),
+      }
+    }
+    Node::Tuple(elements) => {
+      let mut values = vec![];
+      for element in elements.iter() {
+        match eval_expr(element, context, funcs) {
+          Ok(value) => values.push(value),
+          Err(e) => return Err(e),
+        }
+      }
+      Ok(Node::Tuple(values))
+    }
+    Node::TupleField(tuple, field) => match eval_expr(tuple, context, funcs) {
+      Ok(Node::Tuple(mut elements)) => Ok(elements.swap_remove(*field)),
+      Err(e) => Err(e),
+      Ok(_) => {
+        let message = "Accessing a field of a value that is not a tuple";
+        Err(internal(message.to_string()))
+      }
+    },
+    Node::Struct(name, fields) => {
+      let mut values = vec![];
+      for (field, expr) in fields.iter() {
+        match eval_expr(expr, context, funcs) {
+          Ok(value) => values.push((field.clone(), value)),
+          Err(e) => return Err(e),
+        }
+      }
+      Ok(Node::Struct(name.clone(), values))
+    }
+    Node::Field(r#struct, field) => match eval_expr(r#struct, context, funcs) {
+      Ok(Node::Struct(_, fields)) => {
+        match fields.into_iter().find(|(name, _)| name == field) {
+          Some((_, value)) => Ok(value),
+          None => Err(internal(format!("No field {} found", field))),
+        }
+      }
+      Err(e) => Err(e),
+      Ok(_) => {
+        let message = "Accessing a field of a value that is not a struct";
+        Err(internal(message.to_string()))
+      }
+    },
+    Node::Variant(r#enum, variant, fields) => {
+      let mut values = vec![];
+      for field in fields.iter() {
+        match eval_expr(field, context, funcs) {
+          Ok(value) => values.push(value),
+          Err(e) => return Err(e),
+        }
+      }
+      Ok(Node::Variant(r#enum.clone(), variant.clone(), values))
+    }
+    Node::Match(scrutinee, arms, _) => {
+      let value = match eval_expr(scrutinee, context, funcs) {
+        Ok(value) => value,
+        Err(e) => return Err(e),
+      };
+      let body = match select_arm(arms, &value, context, funcs) {
+        Ok(body) => body,
+        Err(e) => return Err(e),
+      };
+      let res = match eval_expr(body, context, funcs) {
+        Ok(res) => res,
+        Err(e) => return Err(e),
+      };
+      context.pop();
+      Ok(res)
+    }
+    Node::MethodCall(receiver, method, args) => {
+      let receiver = match eval_expr(receiver, context, funcs) {
+        Ok(receiver) => receiver,
+        Err(e) => return Err(e),
+      };
+      match (receiver, method.as_str()) {
+        (Node::Array(elements), "len") => Ok(Node::Number(elements.len() as i32)),
+        // The length of a string is its length in bytes
+        (Node::Str(s), "len") => Ok(Node::Number(s.len() as i32)),
+        // Methods of traits are dispatched on the type of the receiver, which is
+        // passed as the first argument
+        (receiver, _) => {
+          let value: Value = match receiver.clone().try_into() {
+            Ok(value) => value,
+            Err(e) => return Err(internal(e.to_string())),
           };
-          Node::Bool(b1 || b2)
+          match find_method(funcs, &(&value).into(), method) {
+            Some(func) => {
+              let mut args = args.clone();
+              args.insert(0, receiver);
+              match func.execute(&args, funcs, context) {
+                Ok(value) => Ok(value.into()),
+                Err(e) => Err(e),
+              }
+            }
+            None => Err(RuntimeError::new(
+              RuntimeErrorKind::UndefinedFunction,
+              format!("No method {} found", method),
+            )),
+          }
         }
       }
     }
+    // Variables holding closures shadow the functions of the program
+    Node::FuncCall(func, args, _) => {
+      match (context.get_variable(func), funcs.get(func)) {
+        (Some(var), _) => call_closure(var.value.clone(), args, context, funcs),
+        (None, Some(func)) => match func.execute(args, funcs, context) {
+          Ok(value) => Ok(value.into()),
+          Err(e) => Err(e),
+        },
+        (None, None) => Err(RuntimeError::new(
+          RuntimeErrorKind::UndefinedFunction,
+          format!("No function {}", func),
+        )),
+      }
+    }
+    // The variables the body refers to are captured by value, globals are visible
+    // in the closure without being captured
+    Node::Closure(params, ret_type, body, None) => {
+      let mut captures = vec![];
+      for name in node.free_variables() {
+        match context.get_variable(&name) {
+          Some(var) if context.is_local(&name) => {
+            captures.push((name, var.value.clone().into()))
+          }
+          _ => (),
+        }
+      }
+      Ok(Node::Closure(
+        params.clone(),
+        ret_type.clone(),
+        body.clone(),
+        Some(captures),
+      ))
+    }
+    Node::Closure(_, _, _, Some(_)) => Ok(node.clone()),
+    Node::Op(left_node, op, right_node) => {
+      let left = match eval_expr(left_node, context, funcs) {
+        Ok(left) => left,
+        Err(e) => return Err(e),
+      };
+      let right = match eval_expr(right_node, context, funcs) {
+        Ok(right) => right,
+        Err(e) => return Err(e),
+      };
+      match op {
+        Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Div => {
+          eval_arithmetic(left, op, right)
+        }
+        Opcode::Geq => Ok(Node::Bool(left >= right)),
+        Opcode::Leq => Ok(Node::Bool(left <= right)),
+        Opcode::Gneq => Ok(Node::Bool(left > right)),
+        Opcode::Lneq => Ok(Node::Bool(left < right)),
+        Opcode::Eq => Ok(Node::Bool(left == right)),
+        Opcode::Neq => Ok(Node::Bool(left != right)),
+        Opcode::And | Opcode::Or => eval_logical(left, op, right),
+      }
+    }
+    _ => Err(internal(format!(
+      "Cannot evaluate node {:#?} as an expression",
+      node
+    ))),
+  }
+}
+
+// Evaluates a statement and the instructions following it. Returns the value of
+// the first return statement reached, or Node::Empty if there was none.
+pub fn eval(
+  node: &Node,
+  context: &mut Context<Variable>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Node, RuntimeError> {
+  match node {
+    Node::Var(_)
+    | Node::Number(_)
+    | Node::Bool(_)
+    | Node::Char(_)
+    | Node::Str(_)
+    | Node::Unit
+    | Node::Array(_)
+    | Node::ArrayRepeat(..)
+    | Node::Index(..)
+    | Node::Tuple(_)
+    | Node::TupleField(..)
+    | Node::Struct(..)
+    | Node::Field(..)
+    | Node::Variant(..)
+    | Node::MethodCall(..)
+    | Node::Closure(..)
+    | Node::Op(..) => eval_expr(node, context, funcs),
     Node::If(expr, then_body, else_body, next_instr) => {
       context.push(Scope::new());
-      let res = if eval(expr, context, funcs) == Node::Bool(true) {
+      let condition = match eval_expr(expr, context, funcs) {
+        Ok(condition) => condition,
+        Err(e) => return Err(e),
+      };
+      let res = if condition == Node::Bool(true) {
         eval(then_body, context, funcs)
       } else {
         match else_body {
           Some(body) => eval(body, context, funcs),
-          None => Node::Empty,
+          None => Ok(Node::Empty),
         }
       };
+      let res = match res {
+        Ok(res) => res,
+        Err(e) => return Err(e),
+      };
       context.pop();
 
       // If res is empty then there can not have been a return statement in any of the
@@ -85,58 +700,107 @@ pub fn eval(
       return if let Node::Empty = res {
         eval_next_instr!(next_instr, context, funcs)
       } else {
-        res
+        Ok(res)
+      };
+    }
+    Node::Match(scrutinee, arms, next_instr) => {
+      let value = match eval_expr(scrutinee, context, funcs) {
+        Ok(value) => value,
+        Err(e) => return Err(e),
+      };
+      let body = match select_arm(arms, &value, context, funcs) {
+        Ok(body) => body,
+        Err(e) => return Err(e),
+      };
+      let res = match eval(body, context, funcs) {
+        Ok(res) => res,
+        Err(e) => return Err(e),
+      };
+      context.pop();
+
+      // As for if statements, continue with the next instruction unless the arm
+      // returned
+      return if let Node::Empty = res {
+        eval_next_instr!(next_instr, context, funcs)
+      } else {
+        Ok(res)
       };
     }
     Node::While(expr, then_body, next_instr) => {
-      while eval(expr, context, funcs) == Node::Bool(true) {
+      loop {
+        match eval_expr(expr, context, funcs) {
+          Ok(Node::Bool(true)) => (),
+          Ok(_) => break,
+          Err(e) => return Err(e),
+        }
         context.push(Scope::new());
-        let res = eval(then_body, context, funcs);
-        match res {
-          Node::Empty => (),
-          _ => return res,
+        match eval(then_body, context, funcs) {
+          Ok(Node::Empty) => (),
+          res => return res,
         }
         context.pop();
       }
       eval_next_instr!(next_instr, context, funcs)
     }
+    // Dumps the local variables in scope, the same way the compiled program does
     Node::DebugContext(next_instr) => {
-      debug_print!(context);
+      output::write("$DEBUG_CONTEXT\n");
+      for var in context.locals() {
+        let value = match var.value {
+          Value::Closure(..) => "<closure>".to_string(),
+          ref value => String::from(value),
+        };
+        output::write(&format!("  {} = {}\n", var.name, value));
+      }
       eval_next_instr!(next_instr, context, funcs)
     }
-    Node::Print(expr, next_instr) => {
-      debug_print!(eval(expr, context, funcs));
+    Node::Print(pieces, args, newline, next_instr) => {
+      let mut text = match eval_format(pieces, args, context, funcs) {
+        Ok(text) => text,
+        Err(e) => return Err(e),
+      };
+      if *newline {
+        text.push('\n');
+      }
+      output::write(&text);
       eval_next_instr!(next_instr, context, funcs)
     }
-    Node::FuncCall(func, args, next_instr) => {
-      let ret_val = match funcs.get(func) {
-        Some(func) => func.execute(args, funcs, context),
-        None => panic!("No function {}", func),
-      };
-      match next_instr {
-        Some(instr) => eval(instr, context, funcs),
-        None => match ret_val {
-          Some(val) => match val {
-            Value::Int(n) => Node::Number(n),
-            Value::Bool(b) => Node::Bool(b),
-          },
-          None => Node::Empty,
-        },
+    Node::Check(condition, message, line, next_instr) => {
+      match eval_expr(condition, context, funcs) {
+        Ok(Node::Bool(true)) => eval_next_instr!(next_instr, context, funcs),
+        Ok(Node::Bool(false)) => Err(
+          RuntimeError::new(RuntimeErrorKind::ContractViolation, message.clone())
+            .at_line(*line),
+        ),
+        Err(e) => Err(e),
+        Ok(_) => Err(internal(
+          "Contract does not evaluate to a boolean".to_string(),
+        )),
+      }
+    }
+    Node::Panic(pieces, args, line, _) => {
+      match eval_format(pieces, args, context, funcs) {
+        Ok(message) => {
+          Err(RuntimeError::new(RuntimeErrorKind::Panic, message).at_line(*line))
+        }
+        Err(e) => Err(e),
       }
     }
+    Node::FuncCall(_, _, next_instr) => {
+      // The return value of a function call statement is discarded
+      if let Err(e) = eval_expr(node, context, funcs) {
+        return Err(e);
+      }
+      eval_next_instr!(next_instr, context, funcs)
+    }
     Node::Let(id, r#type, _, expr, next_instr) => {
-      let val: Value = eval(expr, context, funcs).try_into().unwrap();
+      let val = match eval_value(expr, context, funcs) {
+        Ok(val) => val,
+        Err(e) => return Err(e),
+      };
 
-      if let Some(r#type) = r#type {
-        let expr_type = (&val).into();
-        if *r#type != expr_type {
-          panic!(
-            "Specified type {} for variable {} does not match type {} of expression",
-            r#type.to_str(),
-            id,
-            expr_type.to_str()
-          );
-        }
+      if let Err(e) = check_let_type(r#type, id, &val, &context.current_func) {
+        return Err(e);
       }
       context.insert_variable(Variable {
         name: id.to_string(),
@@ -144,23 +808,90 @@ pub fn eval(
       });
       eval_next_instr!(next_instr, context, funcs)
     }
-    Node::Assign(id, expr, next_instr) => {
-      let val: Value = match eval(expr, context, funcs).try_into() {
+    // The variable holds no value until it is assigned one, it is not read before
+    Node::Declare(id, _, _, next_instr) => {
+      context.declare_variable(id);
+      eval_next_instr!(next_instr, context, funcs)
+    }
+    Node::LetTuple(names, r#type, expr, next_instr) => {
+      let val = match eval_value(expr, context, funcs) {
         Ok(val) => val,
-        Err(e) => panic!("Invalid expression in assign statement: {}", e),
+        Err(e) => return Err(e),
       };
-      match context.get_variable_mut(id) {
-        Some(var) => {
-          *var = Variable {
-            name: id.to_string(),
-            value: val,
-          }
+
+      let pattern = names
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<String>>()
+        .join(", ");
+      let name = format!("({})", pattern);
+      if let Err(e) = check_let_type(r#type, &name, &val, &context.current_func) {
+        return Err(e);
+      }
+      let values = match val {
+        Value::Tuple(values) => values,
+        _ => {
+          let message = "Destructuring a value that is not a tuple";
+          return Err(internal(message.to_string()));
         }
-        None => panic!("No variable {} found in context", id),
       };
+      for ((name, _), value) in names.iter().zip(values) {
+        context.insert_variable(Variable {
+          name: name.to_string(),
+          value: value,
+        });
+      }
+      eval_next_instr!(next_instr, context, funcs)
+    }
+    Node::Assign(id, expr, next_instr) => {
+      let val = match eval_value(expr, context, funcs) {
+        Ok(val) => val,
+        Err(e) => return Err(e),
+      };
+      if !context.assign_variable(id, val) {
+        return Err(undefined_variable(id));
+      }
+      eval_next_instr!(next_instr, context, funcs)
+    }
+    Node::IndexAssign(array, index, expr, next_instr) => {
+      // As in Rust the assigned value is evaluated before the place
+      let val = match eval_value(expr, context, funcs) {
+        Ok(val) => val,
+        Err(e) => return Err(e),
+      };
+      let (id, mut projections) = match eval_place(array, context, funcs) {
+        Ok(place) => place,
+        Err(e) => return Err(e),
+      };
+      match eval_index(index, context, funcs) {
+        Ok(index) => projections.push(Projection::Index(index)),
+        Err(e) => return Err(e),
+      }
+      if let Err(e) = assign_place(&id, projections, val, context) {
+        return Err(e);
+      }
+      eval_next_instr!(next_instr, context, funcs)
+    }
+    Node::FieldAssign(r#struct, field, expr, next_instr) => {
+      let val = match eval_value(expr, context, funcs) {
+        Ok(val) => val,
+        Err(e) => return Err(e),
+      };
+      let (id, mut projections) = match eval_place(r#struct, context, funcs) {
+        Ok(place) => place,
+        Err(e) => return Err(e),
+      };
+      projections.push(Projection::Field(field.clone()));
+      if let Err(e) = assign_place(&id, projections, val, context) {
+        return Err(e);
+      }
       eval_next_instr!(next_instr, context, funcs)
     }
-    Node::Return(expr, _) => eval(expr, context, funcs),
-    Node::Empty => Node::Empty,
+    Node::Return(expr, _) => eval_expr(expr, context, funcs),
+    Node::FuncDec(..) => Err(internal(
+      "Nested functions are hoisted when parsing".to_string(),
+    )),
+    Node::MacroCall(..) => Err(internal("Macros are expanded when parsing".to_string())),
+    Node::Empty => Ok(Node::Empty),
   }
 }
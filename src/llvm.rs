@@ -1,17 +1,25 @@
 use inkwell::builder::Builder;
 use inkwell::context::Context;
-use inkwell::execution_engine::JitFunction;
-use inkwell::module::Module;
+use inkwell::execution_engine::{ExecutionEngine, JitFunction};
+use inkwell::module::{Linkage, Module};
 use inkwell::OptimizationLevel;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::io::Write;
 
+use crate::errors::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::monomorphize::monomorphize;
+use crate::output;
 use crate::types::{
-  _type::Type, func::Func, node::Node, opcode::Opcode, program::Program,
+  _type::Type, context::is_introduced, enum_dec::EnumDec, func::Func, node::Node,
+  opcode::Opcode, pattern::Pattern, program::Program, struct_dec::StructDec,
+  value::Value,
 };
 use inkwell::basic_block::BasicBlock;
-use inkwell::types::BasicTypeEnum;
+use inkwell::targets::TargetData;
+use inkwell::types::{BasicTypeEnum, FunctionType, PointerType, StructType};
 use inkwell::values::{BasicValueEnum, FunctionValue, IntValue, PointerValue};
-use inkwell::IntPredicate;
+use inkwell::{AddressSpace, IntPredicate};
 
 /// Convenience type alias for the `sum` function.
 ///
@@ -19,6 +27,218 @@ use inkwell::IntPredicate;
 /// do `unsafe` operations internally.
 type MainFunc = unsafe extern "C" fn() -> i32;
 
+/// Name of the runtime function called on out of bounds array accesses.
+/// The dot makes sure that it can not collide with a function in the program.
+const PANIC_BOUNDS_CHECK: &str = "rt.panic_bounds_check";
+
+/// Called from JIT compiled code when an array index is out of bounds.
+/// Reports the panic with Rust's message and exits with Rust's panic exit code.
+extern "C" fn panic_bounds_check(len: i32, index: i32) {
+  report_panic(RuntimeError::index_out_of_bounds(len as usize, index));
+}
+
+/// Flushes what the program printed so far, so that it comes before the panic
+/// message and is not lost on exit.
+fn flush_stdout() {
+  if let Err(e) = std::io::stdout().flush() {
+    eprintln!("Failed to flush stdout: {}", e);
+  }
+}
+
+/// Exits with Rust's panic exit code, std::process::exit does not flush stdout.
+fn exit_panicking() -> ! {
+  flush_stdout();
+  std::process::exit(101);
+}
+
+/// Reports an error of the program the same way as the interpreter and exits.
+fn report_panic(mut error: RuntimeError) -> ! {
+  flush_stdout();
+  error.stack = STACK.with(|stack| stack.borrow().iter().rev().cloned().collect());
+  eprintln!("thread 'main' {}", error);
+  exit_panicking();
+}
+
+/// Names of the runtime functions keeping track of the functions being called,
+/// which a panic reports. Every function enters on its first block and leaves
+/// right before it returns.
+const ENTER: &str = "rt.enter";
+const LEAVE: &str = "rt.leave";
+
+thread_local! {
+  /// The functions being called, outermost first.
+  static STACK: RefCell<Vec<String>> = RefCell::new(vec![]);
+  /// The message of the panic being reported, written by the print functions.
+  static PANIC: RefCell<Option<String>> = RefCell::new(None);
+}
+
+extern "C" fn enter(ptr: *const u8, len: i32) {
+  let name = unsafe { str_from_raw(ptr, len) };
+  STACK.with(|stack| stack.borrow_mut().push(name.to_string()));
+}
+
+extern "C" fn leave() {
+  STACK.with(|stack| stack.borrow_mut().pop());
+}
+
+/// Names of the runtime functions reporting a panic of the program, from panic!, a
+/// failed assertion or a contract. The message is printed in between by the print
+/// functions, which collect it once the program panics.
+const PANIC_BEGIN: &str = "rt.panic_begin";
+const PANIC_END: &str = "rt.panic_end";
+
+extern "C" fn panic_begin() {
+  PANIC.with(|panic| *panic.borrow_mut() = Some(String::new()));
+}
+
+extern "C" fn panic_end(line: i32) {
+  let message = PANIC.with(|panic| panic.borrow_mut().take().unwrap_or_default());
+  report_panic(
+    RuntimeError::new(RuntimeErrorKind::Panic, message).at_line(line as usize),
+  );
+}
+
+/// Writes the output of the print functions, to the panic message while panicking.
+fn output(s: String) {
+  PANIC.with(|panic| match panic.borrow_mut().as_mut() {
+    Some(message) => message.push_str(&s),
+    None => output::write(&s),
+  })
+}
+
+/// Names of the runtime functions printing values without a newline. They format
+/// the values the same way as the interpreter.
+const PRINT_INT: &str = "rt.print_int";
+const PRINT_BOOL: &str = "rt.print_bool";
+const PRINT_CHAR: &str = "rt.print_char";
+const PRINT_STR: &str = "rt.print_str";
+
+/// Name of the runtime function comparing the contents of two strings.
+const STR_EQ: &str = "rt.str_eq";
+
+extern "C" fn print_int(n: i32) {
+  output(String::from(&Value::Int(n)));
+}
+
+extern "C" fn print_bool(b: i32) {
+  output(String::from(&Value::Bool(b != 0)));
+}
+
+extern "C" fn print_char(c: u32) {
+  output(String::from(&Value::Char(std::char::from_u32(c).unwrap())));
+}
+
+/// Strings are passed as a pointer to their bytes along with their length.
+unsafe fn str_from_raw<'a>(ptr: *const u8, len: i32) -> &'a str {
+  std::str::from_utf8_unchecked(std::slice::from_raw_parts(ptr, len as usize))
+}
+
+extern "C" fn print_str(ptr: *const u8, len: i32) {
+  let s = unsafe { str_from_raw(ptr, len) };
+  output(String::from(&Value::Str(s.to_string())));
+}
+
+extern "C" fn str_eq(
+  left: *const u8,
+  left_len: i32,
+  right: *const u8,
+  right_len: i32,
+) -> i32 {
+  unsafe { (str_from_raw(left, left_len) == str_from_raw(right, right_len)) as i32 }
+}
+
+/// Names of the runtime functions dumping the local variables in scope for
+/// $DEBUG_CONTEXT, the value of each variable is printed by the print functions.
+const DEBUG_CONTEXT: &str = "rt.debug_context";
+const DEBUG_LOCAL: &str = "rt.debug_local";
+
+extern "C" fn debug_context() {
+  output("$DEBUG_CONTEXT\n".to_string());
+}
+
+extern "C" fn debug_local(ptr: *const u8, len: i32) {
+  let name = unsafe { str_from_raw(ptr, len) };
+  output(format!("  {} = ", name));
+}
+
+/// Name under which the flag recording whether a variable declared without a value
+/// has been assigned one is kept, no variable can have it.
+fn assigned_flag(id: &str) -> String {
+  format!("{}#assigned", id)
+}
+
+/// Returns the integer literals of the patterns of a match, each along with the
+/// first arm whose pattern matches it. None unless all patterns are made out of
+/// integer literals, ranges and wildcards.
+fn switch_cases(arms: &Vec<(Pattern, Option<Node>, Node)>) -> Option<Vec<(i32, usize)>> {
+  let mut literals = vec![];
+  for (pattern, _, _) in arms.iter() {
+    match pattern {
+      Pattern::Int(n) => literals.push(*n),
+      Pattern::Or(alternatives) => {
+        for alternative in alternatives.iter() {
+          if let Pattern::Int(n) = alternative {
+            literals.push(*n);
+          }
+        }
+      }
+      _ => (),
+    }
+  }
+  if literals.len() == 0 {
+    return None;
+  }
+
+  let mut cases = vec![];
+  for n in literals {
+    if cases.iter().any(|(case, _)| *case == n) {
+      continue;
+    }
+    for (i, (pattern, _, _)) in arms.iter().enumerate() {
+      match int_pattern_matches(pattern, n) {
+        Some(true) => {
+          cases.push((n, i));
+          break;
+        }
+        Some(false) => (),
+        None => return None,
+      }
+    }
+  }
+  Some(cases)
+}
+
+/// Whether an integer pattern matches `n`, None for patterns that are not made out
+/// of integer literals, ranges and wildcards, or that bind variables inside
+/// alternatives.
+fn int_pattern_matches(pattern: &Pattern, n: i32) -> Option<bool> {
+  match pattern {
+    Pattern::Wildcard | Pattern::Binding(_) => Some(true),
+    Pattern::Int(m) => Some(*m == n),
+    Pattern::Range(start, end) => Some(*start <= n && n <= *end),
+    Pattern::Or(alternatives) => {
+      let mut matches = false;
+      for alternative in alternatives.iter() {
+        match alternative {
+          Pattern::Binding(_) => return None,
+          alternative => matches |= int_pattern_matches(alternative, n)?,
+        }
+      }
+      Some(matches)
+    }
+    _ => None,
+  }
+}
+
+/// Whether a pattern only matches the literals it is made out of.
+fn literals_only(pattern: &Pattern) -> bool {
+  match pattern {
+    Pattern::Int(_) => true,
+    Pattern::Or(alternatives) => alternatives.iter().all(literals_only),
+    _ => false,
+  }
+}
+
 /// Compiler holds the LLVM state for the compilation
 pub struct Compiler {
   context: Context,
@@ -29,631 +249,2434 @@ pub struct Compiler {
   // i.e. the latest variables will be at the end of the vector.
   // This enables shadowing.
   variables: Vec<HashMap<String, PointerValue>>,
+
+  // The LLVM globals holding the constants and statics, along with their values
+  // computed at compile time
+  globals: HashMap<String, (PointerValue, Value)>,
+
+  // Struct declarations along with the named LLVM struct types they lower to
+  structs: HashMap<String, (StructDec, StructType)>,
+
+  // Enum declarations along with the named LLVM struct types they lower to, a tag
+  // followed by a payload large enough to hold the fields of any variant
+  enums: HashMap<String, (EnumDec, StructType)>,
+
+  // Chars are wrapped in a named struct to tell them apart from integers, strings
+  // are a pointer to their bytes along with their length
+  char_type: StructType,
+  str_type: StructType,
+
+  // Whether the contracts of the program are checked, they are left out otherwise
+  pub check_contracts: bool,
 }
 
 impl Compiler {
   pub fn new() -> Self {
     let context = Context::create();
+    let char_type = context.opaque_struct_type("char");
+    char_type.set_body(&[context.i32_type().into()], false);
+    let str_type = context.opaque_struct_type("str");
+    let bytes_type = context.i8_type().ptr_type(AddressSpace::Generic);
+    str_type.set_body(&[bytes_type.into(), context.i32_type().into()], false);
     Compiler {
       builder: context.create_builder(),
       module: context.create_module("main"),
       context: context,
       variables: vec![],
+      globals: HashMap::new(),
+      structs: HashMap::new(),
+      enums: HashMap::new(),
+      char_type: char_type,
+      str_type: str_type,
+      check_contracts: false,
     }
   }
 
+  fn find_variable(&self, id: &str) -> Option<&PointerValue> {
+    self.variables.iter().rev().find_map(|block| block.get(id))
+  }
+
+  /// Looks up the flag of a local variable declared without a value, which is kept
+  /// in the same block as the variable.
+  fn find_assigned_flag(&self, id: &str) -> Option<&PointerValue> {
+    self
+      .variables
+      .iter()
+      .rev()
+      .find(|block| block.contains_key(id))
+      .and_then(|block| block.get(&assigned_flag(id)))
+  }
+
+  /// Looks up a local variable, or else a constant or static.
   fn get_variable(&self, id: &str) -> &PointerValue {
-    for block in self.variables.iter().rev() {
-      if let Some(var) = block.get(id) {
-        return var;
-      }
+    match self.find_variable(id) {
+      Some(var) => var,
+      None if self.globals.contains_key(id) => &self.globals[id].0,
+      None => panic!(
+        "Could not find a matching variable, {} in {:?}",
+        id, self.variables
+      ),
     }
-    panic!(
-      "Could not find a matching variable, {} in {:?}",
-      id, self.variables
-    );
   }
 
-  fn compile_expr(&self, expr: &Node, funcs: &HashMap<String, Func>) -> IntValue {
-    match expr {
-      Node::Number(n) => self.context.i32_type().const_int(*n as u64, false),
-      Node::Var(name) => {
-        let var = self.get_variable(&name);
-        self.builder.build_load(*var, &name).into_int_value()
-      }
-      Node::Bool(b) => self
-        .context
-        .i32_type()
-        .const_int(if *b { 1 } else { 0 }, false),
-      Node::Op(left, op, right) => {
-        let left_val = self.compile_expr(left, funcs);
-        let right_val = self.compile_expr(right, funcs);
-        match op {
-          Opcode::Add => self.builder.build_int_add(left_val, right_val, "add"),
-          Opcode::Sub => self.builder.build_int_sub(left_val, right_val, "sub"),
-          Opcode::Mul => self.builder.build_int_mul(left_val, right_val, "mul"),
-          Opcode::Div => self
-            .builder
-            .build_int_signed_div(left_val, right_val, "div"),
-          Opcode::Eq => {
-            self
-              .builder
-              .build_int_compare(IntPredicate::EQ, left_val, right_val, "eq")
-          }
-          Opcode::Neq => {
-            self
-              .builder
-              .build_int_compare(IntPredicate::NE, left_val, right_val, "neq")
-          }
-          Opcode::Geq => {
-            self
-              .builder
-              .build_int_compare(IntPredicate::SGE, left_val, right_val, "geq")
-          }
-          Opcode::Leq => {
-            self
-              .builder
-              .build_int_compare(IntPredicate::SLE, left_val, right_val, "leq")
-          }
-          Opcode::Gneq => {
-            self
-              .builder
-              .build_int_compare(IntPredicate::SGT, left_val, right_val, "gneq")
-          }
-          Opcode::Lneq => {
-            self
-              .builder
-              .build_int_compare(IntPredicate::SLT, left_val, right_val, "lneq")
-          }
-          Opcode::And => self.builder.build_and(left_val, right_val, "and"),
-          Opcode::Or => self.builder.build_or(left_val, right_val, "or"),
-        }
-      }
-      Node::FuncCall(func_name, args, _) => {
-        let function = self.module.get_function(func_name).unwrap();
+  /// Unit is lowered to an empty struct, i.e. a zero sized type.
+  fn unit_type(&self) -> StructType {
+    self.context.struct_type(&[], false)
+  }
 
-        //Compile the arguments
-        let args: Vec<BasicValueEnum> = args
-          .iter()
-          .map(|a| self.compile_expr(a, funcs).into())
-          .collect();
+  fn unit_value(&self) -> BasicValueEnum {
+    self.unit_type().const_named_struct(&[]).into()
+  }
 
-        // Build the call and return the result
-        let call = self.builder.build_call(function, &args, func_name);
-        *call.try_as_basic_value().left().unwrap().as_int_value()
+  fn llvm_type(&self, r#type: &Type) -> BasicTypeEnum {
+    match r#type {
+      Type::Int => self.context.i32_type().into(),
+      Type::Bool => self.context.bool_type().into(),
+      Type::Char => self.char_type.into(),
+      Type::Str => self.str_type.into(),
+      Type::Unit => self.unit_type().into(),
+      Type::Array(r#type, len) => self.llvm_type(r#type).array_type(*len as u32).into(),
+      // Tuples are anonymous structs, returned from functions by value
+      Type::Tuple(types) => {
+        let types: Vec<BasicTypeEnum> =
+          types.iter().map(|r#type| self.llvm_type(r#type)).collect();
+        self.context.struct_type(&types, false).into()
       }
-      _ => unreachable!("Cannot compile node {:#?} in expression", expr),
+      Type::Named(name) => match self.structs.get(name) {
+        Some((_, struct_type)) => (*struct_type).into(),
+        None => self.enums[name].1.into(),
+      },
+      Type::Func(params, ret_type) => self.closure_type(params, ret_type).into(),
+      Type::Alias(_, r#type) => self.llvm_type(r#type),
     }
   }
 
-  pub fn compile_program(&mut self, program: &Program) -> Option<JitFunction<MainFunc>> {
-    let execution_engine = self
-      .module
-      .create_jit_execution_engine(OptimizationLevel::None)
-      .unwrap();
-
-    // Add all functions to the module before compiling
-    for (_, func) in program.funcs.iter() {
-      // Construct a vector of all the parameter types
-      let param_types: Vec<BasicTypeEnum> = func
-        .params
-        .iter()
-        .map(|param| match param._type {
-          Type::Int => self.context.i32_type().into(),
-          Type::Bool => self.context.bool_type().into(),
-        })
-        .collect();
-
-      // Construct the function return type
-      let fn_type = match func.ret_type {
-        Some(ref r#type) => match r#type {
-          Type::Int => self.context.i32_type().fn_type(&param_types, false),
-          Type::Bool => self.context.bool_type().fn_type(&param_types, false),
-        },
-
-        None => self.context.void_type().fn_type(&[], false),
-      };
-      let function = self.module.add_function(&func.name, fn_type, None);
-      self.context.append_basic_block(&function, "entry");
-    }
-
-    // Compile the functions
-    for (_, func) in program.funcs.iter() {
-      let function = self.module.get_function(&func.name).unwrap();
-      self.compile_func(&function, &func, &program.funcs);
+  /// An undefined value of the type, the globals hold one until main initializes
+  /// them.
+  fn undef_value(&self, r#type: BasicTypeEnum) -> BasicValueEnum {
+    match r#type {
+      BasicTypeEnum::IntType(r#type) => r#type.get_undef().into(),
+      BasicTypeEnum::StructType(r#type) => r#type.get_undef().into(),
+      BasicTypeEnum::ArrayType(r#type) => r#type.get_undef().into(),
+      BasicTypeEnum::PointerType(r#type) => r#type.get_undef().into(),
+      _ => unreachable!("No type of the language lowers to {:?}", r#type),
     }
+  }
 
-    self.module.print_to_stderr(); //Uncomment this to get the llvm-ir
-    let temp = unsafe { execution_engine.get_function("main").ok() };
-    return temp;
+  /// The environment of a closure is passed to its function as a pointer to bytes.
+  fn env_type(&self) -> PointerType {
+    self.context.i8_type().ptr_type(AddressSpace::Generic)
   }
 
-  fn compile_func(
-    &mut self,
-    function: &FunctionValue,
-    func_dec: &Func,
-    funcs: &HashMap<String, Func>,
-  ) {
-    // Push a new variable scope
-    self.variables.push(HashMap::new());
+  /// Closures, and functions used as values, are lowered to a pointer to a function
+  /// taking the environment as its first parameter along with the environment.
+  fn closure_type(&self, params: &Vec<Type>, ret_type: &Type) -> StructType {
+    let mut param_types: Vec<BasicTypeEnum> = vec![self.env_type().into()];
+    param_types.extend(params.iter().map(|r#type| self.llvm_type(r#type)));
+    let fn_type = match ret_type {
+      Type::Unit => self.context.void_type().fn_type(&param_types, false),
+      r#type => self.llvm_type(r#type).fn_type(&param_types, false),
+    };
+    self.context.struct_type(
+      &[
+        fn_type.ptr_type(AddressSpace::Generic).into(),
+        self.env_type().into(),
+      ],
+      false,
+    )
+  }
 
-    let func_block = function.get_first_basic_block().unwrap();
-    // Build store instruction for the arguments
-    for (i, param) in func_dec.params.iter().enumerate() {
-      let arg = function.get_nth_param(i as u32).unwrap();
+  /// The fields of a variant are stored in the payload of an enum as a tuple.
+  fn payload_type(&self, r#enum: &str, variant: &str) -> StructType {
+    let types: Vec<BasicTypeEnum> = match self.enums[r#enum].0.get_variant(variant) {
+      Some((_, types)) => types.iter().map(|r#type| self.llvm_type(r#type)).collect(),
+      None => unreachable!("No variant {} found on enum {}", variant, r#enum),
+    };
+    self.context.struct_type(&types, false)
+  }
 
-      let alloca = self.create_entry_block_alloca(&func_block, &param.name);
-      self.builder.position_at_end(&func_block);
-      self.builder.build_store(alloca, arg);
+  /// Sets the bodies of the named types contained in `r#type`.
+  fn define_types_in(
+    &self,
+    r#type: &Type,
+    target_data: &TargetData,
+    defined: &mut Vec<String>,
+  ) {
+    match r#type {
+      Type::Array(r#type, _) => self.define_types_in(r#type, target_data, defined),
+      Type::Tuple(types) => {
+        for r#type in types.iter() {
+          self.define_types_in(r#type, target_data, defined);
+        }
+      }
+      Type::Named(name) => self.define_named_type(name, target_data, defined),
+      _ => (),
     }
+  }
 
-    self.compile_block(&func_dec.body_start, &func_block, function, funcs);
+  /// Sets the body of a struct or enum type after the bodies of the types it
+  /// contains, as the size of an enum depends on the sizes of its variants.
+  fn define_named_type(
+    &self,
+    name: &str,
+    target_data: &TargetData,
+    defined: &mut Vec<String>,
+  ) {
+    if defined.iter().any(|defined| defined == name) {
+      return;
+    }
+    defined.push(name.to_string());
 
-    //If the function is of type void we still need to make sure to build a return
-    if let None = func_dec.ret_type {
-      let default_return_value = self.context.i32_type().const_int(0, false);
-      //Main should always return 0
-      self.builder.build_return(if func_dec.name == "main" {
-        Some(&default_return_value)
-      } else {
-        None
-      });
+    match self.structs.get(name) {
+      Some((r#struct, struct_type)) => {
+        for (_, r#type) in r#struct.fields.iter() {
+          self.define_types_in(r#type, target_data, defined);
+        }
+        let fields: Vec<BasicTypeEnum> = r#struct
+          .fields
+          .iter()
+          .map(|(_, r#type)| self.llvm_type(r#type))
+          .collect();
+        struct_type.set_body(&fields, false);
+      }
+      None => {
+        let (r#enum, enum_type) = &self.enums[name];
+        for r#type in r#enum.variants.iter().flat_map(|(_, types)| types) {
+          self.define_types_in(r#type, target_data, defined);
+        }
+        // The payload is made out of i64s so that it is aligned for any field
+        let size = r#enum
+          .variants
+          .iter()
+          .map(|(variant, _)| target_data.get_abi_size(&self.payload_type(name, variant)))
+          .max()
+          .unwrap_or(0);
+        let payload = self.context.i64_type().array_type(((size + 7) / 8) as u32);
+        enum_type.set_body(&[self.context.i32_type().into(), payload.into()], false);
+      }
     }
+  }
 
-    // Pop the scope
-    self.variables.pop();
+  /// Returns the position of a field in the named struct type.
+  fn field_index(&self, struct_type: StructType, field: &str) -> u32 {
+    let name = struct_type.get_name().unwrap().to_str().unwrap();
+    match self.structs[name].0.get_field(field) {
+      Some((index, _)) => index as u32,
+      None => unreachable!("No field {} found on struct {}", field, name),
+    }
   }
 
-  /// Creates a new stack allocation instruction in the entry block of the function.
-  fn create_entry_block_alloca(
-    &mut self,
-    block: &BasicBlock,
-    name: &str,
-  ) -> PointerValue {
-    let builder = self.context.create_builder();
+  /// Builds a string value referring to a global constant holding its bytes.
+  fn build_str(&self, s: &str) -> BasicValueEnum {
+    let bytes = self
+      .builder
+      .build_global_string_ptr(s, "str")
+      .as_pointer_value();
+    let len = self.context.i32_type().const_int(s.len() as u64, false);
+    let value = self.str_type.get_undef();
+    let value = self
+      .builder
+      .build_insert_value(value, bytes, 0, "str")
+      .unwrap()
+      .into_struct_value();
+    self
+      .builder
+      .build_insert_value(value, len, 1, "str")
+      .unwrap()
+      .into_struct_value()
+      .into()
+  }
 
-    match block.get_first_instruction() {
-      Some(first_instr) => builder.position_before(&first_instr),
-      None => builder.position_at_end(&block),
+  /// Returns the integer representation of a scalar, chars are unwrapped.
+  fn scalar_value(&self, value: BasicValueEnum) -> IntValue {
+    match value {
+      BasicValueEnum::StructValue(c) => self
+        .builder
+        .build_extract_value(c, 0, "char")
+        .unwrap()
+        .into_int_value(),
+      value => value.into_int_value(),
     }
-    let alloca = builder.build_alloca(self.context.i32_type(), name);
-    self
-      .variables
-      .iter_mut()
-      .last()
+  }
+
+  /// Splits a string value into the pointer to its bytes and its length.
+  fn str_parts(&self, s: BasicValueEnum) -> Vec<BasicValueEnum> {
+    let s = s.into_struct_value();
+    vec![
+      self.builder.build_extract_value(s, 0, "bytes").unwrap(),
+      self.builder.build_extract_value(s, 1, "len").unwrap(),
+    ]
+  }
+
+  /// Compares the contents of two strings for equality.
+  fn build_str_compare(
+    &self,
+    op: &Opcode,
+    left: BasicValueEnum,
+    right: BasicValueEnum,
+  ) -> IntValue {
+    let mut args = self.str_parts(left);
+    args.append(&mut self.str_parts(right));
+    let str_eq = self.module.get_function(STR_EQ).unwrap();
+    let equal = self
+      .builder
+      .build_call(str_eq, &args, "str_eq")
+      .try_as_basic_value()
+      .left()
       .unwrap()
-      .insert(name.to_string(), alloca);
-    alloca
+      .into_int_value();
+    let predicate = match op {
+      Opcode::Eq => IntPredicate::NE,
+      Opcode::Neq => IntPredicate::EQ,
+      _ => unreachable!("Strings can only be compared for equality"),
+    };
+    let zero = self.context.i32_type().const_int(0, false);
+    self
+      .builder
+      .build_int_compare(predicate, equal, zero, "str_cmp")
   }
 
-  fn compile_node(
-    &mut self,
-    node: &Node,
-    func: &FunctionValue,
-    funcs: &HashMap<String, Func>,
-    block: &BasicBlock,
-  ) {
-    match node {
-      Node::Return(expr, _) => {
-        let expr_val = self.compile_expr(expr, funcs);
-        self.builder.build_return(Some(&expr_val));
+  /// Prints a scalar or string through the runtime.
+  fn build_print(&self, value: BasicValueEnum) {
+    let (function, args) = if value.get_type() == self.str_type.into() {
+      (PRINT_STR, self.str_parts(value))
+    } else if value.get_type() == self.char_type.into() {
+      (PRINT_CHAR, vec![self.scalar_value(value).into()])
+    } else if value.get_type() == self.context.bool_type().into() {
+      // Booleans are widened as the runtime takes them as integers
+      let b = self.builder.build_int_z_extend(
+        value.into_int_value(),
+        self.context.i32_type(),
+        "bool",
+      );
+      (PRINT_BOOL, vec![b.into()])
+    } else {
+      (PRINT_INT, vec![value])
+    };
+    let function = self.module.get_function(function).unwrap();
+    self.builder.build_call(function, &args, "print");
+  }
+
+  /// Prints a value of any type the way the interpreter formats it. Closures are
+  /// printed as <closure>, their code is not known at this point.
+  fn build_debug_print(&self, value: BasicValueEnum) {
+    let print = |s: &str| self.build_print(self.build_str(s));
+    match value.get_type() {
+      BasicTypeEnum::ArrayType(array_type) => {
+        print("[");
+        for i in 0..array_type.len() {
+          if i > 0 {
+            print(", ");
+          }
+          let element = self
+            .builder
+            .build_extract_value(value.into_array_value(), i, "element")
+            .unwrap();
+          self.build_debug_print(element);
+        }
+        print("]");
       }
-      Node::Let(id, _, _, expr, _) => {
-        let alloca = self.create_entry_block_alloca(block, id);
-        let expr_val = self.compile_expr(expr, funcs);
-        self.builder.build_store(alloca, expr_val);
+      BasicTypeEnum::StructType(struct_type)
+        if struct_type == self.char_type || struct_type == self.str_type =>
+      {
+        self.build_print(value)
       }
-      Node::If(condition, then_body, else_body, _) => {
-        match else_body {
-          Some(else_body) => {
-            self.compile_if_else(condition, then_body, else_body, func, funcs)
+      BasicTypeEnum::StructType(struct_type) => {
+        let name = struct_type.get_name().map(|name| name.to_str().unwrap());
+        match (name, struct_type.get_field_type_at_index(0)) {
+          (Some(name), _) if self.structs.contains_key(name) => {
+            let r#struct = &self.structs[name].0;
+            print(&format!("{} {{ ", name));
+            for (i, (field, _)) in r#struct.fields.iter().enumerate() {
+              print(&format!("{}{}: ", if i > 0 { ", " } else { "" }, field));
+              let field = self
+                .builder
+                .build_extract_value(value.into_struct_value(), i as u32, field)
+                .unwrap();
+              self.build_debug_print(field);
+            }
+            print(" }");
           }
-          None => self.compile_if(condition, then_body, func, funcs),
-        };
+          (Some(name), _) => self.build_variant_debug_print(name, value),
+          (None, None) => print("()"),
+          (None, Some(BasicTypeEnum::PointerType(_))) => print("<closure>"),
+          (None, Some(_)) => {
+            let len = struct_type.count_fields();
+            print("(");
+            for i in 0..len {
+              if i > 0 {
+                print(", ");
+              }
+              let element = self
+                .builder
+                .build_extract_value(value.into_struct_value(), i, "element")
+                .unwrap();
+              self.build_debug_print(element);
+            }
+            print(if len == 1 { ",)" } else { ")" });
+          }
+        }
       }
-      Node::While(condition, then_body, _) => {
-        self.compile_while(condition, then_body, func, funcs);
+      _ => self.build_print(value),
+    }
+  }
+
+  /// Prints an enum value by switching on its tag to the variant it holds.
+  fn build_variant_debug_print(&self, r#enum: &str, value: BasicValueEnum) {
+    let function = self.current_function();
+    let enum_ptr = self.build_temporary(value.get_type());
+    self.builder.build_store(enum_ptr, value);
+    let tag_ptr = unsafe { self.builder.build_struct_gep(enum_ptr, 0, "tag") };
+    let tag = self.builder.build_load(tag_ptr, "tag").into_int_value();
+
+    let enum_dec = &self.enums[r#enum].0;
+    let cont_block = self.context.append_basic_block(&function, "cont");
+    let variant_blocks: Vec<BasicBlock> = enum_dec
+      .variants
+      .iter()
+      .map(|(variant, _)| self.context.append_basic_block(&function, variant))
+      .collect();
+    let cases: Vec<(IntValue, &BasicBlock)> = variant_blocks
+      .iter()
+      .enumerate()
+      .map(|(i, block)| (self.context.i32_type().const_int(i as u64, false), block))
+      .collect();
+    self.builder.build_switch(tag, &cont_block, &cases);
+
+    for ((variant, types), block) in enum_dec.variants.iter().zip(variant_blocks.iter()) {
+      self.builder.position_at_end(block);
+      let name = self.build_str(&format!("{}::{}", r#enum, variant));
+      self.build_print(name);
+      if types.len() != 0 {
+        let payload_ptr = self.variant_payload_ptr(enum_ptr, r#enum, variant);
+        let payload = self
+          .builder
+          .build_load(payload_ptr, "payload")
+          .into_struct_value();
+        self.build_print(self.build_str("("));
+        for i in 0..types.len() {
+          if i > 0 {
+            self.build_print(self.build_str(", "));
+          }
+          let field = self
+            .builder
+            .build_extract_value(payload, i as u32, "field")
+            .unwrap();
+          self.build_debug_print(field);
+        }
+        self.build_print(self.build_str(")"));
       }
-      Node::Assign(variable, expr, _) => {
-        let variable = self.get_variable(variable);
-        let expr = self.compile_expr(expr, funcs);
-        self.builder.build_store(*variable, expr);
+      self.builder.build_unconditional_branch(&cont_block);
+    }
+    self.builder.position_at_end(&cont_block);
+  }
+
+  /// Dumps the local variables in scope through the runtime, sorted by name the
+  /// same way as the interpreter.
+  fn compile_debug_context(&self) {
+    let mut locals: Vec<(&String, &PointerValue)> = vec![];
+    for (name, pointer) in self.variables.iter().rev().flatten() {
+      if !is_introduced(name) && !locals.iter().any(|(local, _)| *local == name) {
+        locals.push((name, pointer));
       }
-      Node::FuncCall(func_name, args, _) => {
-        // Compile the arguments
-        let args: Vec<BasicValueEnum> = args
-          .iter()
-          .map(|a| self.compile_expr(a, funcs).into())
-          .collect();
-
-        let func = self
-          .module
-          .get_function(func_name)
-          .expect(&format!("Could not find function {}", func_name));
-
-        self.builder.build_call(func, &args, func_name);
+    }
+    locals.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let debug_context = self.module.get_function(DEBUG_CONTEXT).unwrap();
+    self.builder.build_call(debug_context, &[], "debug_context");
+    let debug_local = self.module.get_function(DEBUG_LOCAL).unwrap();
+    for (name, pointer) in locals {
+      // A variable declared without a value is printed once it is assigned one
+      let cont_block = match self.find_assigned_flag(name) {
+        Some(assigned) => {
+          let function = self
+            .builder
+            .get_insert_block()
+            .unwrap()
+            .get_parent()
+            .unwrap();
+          let assigned = self.builder.build_load(*assigned, "assigned");
+          let print_block = self.context.append_basic_block(&function, "print_local");
+          let cont_block = self.context.append_basic_block(&function, "cont");
+          self.builder.build_conditional_branch(
+            assigned.into_int_value(),
+            &print_block,
+            &cont_block,
+          );
+          self.builder.position_at_end(&print_block);
+          Some(cont_block)
+        }
+        None => None,
+      };
+      let name = self.str_parts(self.build_str(name));
+      self.builder.build_call(debug_local, &name, "debug_local");
+      let value = self.builder.build_load(*pointer, "local");
+      self.build_debug_print(value);
+      self.build_print(self.build_str("\n"));
+      if let Some(cont_block) = cont_block {
+        self.builder.build_unconditional_branch(&cont_block);
+        self.builder.position_at_end(&cont_block);
       }
-      Node::Empty => (),
-      _ => unreachable!("Cannot compile node {:#?}", node),
-    };
+    }
   }
 
-  fn compile_if_else(
-    &mut self,
-    condition: &Node,
-    then_body: &Node,
-    else_body: &Node,
-    func: &FunctionValue,
-    funcs: &HashMap<String, Func>,
-  ) {
-    let cond = self.compile_expr(condition, funcs);
+  /// Builds an array value out of its elements, there is always at least one.
+  fn build_array(&self, elements: Vec<BasicValueEnum>) -> BasicValueEnum {
+    let array_type = elements[0].get_type().array_type(elements.len() as u32);
+    let mut array = array_type.get_undef();
+    for (i, element) in elements.iter().enumerate() {
+      array = self
+        .builder
+        .build_insert_value(array, *element, i as u32, "array")
+        .unwrap()
+        .into_array_value();
+    }
+    array.into()
+  }
 
-    // build branch
-    let then_block = self.context.append_basic_block(&func, "then");
-    let else_block = self.context.append_basic_block(&func, "else");
-    let cont_block = self.context.append_basic_block(&func, "cont");
+  /// Builds a tuple value as an anonymous struct holding its elements.
+  fn build_tuple(&self, elements: Vec<BasicValueEnum>) -> BasicValueEnum {
+    let types: Vec<BasicTypeEnum> =
+      elements.iter().map(|element| element.get_type()).collect();
+    let mut tuple = self.context.struct_type(&types, false).get_undef();
+    for (i, element) in elements.iter().enumerate() {
+      tuple = self
+        .builder
+        .build_insert_value(tuple, *element, i as u32, "tuple")
+        .unwrap()
+        .into_struct_value();
+    }
+    tuple.into()
+  }
 
+  /// Branches to the runtime panic routine if `index` is out of bounds for an array
+  /// of length `len`. Negative indices are caught by the unsigned comparison.
+  fn build_bounds_check(&self, len: u32, index: IntValue) {
+    let function = self
+      .builder
+      .get_insert_block()
+      .unwrap()
+      .get_parent()
+      .unwrap();
+    let len = self.context.i32_type().const_int(len as u64, false);
+    let in_bounds =
+      self
+        .builder
+        .build_int_compare(IntPredicate::ULT, index, len, "in_bounds");
+
+    let panic_block = self.context.append_basic_block(&function, "out_of_bounds");
+    let cont_block = self.context.append_basic_block(&function, "cont");
     self
       .builder
-      .build_conditional_branch(cond, &then_block, &else_block);
+      .build_conditional_branch(in_bounds, &cont_block, &panic_block);
 
-    // build then block
-    self.compile_block(then_body, &then_block, func, funcs);
-    self.builder.build_unconditional_branch(&cont_block);
-
-    // build else block
-    self.compile_block(else_body, &else_block, func, funcs);
-    self.builder.build_unconditional_branch(&cont_block);
+    self.builder.position_at_end(&panic_block);
+    let panic = self.module.get_function(PANIC_BOUNDS_CHECK).unwrap();
+    self
+      .builder
+      .build_call(panic, &[len.into(), index.into()], "panic");
+    self.builder.build_unreachable();
 
-    // emit merge block
     self.builder.position_at_end(&cont_block);
+  }
 
-    self.builder.build_phi(self.context.i32_type(), "phi");
+  /// Builds a pointer to the element `index` of `array`, guarded by a bounds check.
+  fn compile_element_ptr(
+    &mut self,
+    array: &Node,
+    index: &Node,
+    funcs: &HashMap<String, Func>,
+  ) -> PointerValue {
+    let array_ptr = self.compile_place(array, funcs);
+    let len = array_ptr
+      .get_type()
+      .get_element_type()
+      .into_array_type()
+      .len();
+    let index = self.compile_expr(index, funcs).into_int_value();
+    self.build_bounds_check(len, index);
+
+    let zero = self.context.i32_type().const_int(0, false);
+    unsafe {
+      self
+        .builder
+        .build_in_bounds_gep(array_ptr, &[zero, index], "element")
+    }
   }
-  fn compile_if(
+
+  /// Builds a pointer to the field `field` of the struct `r#struct`.
+  fn compile_field_ptr(
     &mut self,
-    condition: &Node,
-    then_body: &Node,
-    parent_block: &FunctionValue,
+    r#struct: &Node,
+    field: &str,
     funcs: &HashMap<String, Func>,
-  ) {
-    let cond = self.compile_expr(condition, funcs);
+  ) -> PointerValue {
+    let struct_ptr = self.compile_place(r#struct, funcs);
+    let struct_type = struct_ptr.get_type().get_element_type().into_struct_type();
+    let index = self.field_index(struct_type, field);
+    unsafe { self.builder.build_struct_gep(struct_ptr, index, field) }
+  }
 
-    // build branch
-    let then_block = self.context.append_basic_block(parent_block, "then");
-    let cont_block = self.context.append_basic_block(parent_block, "cont");
+  /// Returns a pointer to the memory holding the value of `node`. Variables and
+  /// array elements are referred to in place, other values are stored in a
+  /// temporary.
+  fn compile_place(
+    &mut self,
+    node: &Node,
+    funcs: &HashMap<String, Func>,
+  ) -> PointerValue {
+    match node {
+      Node::Var(name) => *self.get_variable(name),
+      Node::Index(array, index) => self.compile_element_ptr(array, index, funcs),
+      Node::Field(r#struct, field) => self.compile_field_ptr(r#struct, field, funcs),
+      _ => {
+        let value = self.compile_expr(node, funcs);
+        let tmp = self.build_temporary(value.get_type());
+        self.builder.build_store(tmp, value);
+        tmp
+      }
+    }
+  }
 
+  /// Returns the function the builder is positioned in.
+  fn current_function(&self) -> FunctionValue {
     self
       .builder
-      .build_conditional_branch(cond, &then_block, &cont_block);
+      .get_insert_block()
+      .unwrap()
+      .get_parent()
+      .unwrap()
+  }
 
-    // build then block
-    self.builder.position_at_end(&then_block);
-    self.compile_block(then_body, &then_block, parent_block, funcs);
-    self.builder.build_unconditional_branch(&cont_block);
+  /// Allocates a temporary in the entry block of the current function.
+  fn build_temporary(&self, r#type: BasicTypeEnum) -> PointerValue {
+    let function = self.current_function();
+    self.build_alloca_at_start(&function.get_first_basic_block().unwrap(), "tmp", r#type)
+  }
+
+  /// Builds a pointer to the fields of `variant` in the enum value at `enum_ptr`.
+  fn variant_payload_ptr(
+    &self,
+    enum_ptr: PointerValue,
+    r#enum: &str,
+    variant: &str,
+  ) -> PointerValue {
+    let payload_ptr = unsafe { self.builder.build_struct_gep(enum_ptr, 1, "payload") };
+    let payload_type = self.payload_type(r#enum, variant);
+    self.builder.build_pointer_cast(
+      payload_ptr,
+      payload_type.ptr_type(AddressSpace::Generic),
+      variant,
+    )
+  }
 
-    self.builder.build_unconditional_branch(&cont_block);
+  /// Builds an enum value. It is assembled in memory as the payload is
+  /// reinterpreted as the fields of the variant.
+  fn build_variant(
+    &self,
+    r#enum: &str,
+    variant: &str,
+    fields: Vec<BasicValueEnum>,
+  ) -> BasicValueEnum {
+    let (enum_dec, enum_type) = &self.enums[r#enum];
+    let tag = match enum_dec.get_variant(variant) {
+      Some((tag, _)) => tag,
+      None => unreachable!("No variant {} found on enum {}", variant, r#enum),
+    };
 
-    // emit merge block
-    self.builder.position_at_end(&cont_block);
+    let enum_ptr = self.build_temporary((*enum_type).into());
+    let tag_ptr = unsafe { self.builder.build_struct_gep(enum_ptr, 0, "tag") };
+    let tag = self.context.i32_type().const_int(tag as u64, false);
+    self.builder.build_store(tag_ptr, tag);
+    if fields.len() != 0 {
+      let payload_ptr = self.variant_payload_ptr(enum_ptr, r#enum, variant);
+      let payload = self.build_tuple(fields);
+      self.builder.build_store(payload_ptr, payload);
+    }
+    self.builder.build_load(enum_ptr, r#enum)
+  }
 
-    self.builder.build_phi(self.context.i32_type(), "phi");
+  /// Builds a comparison of an integer or boolean value with a literal or range
+  /// pattern.
+  fn build_literal_test(&self, pattern: &Pattern, value: IntValue) -> IntValue {
+    let int_type = self.context.i32_type();
+    match pattern {
+      Pattern::Int(n) => {
+        let n = int_type.const_int(*n as u64, true);
+        self
+          .builder
+          .build_int_compare(IntPredicate::EQ, value, n, "is_literal")
+      }
+      Pattern::Bool(b) => {
+        let b = self.context.bool_type().const_int(*b as u64, false);
+        self
+          .builder
+          .build_int_compare(IntPredicate::EQ, value, b, "is_literal")
+      }
+      Pattern::Range(start, end) => {
+        let start = int_type.const_int(*start as u64, true);
+        let end = int_type.const_int(*end as u64, true);
+        let above =
+          self
+            .builder
+            .build_int_compare(IntPredicate::SGE, value, start, "above_start");
+        let below =
+          self
+            .builder
+            .build_int_compare(IntPredicate::SLE, value, end, "below_end");
+        self.builder.build_and(above, below, "in_range")
+      }
+      _ => unreachable!("Pattern {} is not a literal", pattern.to_str()),
+    }
   }
 
-  fn compile_while(
+  /// Branches to `fail_block` unless the value at `ptr` matches `pattern`. The
+  /// builder is left where the value matched, and the variables bound by the
+  /// pattern are collected along with pointers to their values.
+  fn build_pattern_test(
+    &self,
+    pattern: &Pattern,
+    ptr: PointerValue,
+    fail_block: &BasicBlock,
+    bindings: &mut Vec<(String, PointerValue)>,
+  ) {
+    let function = self.current_function();
+    match pattern {
+      Pattern::Wildcard => (),
+      Pattern::Binding(name) => bindings.push((name.clone(), ptr)),
+      Pattern::Variant(r#enum, variant, patterns) => {
+        let tag = match self.enums[r#enum].0.get_variant(variant) {
+          Some((tag, _)) => tag,
+          None => unreachable!("No variant {} found on enum {}", variant, r#enum),
+        };
+        let tag_ptr = unsafe { self.builder.build_struct_gep(ptr, 0, "tag") };
+        let value = self.builder.build_load(tag_ptr, "tag").into_int_value();
+        let tag = self.context.i32_type().const_int(tag as u64, false);
+        let is_variant =
+          self
+            .builder
+            .build_int_compare(IntPredicate::EQ, value, tag, "is_variant");
+
+        let variant_block = self.context.append_basic_block(&function, variant);
+        self
+          .builder
+          .build_conditional_branch(is_variant, &variant_block, fail_block);
+        self.builder.position_at_end(&variant_block);
+
+        if patterns.len() != 0 {
+          let payload_ptr = self.variant_payload_ptr(ptr, r#enum, variant);
+          for (i, pattern) in patterns.iter().enumerate() {
+            let field_ptr = unsafe {
+              self
+                .builder
+                .build_struct_gep(payload_ptr, i as u32, "field")
+            };
+            self.build_pattern_test(pattern, field_ptr, fail_block, bindings);
+          }
+        }
+      }
+      Pattern::Int(_) | Pattern::Bool(_) | Pattern::Range(..) => {
+        let value = self.builder.build_load(ptr, "value").into_int_value();
+        let matches = self.build_literal_test(pattern, value);
+        let matched_block = self.context.append_basic_block(&function, "matched");
+        self
+          .builder
+          .build_conditional_branch(matches, &matched_block, fail_block);
+        self.builder.position_at_end(&matched_block);
+      }
+      Pattern::Or(alternatives) => {
+        // The variables bound by the matching alternative are copied to temporaries
+        // shared by all alternatives
+        let matched_block = self.context.append_basic_block(&function, "matched");
+        let mut temporaries: Vec<(String, PointerValue)> = vec![];
+        for alternative in alternatives.iter() {
+          let next_block = self.context.append_basic_block(&function, "alternative");
+          let mut alternative_bindings = vec![];
+          self.build_pattern_test(
+            alternative,
+            ptr,
+            &next_block,
+            &mut alternative_bindings,
+          );
+          for (name, value_ptr) in alternative_bindings {
+            let value = self.builder.build_load(value_ptr, &name);
+            let temporary = match temporaries.iter().find(|(bound, _)| *bound == name) {
+              Some((_, temporary)) => *temporary,
+              None => {
+                let temporary = self.build_temporary(value.get_type());
+                temporaries.push((name, temporary));
+                temporary
+              }
+            };
+            self.builder.build_store(temporary, value);
+          }
+          self.builder.build_unconditional_branch(&matched_block);
+          self.builder.position_at_end(&next_block);
+        }
+        self.builder.build_unconditional_branch(fail_block);
+        self.builder.position_at_end(&matched_block);
+        bindings.append(&mut temporaries);
+      }
+    }
+  }
+
+  /// Builds the tests selecting the arm of a match on the value at `ptr`. Returns
+  /// for every arm the block entered when its pattern matches, the variables bound
+  /// by the pattern and the block testing the following arms. Matches on integers
+  /// jump straight to the arms through a switch on the literals of the patterns.
+  fn build_match_dispatch(
+    &self,
+    ptr: PointerValue,
+    arms: &Vec<(Pattern, Option<Node>, Node)>,
+  ) -> Vec<(BasicBlock, Vec<(String, PointerValue)>, BasicBlock)> {
+    let function = self.current_function();
+    let test_blocks: Vec<BasicBlock> = (0..=arms.len())
+      .map(|_| self.context.append_basic_block(&function, "arm_test"))
+      .collect();
+    let matched_blocks: Vec<BasicBlock> = arms
+      .iter()
+      .map(|_| self.context.append_basic_block(&function, "arm"))
+      .collect();
+
+    match switch_cases(arms) {
+      Some(cases) => {
+        // Values that are not literals of the patterns are tested against the
+        // arms from the first one that can match other values
+        let default = arms
+          .iter()
+          .position(|(pattern, _, _)| !literals_only(pattern))
+          .unwrap_or(arms.len());
+        let value = self.builder.build_load(ptr, "value").into_int_value();
+        let cases: Vec<(IntValue, &BasicBlock)> = cases
+          .into_iter()
+          .map(|(n, arm)| {
+            let n = self.context.i32_type().const_int(n as u64, true);
+            (n, &matched_blocks[arm])
+          })
+          .collect();
+        self
+          .builder
+          .build_switch(value, &test_blocks[default], &cases);
+      }
+      None => {
+        self.builder.build_unconditional_branch(&test_blocks[0]);
+      }
+    }
+
+    let mut dispatch = vec![];
+    for (i, (pattern, _, _)) in arms.iter().enumerate() {
+      self.builder.position_at_end(&test_blocks[i]);
+      let mut bindings = vec![];
+      self.build_pattern_test(pattern, ptr, &test_blocks[i + 1], &mut bindings);
+      self.builder.build_unconditional_branch(&matched_blocks[i]);
+      dispatch.push((
+        matched_blocks[i].clone(),
+        bindings,
+        test_blocks[i + 1].clone(),
+      ));
+    }
+
+    // The match is exhaustive so no value gets past the last arm
+    self.builder.position_at_end(&test_blocks[arms.len()]);
+    self.builder.build_unreachable();
+    dispatch
+  }
+
+  /// Enters an arm whose pattern matched, pushing a scope holding copies of the
+  /// variables bound by the pattern. Branches to `next_block` if the guard of the
+  /// arm does not hold.
+  fn build_arm_entry(
     &mut self,
-    condition: &Node,
-    then_body: &Node,
-    parent_block: &FunctionValue,
+    matched_block: &BasicBlock,
+    bindings: Vec<(String, PointerValue)>,
+    guard: &Option<Node>,
+    next_block: &BasicBlock,
     funcs: &HashMap<String, Func>,
   ) {
-    let cond_block = self.context.append_basic_block(parent_block, "cond");
-    let then_block = self.context.append_basic_block(parent_block, "then");
-    let cont_block = self.context.append_basic_block(parent_block, "cont");
-
-    // Build the condition
-    self.builder.build_unconditional_branch(&cond_block);
-    self.builder.position_at_end(&cond_block);
-    let cond = self.compile_expr(condition, funcs);
-    self
-      .builder
-      .build_conditional_branch(cond, &then_block, &cont_block);
+    self.builder.position_at_end(matched_block);
+    let mut scope = HashMap::new();
+    for (name, value_ptr) in bindings {
+      let value = self.builder.build_load(value_ptr, &name);
+      let alloca = self.build_temporary(value.get_type());
+      self.builder.build_store(alloca, value);
+      scope.insert(name, alloca);
+    }
+    self.variables.push(scope);
 
-    // Build the then block
-    self.builder.position_at_end(&then_block);
-    self.compile_block(then_body, &then_block, parent_block, funcs);
+    if let Some(guard) = guard {
+      let holds = self.compile_expr(guard, funcs).into_int_value();
+      let guard_block = self
+        .context
+        .append_basic_block(&self.current_function(), "guarded");
+      self
+        .builder
+        .build_conditional_branch(holds, &guard_block, next_block);
+      self.builder.position_at_end(&guard_block);
+    }
+  }
 
-    self.builder.build_unconditional_branch(&cond_block);
+  /// Evaluates the arm matching the scrutinee, storing the result in a temporary
+  /// which is loaded after the match.
+  fn compile_match_expr(
+    &mut self,
+    scrutinee: &Node,
+    arms: &Vec<(Pattern, Option<Node>, Node)>,
+    funcs: &HashMap<String, Func>,
+  ) -> BasicValueEnum {
+    let ptr = self.compile_place(scrutinee, funcs);
+    let dispatch = self.build_match_dispatch(ptr, arms);
+    let cont_block = self
+      .context
+      .append_basic_block(&self.current_function(), "cont");
+
+    let mut result = None;
+    for ((_, guard, expr), (matched_block, bindings, next_block)) in
+      arms.iter().zip(dispatch)
+    {
+      self.build_arm_entry(&matched_block, bindings, guard, &next_block, funcs);
+      let value = self.compile_expr(expr, funcs);
+      self.variables.pop();
+      let result_ptr =
+        *result.get_or_insert_with(|| self.build_temporary(value.get_type()));
+      self.builder.build_store(result_ptr, value);
+      self.builder.build_unconditional_branch(&cont_block);
+    }
 
     self.builder.position_at_end(&cont_block);
+    match result {
+      Some(result_ptr) => self.builder.build_load(result_ptr, "match"),
+      None => {
+        self.builder.build_unreachable();
+        self.unit_value()
+      }
+    }
   }
 
-  fn compile_block(
+  /// Unit functions are lowered to void functions, except for main which always
+  /// returns an exit code.
+  fn llvm_fn_type(&self, func: &Func) -> FunctionType {
+    let param_types: Vec<BasicTypeEnum> = func
+      .params
+      .iter()
+      .map(|param| self.llvm_type(&param._type))
+      .collect();
+
+    match func.ret_type {
+      Type::Unit if func.name == "main" => {
+        self.context.i32_type().fn_type(&param_types, false)
+      }
+      Type::Unit => self.context.void_type().fn_type(&param_types, false),
+      ref r#type => self.llvm_type(r#type).fn_type(&param_types, false),
+    }
+  }
+
+  fn compile_expr(
     &mut self,
-    body_start: &Node,
-    block: &BasicBlock,
-    func: &FunctionValue,
+    expr: &Node,
     funcs: &HashMap<String, Func>,
-  ) {
-    // Push a new variable scope
-    self.variables.push(HashMap::new());
-
-    self.builder.position_at_end(&block);
+  ) -> BasicValueEnum {
+    match expr {
+      Node::Number(n) => self.context.i32_type().const_int(*n as u64, false).into(),
+      Node::Var(name) => {
+        let var = self.get_variable(&name);
+        self.builder.build_load(*var, &name)
+      }
+      Node::Bool(b) => self
+        .context
+        .bool_type()
+        .const_int(if *b { 1 } else { 0 }, false)
+        .into(),
+      Node::Char(c) => {
+        let c = self.context.i32_type().const_int(*c as u64, false);
+        self.char_type.const_named_struct(&[c.into()]).into()
+      }
+      Node::Str(s) => self.build_str(s),
+      Node::Unit => self.unit_value(),
+      Node::Array(elements) => {
+        let elements: Vec<BasicValueEnum> = elements
+          .iter()
+          .map(|element| self.compile_expr(element, funcs))
+          .collect();
+        self.build_array(elements)
+      }
+      Node::ArrayRepeat(element, len) => {
+        let element = self.compile_expr(element, funcs);
+        self.build_array(vec![element; *len])
+      }
+      Node::Index(array, index) => {
+        let element = self.compile_element_ptr(array, index, funcs);
+        self.builder.build_load(element, "element")
+      }
+      Node::Tuple(elements) => {
+        let elements: Vec<BasicValueEnum> = elements
+          .iter()
+          .map(|element| self.compile_expr(element, funcs))
+          .collect();
+        self.build_tuple(elements)
+      }
+      Node::TupleField(tuple, field) => {
+        let tuple = self.compile_expr(tuple, funcs).into_struct_value();
+        self
+          .builder
+          .build_extract_value(tuple, *field as u32, "field")
+          .unwrap()
+      }
+      Node::Struct(name, fields) => {
+        // Fields are evaluated in the order they are written in, as in Rust
+        let struct_type = self.structs[name].1;
+        let mut value = struct_type.get_undef();
+        for (field, expr) in fields.iter() {
+          let field_value = self.compile_expr(expr, funcs);
+          value = self
+            .builder
+            .build_insert_value(
+              value,
+              field_value,
+              self.field_index(struct_type, field),
+              field,
+            )
+            .unwrap()
+            .into_struct_value();
+        }
+        value.into()
+      }
+      Node::Field(r#struct, field) => {
+        let value = self.compile_expr(r#struct, funcs).into_struct_value();
+        self
+          .builder
+          .build_extract_value(value, self.field_index(value.get_type(), field), field)
+          .unwrap()
+      }
+      Node::Variant(r#enum, variant, fields) => {
+        let fields: Vec<BasicValueEnum> = fields
+          .iter()
+          .map(|field| self.compile_expr(field, funcs))
+          .collect();
+        self.build_variant(r#enum, variant, fields)
+      }
+      Node::Match(scrutinee, arms, _) => self.compile_match_expr(scrutinee, arms, funcs),
+      Node::MethodCall(receiver, method, _) => {
+        match (self.compile_expr(receiver, funcs), method.as_str()) {
+          (BasicValueEnum::ArrayValue(array), "len") => self
+            .context
+            .i32_type()
+            .const_int(array.get_type().len() as u64, false)
+            .into(),
+          (BasicValueEnum::StructValue(s), "len") => {
+            self.builder.build_extract_value(s, 1, "len").unwrap()
+          }
+          _ => unreachable!("No method {} found", method),
+        }
+      }
+      Node::Op(left, op, right) => {
+        let left_val = self.compile_expr(left, funcs);
+        let right_val = self.compile_expr(right, funcs);
+        if left_val.get_type() == self.str_type.into() {
+          return self.build_str_compare(op, left_val, right_val).into();
+        }
+        let left_val = self.scalar_value(left_val);
+        let right_val = self.scalar_value(right_val);
+        let res = match op {
+          Opcode::Add => self.builder.build_int_add(left_val, right_val, "add"),
+          Opcode::Sub => self.builder.build_int_sub(left_val, right_val, "sub"),
+          Opcode::Mul => self.builder.build_int_mul(left_val, right_val, "mul"),
+          Opcode::Div => self
+            .builder
+            .build_int_signed_div(left_val, right_val, "div"),
+          Opcode::Eq => {
+            self
+              .builder
+              .build_int_compare(IntPredicate::EQ, left_val, right_val, "eq")
+          }
+          Opcode::Neq => {
+            self
+              .builder
+              .build_int_compare(IntPredicate::NE, left_val, right_val, "neq")
+          }
+          Opcode::Geq => {
+            self
+              .builder
+              .build_int_compare(IntPredicate::SGE, left_val, right_val, "geq")
+          }
+          Opcode::Leq => {
+            self
+              .builder
+              .build_int_compare(IntPredicate::SLE, left_val, right_val, "leq")
+          }
+          Opcode::Gneq => {
+            self
+              .builder
+              .build_int_compare(IntPredicate::SGT, left_val, right_val, "gneq")
+          }
+          Opcode::Lneq => {
+            self
+              .builder
+              .build_int_compare(IntPredicate::SLT, left_val, right_val, "lneq")
+          }
+          Opcode::And => self.builder.build_and(left_val, right_val, "and"),
+          Opcode::Or => self.builder.build_or(left_val, right_val, "or"),
+        };
+        res.into()
+      }
+      Node::FuncCall(func_name, args, _) => self.compile_call(func_name, args, funcs),
+      Node::Closure(..) => self.compile_closure(expr, funcs),
+      _ => unreachable!("Cannot compile node {:#?} in expression", expr),
+    }
+  }
 
-    if let Node::Empty = body_start {
-      return;
+  /// Builds a call to a function of the program, or to the closure held by a
+  /// variable of the same name. Calls to void functions evaluate to unit.
+  fn compile_call(
+    &mut self,
+    func_name: &str,
+    args: &Vec<Node>,
+    funcs: &HashMap<String, Func>,
+  ) -> BasicValueEnum {
+    let mut args: Vec<BasicValueEnum> =
+      args.iter().map(|a| self.compile_expr(a, funcs)).collect();
+
+    let call = match self.find_variable(func_name) {
+      Some(var) => {
+        let closure = self.builder.build_load(*var, func_name).into_struct_value();
+        let function = self
+          .builder
+          .build_extract_value(closure, 0, "function")
+          .unwrap()
+          .into_pointer_value();
+        let env = self.builder.build_extract_value(closure, 1, "env").unwrap();
+        args.insert(0, env);
+        self.builder.build_call(function, &args, func_name)
+      }
+      None => {
+        let function = self
+          .module
+          .get_function(func_name)
+          .expect(&format!("Could not find function {}", func_name));
+        self.builder.build_call(function, &args, func_name)
+      }
+    };
+    match call.try_as_basic_value().left() {
+      Some(val) => val,
+      None => self.unit_value(),
     }
-    let mut next_node = Some(body_start);
+  }
 
-    // Compile the instructions
-    while match next_node {
-      Some(_) => true,
-      _ => false,
-    } {
-      self.compile_node(&next_node.clone().unwrap(), func, funcs, block);
-      next_node = next_node.unwrap().get_next_instruction();
+  /// Compiles the body of a closure into a function of its own. The variables it
+  /// captures are copied into an environment on the heap, which lives as long as
+  /// the program as closures can outlive the function creating them.
+  fn compile_closure(
+    &mut self,
+    closure: &Node,
+    funcs: &HashMap<String, Func>,
+  ) -> BasicValueEnum {
+    let (params, ret_type, body) = match closure {
+      Node::Closure(params, Some(ret_type), body, _) => (params, ret_type, body),
+      _ => unreachable!("Compiling a closure that has not been monomorphized"),
+    };
+
+    // The free variables that are not local are functions, constants or statics
+    let mut captures = vec![];
+    for name in closure.free_variables() {
+      if let Some(var) = self.find_variable(&name) {
+        let value = self.builder.build_load(*var, &name);
+        captures.push((name, value));
+      }
     }
+    let env_type = self.context.struct_type(
+      &captures
+        .iter()
+        .map(|(_, value)| value.get_type())
+        .collect::<Vec<BasicTypeEnum>>(),
+      false,
+    );
+    let env = if captures.len() == 0 {
+      self.env_type().const_null()
+    } else {
+      let env = self.builder.build_malloc(env_type, "env");
+      for (i, (name, value)) in captures.iter().enumerate() {
+        let field = unsafe { self.builder.build_struct_gep(env, i as u32, name) };
+        self.builder.build_store(field, *value);
+      }
+      self.builder.build_pointer_cast(env, self.env_type(), "env")
+    };
+
+    // LLVM makes the names of the closures of a function unique
+    let param_types = params.iter().map(|param| param._type.clone()).collect();
+    let closure_type = self.closure_type(&param_types, ret_type);
+    let fn_type = closure_type
+      .get_field_type_at_index(0)
+      .unwrap()
+      .into_pointer_type()
+      .get_element_type()
+      .into_function_type();
+    let name = format!(
+      "{}.closure",
+      self.current_function().get_name().to_str().unwrap()
+    );
+    let function = self
+      .module
+      .add_function(&name, fn_type, Some(Linkage::Private));
+
+    // The body is compiled with only the captured variables and parameters in scope
+    let block = self.builder.get_insert_block().unwrap();
+    let variables = std::mem::replace(&mut self.variables, vec![HashMap::new()]);
+    let entry = self.context.append_basic_block(&function, "entry");
+    self.builder.position_at_end(&entry);
+    self.build_enter("<closure>");
+    if captures.len() != 0 {
+      let env = self.builder.build_pointer_cast(
+        function.get_nth_param(0).unwrap().into_pointer_value(),
+        env_type.ptr_type(AddressSpace::Generic),
+        "env",
+      );
+      for (i, (name, _)) in captures.iter().enumerate() {
+        let field = unsafe { self.builder.build_struct_gep(env, i as u32, name) };
+        let value = self.builder.build_load(field, name);
+        let alloca = self.create_entry_block_alloca(&entry, name, value.get_type());
+        self.builder.build_store(alloca, value);
+      }
+    }
+    for (i, param) in params.iter().enumerate() {
+      let arg = function.get_nth_param(i as u32 + 1).unwrap();
+      let alloca =
+        self.create_entry_block_alloca(&entry, &param.name, self.llvm_type(&param._type));
+      self.builder.build_store(alloca, arg);
+    }
+    let value = self.compile_expr(body, funcs);
+    self.build_return(&function, value);
+    self.variables = variables;
+    self.builder.position_at_end(&block);
+
+    let closure = self
+      .builder
+      .build_insert_value(
+        closure_type.get_undef(),
+        function.as_global_value().as_pointer_value(),
+        0,
+        "closure",
+      )
+      .unwrap()
+      .into_struct_value();
+    self
+      .builder
+      .build_insert_value(closure, env, 1, "closure")
+      .unwrap()
+      .into_struct_value()
+      .into()
+  }
+
+  pub fn compile_program(
+    &mut self,
+    program: &Program,
+  ) -> Result<JitFunction<MainFunc>, RuntimeError> {
+    let execution_engine = self
+      .module
+      .create_jit_execution_engine(OptimizationLevel::None)
+      .unwrap();
+
+    self.add_runtime_functions(&execution_engine);
+
+    // Declare the struct and enum types before their bodies so that they can refer
+    // to each other
+    for (name, r#struct) in program.structs.iter() {
+      let struct_type = self.context.opaque_struct_type(name);
+      self
+        .structs
+        .insert(name.clone(), (r#struct.clone(), struct_type));
+    }
+    for (name, r#enum) in program.enums.iter() {
+      let enum_type = self.context.opaque_struct_type(name);
+      self.enums.insert(name.clone(), (r#enum.clone(), enum_type));
+    }
+    let mut defined = vec![];
+    for name in self.structs.keys().chain(self.enums.keys()) {
+      self.define_named_type(name, execution_engine.get_target_data(), &mut defined);
+    }
+
+    // Constants and statics live in globals, which are initialized when main starts
+    // as there is no other entry point into the program. Their values are the ones
+    // the type checker evaluated
+    let values = program.global_values.borrow();
+    for global in program.globals.iter() {
+      let value = match values.elements.get(&global.name) {
+        Some(variable) => variable.value.clone(),
+        None => {
+          return Err(RuntimeError::new(
+            RuntimeErrorKind::Internal,
+            format!(
+              "Global {} has not been evaluated by the type checker",
+              global.name
+            ),
+          ))
+        }
+      };
+      let r#type = self.llvm_type(&global._type);
+      let pointer = self.module.add_global(r#type, None, &global.name);
+      pointer.set_initializer(&self.undef_value(r#type));
+      self
+        .globals
+        .insert(global.name.clone(), (pointer.as_pointer_value(), value));
+    }
+
+    // Generic functions are compiled as one function per instantiation
+    let funcs = monomorphize(program);
+
+    // Add all functions to the module before compiling
+    for (_, func) in funcs.iter() {
+      let fn_type = self.llvm_fn_type(func);
+      let function = self.module.add_function(&func.name, fn_type, None);
+      self.context.append_basic_block(&function, "entry");
+    }
+
+    // Compile the functions
+    for (_, func) in funcs.iter() {
+      let function = self.module.get_function(&func.name).unwrap();
+      self.compile_func(&function, &func, &funcs);
+    }
+
+    // self.module.print_to_stderr(); //Uncomment this to get the llvm-ir
+    match unsafe { execution_engine.get_function("main") } {
+      Ok(main) => Ok(main),
+      Err(_) => Err(RuntimeError::new(
+        RuntimeErrorKind::UndefinedFunction,
+        "No main function found".to_string(),
+      )),
+    }
+  }
+
+  /// Declares the runtime support functions and maps them to their implementations.
+  fn add_runtime_functions(&self, execution_engine: &ExecutionEngine) {
+    let i32_type: BasicTypeEnum = self.context.i32_type().into();
+    let bytes_type: BasicTypeEnum = self
+      .context
+      .i8_type()
+      .ptr_type(AddressSpace::Generic)
+      .into();
+    let void_type = self.context.void_type();
+    let runtime_functions = vec![
+      (
+        PANIC_BOUNDS_CHECK,
+        void_type.fn_type(&[i32_type, i32_type], false),
+        panic_bounds_check as *const () as usize,
+      ),
+      (
+        ENTER,
+        void_type.fn_type(&[bytes_type, i32_type], false),
+        enter as *const () as usize,
+      ),
+      (
+        LEAVE,
+        void_type.fn_type(&[], false),
+        leave as *const () as usize,
+      ),
+      (
+        PANIC_BEGIN,
+        void_type.fn_type(&[], false),
+        panic_begin as *const () as usize,
+      ),
+      (
+        PANIC_END,
+        void_type.fn_type(&[i32_type], false),
+        panic_end as *const () as usize,
+      ),
+      (
+        PRINT_INT,
+        void_type.fn_type(&[i32_type], false),
+        print_int as *const () as usize,
+      ),
+      (
+        PRINT_BOOL,
+        void_type.fn_type(&[i32_type], false),
+        print_bool as *const () as usize,
+      ),
+      (
+        PRINT_CHAR,
+        void_type.fn_type(&[i32_type], false),
+        print_char as *const () as usize,
+      ),
+      (
+        PRINT_STR,
+        void_type.fn_type(&[bytes_type, i32_type], false),
+        print_str as *const () as usize,
+      ),
+      (
+        DEBUG_CONTEXT,
+        void_type.fn_type(&[], false),
+        debug_context as *const () as usize,
+      ),
+      (
+        DEBUG_LOCAL,
+        void_type.fn_type(&[bytes_type, i32_type], false),
+        debug_local as *const () as usize,
+      ),
+      (
+        STR_EQ,
+        self
+          .context
+          .i32_type()
+          .fn_type(&[bytes_type, i32_type, bytes_type, i32_type], false),
+        str_eq as *const () as usize,
+      ),
+    ];
+    for (name, fn_type, address) in runtime_functions {
+      let function = self
+        .module
+        .add_function(name, fn_type, Some(Linkage::External));
+      execution_engine.add_global_mapping(&function, address);
+    }
+  }
+
+  fn compile_func(
+    &mut self,
+    function: &FunctionValue,
+    func_dec: &Func,
+    funcs: &HashMap<String, Func>,
+  ) {
+    // Push a new variable scope
+    self.variables.push(HashMap::new());
+
+    let func_block = function.get_first_basic_block().unwrap();
+    // Build store instruction for the arguments
+    for (i, param) in func_dec.params.iter().enumerate() {
+      let arg = function.get_nth_param(i as u32).unwrap();
+
+      let alloca = self.create_entry_block_alloca(
+        &func_block,
+        &param.name,
+        self.llvm_type(&param._type),
+      );
+      self.builder.position_at_end(&func_block);
+      self.builder.build_store(alloca, arg);
+    }
+    // Specializations of generic functions are reported under the generic name
+    self.builder.position_at_end(&func_block);
+    self.build_enter(func_dec.name.split('<').next().unwrap());
+    if func_dec.name == "main" {
+      self.builder.position_at_end(&func_block);
+      for (pointer, value) in self.globals.clone().values() {
+        let value = self.compile_expr(&value.clone().into(), funcs);
+        self.builder.build_store(*pointer, value);
+      }
+    }
+
+    self.compile_block(&func_dec.body_start, &func_block, function, funcs);
+
+    //If the function is of type unit we still need to make sure to build a return,
+    //unless its body ends with a panic
+    let terminated = self
+      .builder
+      .get_insert_block()
+      .and_then(|block| block.get_terminator())
+      .is_some();
+    if func_dec.ret_type == Type::Unit && !terminated {
+      self.build_return(function, self.unit_value());
+    }
+
+    // Pop the scope
+    self.variables.pop();
+  }
+
+  /// Records that the function being compiled is called, see ENTER.
+  fn build_enter(&self, name: &str) {
+    let enter = self.module.get_function(ENTER).unwrap();
+    let name = self.str_parts(self.build_str(name));
+    self.builder.build_call(enter, &name, "enter");
+  }
+
+  /// Builds a return of `value`, leaving the function. Unit values are returned as
+  /// void, or as the exit code 0 from main.
+  fn build_return(&self, function: &FunctionValue, value: BasicValueEnum) {
+    let leave = self.module.get_function(LEAVE).unwrap();
+    self.builder.build_call(leave, &[], "leave");
+    match function.get_type().get_return_type() {
+      None => self.builder.build_return(None),
+      Some(_) if value.get_type() == self.unit_type().into() => {
+        let default_return_value = self.context.i32_type().const_int(0, false);
+        self.builder.build_return(Some(&default_return_value))
+      }
+      Some(_) => self.builder.build_return(Some(&value)),
+    };
+  }
+
+  /// Builds a stack allocation at the start of `block` without moving the builder.
+  fn build_alloca_at_start(
+    &self,
+    block: &BasicBlock,
+    name: &str,
+    r#type: BasicTypeEnum,
+  ) -> PointerValue {
+    let builder = self.context.create_builder();
+
+    match block.get_first_instruction() {
+      Some(first_instr) => builder.position_before(&first_instr),
+      None => builder.position_at_end(&block),
+    }
+    builder.build_alloca(r#type, name)
+  }
+
+  /// Creates a new stack allocation instruction in the entry block of the function.
+  /// Arrays, like all other variables, are allocated on the stack this way.
+  fn create_entry_block_alloca(
+    &mut self,
+    block: &BasicBlock,
+    name: &str,
+    r#type: BasicTypeEnum,
+  ) -> PointerValue {
+    let alloca = self.build_alloca_at_start(block, name, r#type);
+    let variables = self.variables.iter_mut().last().unwrap();
+    // A variable shadowing one declared without a value in the same block is not
+    // kept out of $DEBUG_CONTEXT, unless it is declared without a value as well
+    variables.remove(&assigned_flag(name));
+    variables.insert(name.to_string(), alloca);
+    alloca
+  }
+
+  fn compile_node(
+    &mut self,
+    node: &Node,
+    func: &FunctionValue,
+    funcs: &HashMap<String, Func>,
+    block: &BasicBlock,
+  ) {
+    match node {
+      Node::Return(expr, _) => {
+        let expr_val = self.compile_expr(expr, funcs);
+        self.build_return(func, expr_val);
+      }
+      Node::Let(id, _, _, expr, _) => {
+        // Compile the expression first so that it can refer to a shadowed variable
+        let expr_val = self.compile_expr(expr, funcs);
+        let alloca = self.create_entry_block_alloca(block, id, expr_val.get_type());
+        self.builder.build_store(alloca, expr_val);
+      }
+      // Whether the variable has been assigned a value is kept for $DEBUG_CONTEXT,
+      // which leaves out the variables that have not
+      Node::Declare(id, r#type, _, _) => {
+        let r#type = self.llvm_type(r#type);
+        self.create_entry_block_alloca(block, id, r#type);
+        let bool_type = self.context.bool_type();
+        let assigned =
+          self.create_entry_block_alloca(block, &assigned_flag(id), bool_type.into());
+        self
+          .builder
+          .build_store(assigned, bool_type.const_int(0, false));
+      }
+      Node::LetTuple(names, _, expr, _) => {
+        let tuple = self.compile_expr(expr, funcs).into_struct_value();
+        for (i, (name, _)) in names.iter().enumerate() {
+          let element = self
+            .builder
+            .build_extract_value(tuple, i as u32, name)
+            .unwrap();
+          let alloca = self.create_entry_block_alloca(block, name, element.get_type());
+          self.builder.build_store(alloca, element);
+        }
+      }
+      Node::If(condition, then_body, else_body, _) => {
+        match else_body {
+          Some(else_body) => {
+            self.compile_if_else(condition, then_body, else_body, func, funcs)
+          }
+          None => self.compile_if(condition, then_body, func, funcs),
+        };
+      }
+      Node::While(condition, then_body, _) => {
+        self.compile_while(condition, then_body, func, funcs);
+      }
+      Node::Assign(id, expr, _) => {
+        let variable = *self.get_variable(id);
+        let expr = self.compile_expr(expr, funcs);
+        self.builder.build_store(variable, expr);
+        if let Some(assigned) = self.find_assigned_flag(id) {
+          let assigned_true = self.context.bool_type().const_int(1, false);
+          self.builder.build_store(*assigned, assigned_true);
+        }
+      }
+      Node::IndexAssign(array, index, expr, _) => {
+        // As in Rust the assigned value is evaluated before the place
+        let expr = self.compile_expr(expr, funcs);
+        let element = self.compile_element_ptr(array, index, funcs);
+        self.builder.build_store(element, expr);
+      }
+      Node::FieldAssign(r#struct, field, expr, _) => {
+        let expr = self.compile_expr(expr, funcs);
+        let field = self.compile_field_ptr(r#struct, field, funcs);
+        self.builder.build_store(field, expr);
+      }
+      Node::Match(scrutinee, arms, _) => self.compile_match(scrutinee, arms, func, funcs),
+      Node::FuncCall(func_name, args, _) => {
+        self.compile_call(func_name, args, funcs);
+      }
+      Node::Print(pieces, args, newline, _) => {
+        self.compile_print(pieces, args, *newline, funcs)
+      }
+      Node::Check(condition, message, line, _) => {
+        if self.check_contracts {
+          self.compile_check(condition, message, *line, func, funcs);
+        }
+      }
+      Node::Panic(pieces, args, line, next) => {
+        self.compile_panic(pieces, args, *line, funcs);
+        // The instructions after a panic are unreachable, they are compiled into a
+        // block of their own
+        if next.is_some() {
+          let after_panic = self.context.append_basic_block(func, "after_panic");
+          self.builder.position_at_end(&after_panic);
+        }
+      }
+      Node::DebugContext(_) => self.compile_debug_context(),
+      Node::Empty => (),
+      _ => unreachable!("Cannot compile node {:#?}", node),
+    };
+  }
+
+  /// Prints the text of the format string and the arguments in between, all
+  /// arguments are evaluated before anything is printed.
+  fn compile_print(
+    &mut self,
+    pieces: &Vec<String>,
+    args: &Vec<Node>,
+    newline: bool,
+    funcs: &HashMap<String, Func>,
+  ) {
+    let values: Vec<BasicValueEnum> = args
+      .iter()
+      .map(|arg| self.compile_expr(arg, funcs))
+      .collect();
+
+    let mut pieces = pieces.clone();
+    if newline {
+      pieces.last_mut().unwrap().push('\n');
+    }
+    self.build_format(&pieces, &values);
+  }
+
+  /// Prints the text of a format string with the values in between.
+  fn build_format(&self, pieces: &Vec<String>, values: &Vec<BasicValueEnum>) {
+    for (i, piece) in pieces.iter().enumerate() {
+      if i > 0 {
+        self.build_print(values[i - 1]);
+      }
+      if piece.len() != 0 {
+        let piece = self.build_str(piece);
+        self.build_print(piece);
+      }
+    }
+  }
+
+  /// Reports the contract through the runtime if its condition does not hold.
+  fn compile_check(
+    &mut self,
+    condition: &Node,
+    message: &str,
+    line: usize,
+    func: &FunctionValue,
+    funcs: &HashMap<String, Func>,
+  ) {
+    let holds = self.compile_expr(condition, funcs).into_int_value();
+    let violated_block = self.context.append_basic_block(func, "violated");
+    let cont_block = self.context.append_basic_block(func, "cont");
+    self
+      .builder
+      .build_conditional_branch(holds, &cont_block, &violated_block);
+
+    self.builder.position_at_end(&violated_block);
+    self.compile_panic(&vec![message.to_string()], &vec![], line, funcs);
+
+    self.builder.position_at_end(&cont_block);
+  }
+
+  /// Reports a panic through the runtime, which exits the program. The arguments
+  /// of the message are evaluated before anything is reported.
+  fn compile_panic(
+    &mut self,
+    pieces: &Vec<String>,
+    args: &Vec<Node>,
+    line: usize,
+    funcs: &HashMap<String, Func>,
+  ) {
+    let values: Vec<BasicValueEnum> = args
+      .iter()
+      .map(|arg| self.compile_expr(arg, funcs))
+      .collect();
+
+    let panic_begin = self.module.get_function(PANIC_BEGIN).unwrap();
+    self.builder.build_call(panic_begin, &[], "panic");
+    self.build_format(pieces, &values);
+    let line = self.context.i32_type().const_int(line as u64, false);
+    let panic_end = self.module.get_function(PANIC_END).unwrap();
+    self.builder.build_call(panic_end, &[line.into()], "panic");
+    self.builder.build_unreachable();
+  }
+
+  fn compile_if_else(
+    &mut self,
+    condition: &Node,
+    then_body: &Node,
+    else_body: &Node,
+    func: &FunctionValue,
+    funcs: &HashMap<String, Func>,
+  ) {
+    let cond = self.compile_expr(condition, funcs).into_int_value();
+
+    // build branch
+    let then_block = self.context.append_basic_block(&func, "then");
+    let else_block = self.context.append_basic_block(&func, "else");
+    let cont_block = self.context.append_basic_block(&func, "cont");
+
+    self
+      .builder
+      .build_conditional_branch(cond, &then_block, &else_block);
+
+    // build then block
+    self.compile_block(then_body, &then_block, func, funcs);
+    let then_falls_through = self.branch_unless_terminated(&cont_block);
+
+    // build else block
+    self.compile_block(else_body, &else_block, func, funcs);
+    let else_falls_through = self.branch_unless_terminated(&cont_block);
+
+    // emit merge block, which is never reached if both branches return
+    self.builder.position_at_end(&cont_block);
+    if !then_falls_through && !else_falls_through {
+      self.builder.build_unreachable();
+      return;
+    }
+
+    self.builder.build_phi(self.context.i32_type(), "phi");
+  }
+  /// Branches from the current block unless it already ends with a return or a
+  /// panic, returns whether it did
+  fn branch_unless_terminated(&self, block: &BasicBlock) -> bool {
+    let terminated = self
+      .builder
+      .get_insert_block()
+      .and_then(|block| block.get_terminator())
+      .is_some();
+    if !terminated {
+      self.builder.build_unconditional_branch(block);
+    }
+    !terminated
+  }
+
+  fn compile_if(
+    &mut self,
+    condition: &Node,
+    then_body: &Node,
+    parent_block: &FunctionValue,
+    funcs: &HashMap<String, Func>,
+  ) {
+    let cond = self.compile_expr(condition, funcs).into_int_value();
+
+    // build branch
+    let then_block = self.context.append_basic_block(parent_block, "then");
+    let cont_block = self.context.append_basic_block(parent_block, "cont");
+
+    self
+      .builder
+      .build_conditional_branch(cond, &then_block, &cont_block);
+
+    // build then block
+    self.builder.position_at_end(&then_block);
+    self.compile_block(then_body, &then_block, parent_block, funcs);
+    self.branch_unless_terminated(&cont_block);
+
+    // emit merge block
+    self.builder.position_at_end(&cont_block);
+
+    self.builder.build_phi(self.context.i32_type(), "phi");
+  }
+
+  fn compile_while(
+    &mut self,
+    condition: &Node,
+    then_body: &Node,
+    parent_block: &FunctionValue,
+    funcs: &HashMap<String, Func>,
+  ) {
+    let cond_block = self.context.append_basic_block(parent_block, "cond");
+    let then_block = self.context.append_basic_block(parent_block, "then");
+    let cont_block = self.context.append_basic_block(parent_block, "cont");
+
+    // Build the condition
+    self.builder.build_unconditional_branch(&cond_block);
+    self.builder.position_at_end(&cond_block);
+    let cond = self.compile_expr(condition, funcs).into_int_value();
+    self
+      .builder
+      .build_conditional_branch(cond, &then_block, &cont_block);
+
+    // Build the then block
+    self.builder.position_at_end(&then_block);
+    self.compile_block(then_body, &then_block, parent_block, funcs);
+
+    self.builder.build_unconditional_branch(&cond_block);
+
+    self.builder.position_at_end(&cont_block);
+  }
+
+  fn compile_match(
+    &mut self,
+    scrutinee: &Node,
+    arms: &Vec<(Pattern, Option<Node>, Node)>,
+    func: &FunctionValue,
+    funcs: &HashMap<String, Func>,
+  ) {
+    let ptr = self.compile_place(scrutinee, funcs);
+    let dispatch = self.build_match_dispatch(ptr, arms);
+    let cont_block = self.context.append_basic_block(func, "cont");
+
+    let mut falls_through = false;
+    for ((_, guard, body), (matched_block, bindings, next_block)) in
+      arms.iter().zip(dispatch)
+    {
+      self.build_arm_entry(&matched_block, bindings, guard, &next_block, funcs);
+      let arm_block = self.builder.get_insert_block().unwrap();
+      self.compile_block(body, &arm_block, func, funcs);
+      self.variables.pop();
+
+      // Arms that return have already been terminated
+      if self
+        .builder
+        .get_insert_block()
+        .unwrap()
+        .get_terminator()
+        .is_none()
+      {
+        self.builder.build_unconditional_branch(&cont_block);
+        falls_through = true;
+      }
+    }
+
+    self.builder.position_at_end(&cont_block);
+    if !falls_through {
+      self.builder.build_unreachable();
+    }
+  }
+
+  fn compile_block(
+    &mut self,
+    body_start: &Node,
+    block: &BasicBlock,
+    func: &FunctionValue,
+    funcs: &HashMap<String, Func>,
+  ) {
+    self.builder.position_at_end(&block);
+
+    if let Node::Empty = body_start {
+      return;
+    }
+
+    // Push a new variable scope
+    self.variables.push(HashMap::new());
+    let mut next_node = Some(body_start);
+
+    // Compile the instructions
+    while match next_node {
+      Some(_) => true,
+      _ => false,
+    } {
+      self.compile_node(&next_node.clone().unwrap(), func, funcs, block);
+      next_node = next_node.unwrap().get_next_instruction();
+    }
+
+    // Push the scope
+    self.variables.pop();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::type_checker::type_check_program;
+  use crate::types::program::Program;
+  use std::{convert::TryFrom, path::Path};
+
+  #[test]
+  fn test_if_statement_true() {
+    let program = Program::try_from(Path::new("tests/samples/if_a_eq_2.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 11);
+  }
+
+  #[test]
+  fn test_if_empty_body() {
+    let program = Program::try_from(Path::new("tests/samples/if_empty_body.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 6);
+  }
+
+  #[test]
+  fn test_if_else_into_else() {
+    let program =
+      Program::try_from(Path::new("tests/samples/if_else_into_else.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 11);
+  }
+
+  #[test]
+  fn test_empty_function() {
+    let program =
+      Program::try_from(Path::new("tests/samples/empty_function.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 0);
+  }
+
+  #[test]
+  fn test_variable_in_expression_add() {
+    let program =
+      Program::try_from(Path::new("tests/samples/variable_in_expression_add.rs"))
+        .unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 5);
+  }
+
+  #[test]
+  fn test_variable_in_expression_sub() {
+    let program =
+      Program::try_from(Path::new("tests/samples/variable_in_expression_sub.rs"))
+        .unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 5);
+  }
+  #[test]
+  fn test_variable_in_expression_mul() {
+    let program =
+      Program::try_from(Path::new("tests/samples/variable_in_expression_mul.rs"))
+        .unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 6);
+  }
+  #[test]
+  fn test_variable_in_expression_div() {
+    let program =
+      Program::try_from(Path::new("tests/samples/variable_in_expression_div.rs"))
+        .unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 2);
+  }
+
+  #[test]
+  fn test_assign_variable_to_another() {
+    let program =
+      Program::try_from(Path::new("tests/samples/assign_variable_to_another.rs"))
+        .unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 3);
+  }
+
+  #[test]
+  fn test_relop_neq_true() {
+    let program =
+      Program::try_from(Path::new("tests/samples/relop_neq_true.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_ne!(result, 0);
+  }
+
+  #[test]
+  fn test_relop_neq_false() {
+    let program =
+      Program::try_from(Path::new("tests/samples/relop_neq_false.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 0);
+  }
+
+  #[test]
+  fn test_variable_int_assignment() {
+    let program =
+      Program::try_from(Path::new("tests/samples/variable_int_assignment.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 4);
+  }
+
+  #[test]
+  fn test_function_call_return_int() {
+    let program =
+      Program::try_from(Path::new("tests/samples/function_call_return_int.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 5);
+  }
+
+  #[test]
+  fn test_function_call_return_variable() {
+    let program =
+      Program::try_from(Path::new("tests/samples/function_call_return_variable.rs"))
+        .unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 7);
+  }
+
+  #[test]
+  fn test_variable_function_assignment() {
+    let program =
+      Program::try_from(Path::new("tests/samples/variable_function_assignment.rs"))
+        .unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 24);
+  }
+
+  #[test]
+  fn test_function_call_return_arg() {
+    let program =
+      Program::try_from(Path::new("tests/samples/function_call_return_arg.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 17);
+  }
+
+  #[test]
+  fn test_function_call_return_sum_of_args() {
+    let program = Program::try_from(Path::new(
+      "tests/samples/function_call_return_sum_of_args.rs",
+    ))
+    .unwrap();
+
+    let mut compiler = Compiler::new();
+
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, -2);
+  }
+
+  #[test]
+  fn test_function_call_return_local_variable() {
+    let program = Program::try_from(Path::new(
+      "tests/samples/function_call_return_local_variable.rs",
+    ))
+    .unwrap();
+
+    let mut compiler = Compiler::new();
+
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 9);
+  }
+
+  #[test]
+  fn test_variable_scope_functions() {
+    let program =
+      Program::try_from(Path::new("tests/samples/variable_scope_functions.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 3);
+  }
+
+  #[test]
+  fn test_fibbonacci_recursive() {
+    let program =
+      Program::try_from(Path::new("tests/samples/fibbonaci_recursive.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+
+    assert_eq!(result, 34);
+  }
+
+  #[test]
+  fn test_shadowing_return_original() {
+    let program =
+      Program::try_from(Path::new("tests/samples/shadowing_return_original.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
 
-    // Push the scope
-    self.variables.pop();
+    assert_eq!(result, 14);
   }
-}
-
-#[cfg(test)]
-mod tests {
-  use super::*;
-  use crate::types::program::Program;
-  use std::{convert::TryFrom, path::Path};
 
   #[test]
-  fn test_if_statement_true() {
-    let program = Program::try_from(Path::new("tests/samples/if_a_eq_2.rs")).unwrap();
+  fn test_shadowing_return_shadowed() {
+    let program =
+      Program::try_from(Path::new("tests/samples/shadowing_return_shadowed.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
 
-    assert_eq!(result, 11);
+    assert_eq!(result, 4);
   }
 
   #[test]
-  fn test_if_empty_body() {
-    let program = Program::try_from(Path::new("tests/samples/if_empty_body.rs")).unwrap();
+  fn test_variable_redeclare_in_same_scope() {
+    let program = Program::try_from(Path::new(
+      "tests/samples/variable_redeclare_in_same_scope.rs",
+    ))
+    .unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
 
-    assert_eq!(result, 6);
+    assert_eq!(result, 7);
   }
 
   #[test]
-  fn test_if_else_into_else() {
-    let program =
-      Program::try_from(Path::new("tests/samples/if_else_into_else.rs")).unwrap();
+  fn test_variable_assignment_in_if_block() {
+    let program = Program::try_from(Path::new(
+      "tests/samples/variable_assignment_in_if_block.rs",
+    ))
+    .unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
 
-    assert_eq!(result, 11);
+    assert_eq!(result, 10);
   }
 
   #[test]
-  fn test_empty_function() {
+  #[should_panic]
+  fn test_function_argument_scope() {
     let program =
-      Program::try_from(Path::new("tests/samples/empty_function.rs")).unwrap();
+      Program::try_from(Path::new("tests/samples/function_argument_scope.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+
+    let main = compiler.compile_program(&program).unwrap();
+    unsafe {
+      main.call();
+    };
+  }
+
+  #[test]
+  fn while_count_to_10() {
+    let program =
+      Program::try_from(Path::new("tests/samples/while_count_to_10.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 0);
+    assert_eq!(result, 10);
   }
 
   #[test]
-  fn test_variable_in_expression_add() {
+  fn test_unit_function_let() {
     let program =
-      Program::try_from(Path::new("tests/samples/variable_in_expression_add.rs"))
-        .unwrap();
+      Program::try_from(Path::new("tests/samples/unit_function_let.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 5);
+    assert_eq!(result, 4);
   }
 
   #[test]
-  fn test_variable_in_expression_sub() {
+  fn test_unit_function_return() {
     let program =
-      Program::try_from(Path::new("tests/samples/variable_in_expression_sub.rs"))
-        .unwrap();
+      Program::try_from(Path::new("tests/samples/unit_function_return.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
-
     assert_eq!(result, 5);
   }
+
   #[test]
-  fn test_variable_in_expression_mul() {
-    let program =
-      Program::try_from(Path::new("tests/samples/variable_in_expression_mul.rs"))
-        .unwrap();
+  fn test_array_sum() {
+    let program = Program::try_from(Path::new("tests/samples/array_sum.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 6);
+    assert_eq!(result, 10);
   }
+
   #[test]
-  fn test_variable_in_expression_div() {
+  fn test_array_index_assign() {
     let program =
-      Program::try_from(Path::new("tests/samples/variable_in_expression_div.rs"))
-        .unwrap();
+      Program::try_from(Path::new("tests/samples/array_index_assign.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 2);
+    assert_eq!(result, 7);
   }
 
   #[test]
-  fn test_assign_variable_to_another() {
-    let program =
-      Program::try_from(Path::new("tests/samples/assign_variable_to_another.rs"))
-        .unwrap();
+  fn test_tuple_return() {
+    let program = Program::try_from(Path::new("tests/samples/tuple_return.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 3);
+    assert_eq!(result, 32);
   }
 
   #[test]
-  fn test_relop_neq_true() {
-    let program =
-      Program::try_from(Path::new("tests/samples/relop_neq_true.rs")).unwrap();
+  fn test_tuple_nested() {
+    let program = Program::try_from(Path::new("tests/samples/tuple_nested.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
-
-    assert_ne!(result, 0);
+    assert_eq!(result, 242);
   }
 
   #[test]
-  fn test_relop_neq_false() {
-    let program =
-      Program::try_from(Path::new("tests/samples/relop_neq_false.rs")).unwrap();
+  fn test_struct_fields() {
+    let program = Program::try_from(Path::new("tests/samples/struct_fields.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 0);
+    assert_eq!(result, 50);
   }
 
   #[test]
-  fn test_variable_int_assignment() {
-    let program =
-      Program::try_from(Path::new("tests/samples/variable_int_assignment.rs")).unwrap();
+  fn test_struct_array() {
+    let program = Program::try_from(Path::new("tests/samples/struct_array.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 4);
+    assert_eq!(result, 7);
   }
 
   #[test]
-  fn test_function_call_return_int() {
-    let program =
-      Program::try_from(Path::new("tests/samples/function_call_return_int.rs")).unwrap();
+  fn test_enum_match() {
+    let program = Program::try_from(Path::new("tests/samples/enum_match.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 5);
+    assert_eq!(result, 124);
   }
 
   #[test]
-  fn test_function_call_return_variable() {
+  fn test_match_int_bool() {
     let program =
-      Program::try_from(Path::new("tests/samples/function_call_return_variable.rs"))
-        .unwrap();
+      Program::try_from(Path::new("tests/samples/match_int_bool.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 7);
+    assert_eq!(result, 72);
   }
 
   #[test]
-  fn test_variable_function_assignment() {
-    let program =
-      Program::try_from(Path::new("tests/samples/variable_function_assignment.rs"))
-        .unwrap();
+  fn test_strings() {
+    let program = Program::try_from(Path::new("tests/samples/strings.rs")).unwrap();
+    type_check_program(&program).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 24);
+    assert_eq!(result, 127);
   }
 
   #[test]
-  fn test_function_call_return_arg() {
+  fn test_formatted_output() {
     let program =
-      Program::try_from(Path::new("tests/samples/function_call_return_arg.rs")).unwrap();
+      Program::try_from(Path::new("tests/samples/formatted_output.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 17);
+    assert_eq!(result, 15);
   }
 
   #[test]
-  fn test_function_call_return_sum_of_args() {
-    let program = Program::try_from(Path::new(
-      "tests/samples/function_call_return_sum_of_args.rs",
-    ))
-    .unwrap();
+  fn test_generics() {
+    let program = Program::try_from(Path::new("tests/samples/generics.rs")).unwrap();
 
     let mut compiler = Compiler::new();
 
@@ -662,16 +2685,12 @@ mod tests {
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, -2);
+    assert_eq!(result, 45);
   }
 
   #[test]
-  fn test_function_call_return_local_variable() {
-    let program = Program::try_from(Path::new(
-      "tests/samples/function_call_return_local_variable.rs",
-    ))
-    .unwrap();
+  fn test_closures() {
+    let program = Program::try_from(Path::new("tests/samples/closures.rs")).unwrap();
 
     let mut compiler = Compiler::new();
 
@@ -680,14 +2699,13 @@ mod tests {
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 9);
+    assert_eq!(result, 46);
   }
 
   #[test]
-  fn test_variable_scope_functions() {
+  fn test_nested_functions() {
     let program =
-      Program::try_from(Path::new("tests/samples/variable_scope_functions.rs")).unwrap();
+      Program::try_from(Path::new("tests/samples/nested_functions.rs")).unwrap();
 
     let mut compiler = Compiler::new();
 
@@ -696,14 +2714,13 @@ mod tests {
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 3);
+    assert_eq!(result, 1136);
   }
 
   #[test]
-  fn test_fibbonacci_recursive() {
-    let program =
-      Program::try_from(Path::new("tests/samples/fibbonaci_recursive.rs")).unwrap();
+  fn test_globals() {
+    let program = Program::try_from(Path::new("tests/samples/globals.rs")).unwrap();
+    type_check_program(&program).unwrap();
 
     let mut compiler = Compiler::new();
 
@@ -712,14 +2729,12 @@ mod tests {
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 34);
+    assert_eq!(result, 154);
   }
 
   #[test]
-  fn test_shadowing_return_original() {
-    let program =
-      Program::try_from(Path::new("tests/samples/shadowing_return_original.rs")).unwrap();
+  fn test_type_aliases() {
+    let program = Program::try_from(Path::new("tests/samples/type_aliases.rs")).unwrap();
 
     let mut compiler = Compiler::new();
 
@@ -728,14 +2743,12 @@ mod tests {
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 14);
+    assert_eq!(result, 97);
   }
 
   #[test]
-  fn test_shadowing_return_shadowed() {
-    let program =
-      Program::try_from(Path::new("tests/samples/shadowing_return_shadowed.rs")).unwrap();
+  fn test_modules() {
+    let program = Program::try_from(Path::new("tests/samples/modules/main.rs")).unwrap();
 
     let mut compiler = Compiler::new();
 
@@ -744,16 +2757,12 @@ mod tests {
     unsafe {
       result = main.call();
     };
-
-    assert_eq!(result, 4);
+    assert_eq!(result, 37);
   }
 
   #[test]
-  fn test_variable_redeclare_in_same_scope() {
-    let program = Program::try_from(Path::new(
-      "tests/samples/variable_redeclare_in_same_scope.rs",
-    ))
-    .unwrap();
+  fn test_macros() {
+    let program = Program::try_from(Path::new("tests/samples/macros.rs")).unwrap();
 
     let mut compiler = Compiler::new();
 
@@ -762,46 +2771,71 @@ mod tests {
     unsafe {
       result = main.call();
     };
+    assert_eq!(result, 36);
+  }
+
+  #[test]
+  fn test_assertions() {
+    let program = Program::try_from(Path::new("tests/samples/assertions.rs")).unwrap();
 
+    let mut compiler = Compiler::new();
+
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
     assert_eq!(result, 7);
   }
 
   #[test]
-  fn test_variable_assignment_in_if_block() {
-    let program = Program::try_from(Path::new(
-      "tests/samples/variable_assignment_in_if_block.rs",
-    ))
-    .unwrap();
+  fn test_contracts() {
+    let program = Program::try_from(Path::new("tests/samples/contracts.rs")).unwrap();
 
     let mut compiler = Compiler::new();
+    compiler.check_contracts = true;
 
     let main = compiler.compile_program(&program).unwrap();
     let result;
     unsafe {
       result = main.call();
     };
+    assert_eq!(result, 28);
+  }
 
-    assert_eq!(result, 10);
+  #[test]
+  fn test_deferred_let() {
+    let program = Program::try_from(Path::new("tests/samples/deferred_let.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+    assert_eq!(result, 16);
   }
 
   #[test]
-  #[should_panic]
-  fn test_function_argument_scope() {
+  fn test_branch_returns() {
     let program =
-      Program::try_from(Path::new("tests/samples/function_argument_scope.rs")).unwrap();
+      Program::try_from(Path::new("tests/samples/branch_returns.rs")).unwrap();
 
     let mut compiler = Compiler::new();
 
     let main = compiler.compile_program(&program).unwrap();
+    let result;
     unsafe {
-      main.call();
+      result = main.call();
     };
+    assert_eq!(result, 26);
   }
 
   #[test]
-  fn while_count_to_10() {
+  fn test_if_else_returns() {
     let program =
-      Program::try_from(Path::new("tests/samples/while_count_to_10.rs")).unwrap();
+      Program::try_from(Path::new("tests/samples/if_else_returns.rs")).unwrap();
 
     let mut compiler = Compiler::new();
 
@@ -810,6 +2844,96 @@ mod tests {
     unsafe {
       result = main.call();
     };
-    assert_eq!(result, 10);
+    assert_eq!(result, 215);
+  }
+
+  /// Compiled code exits when it panics, a test panicking is run again in a process
+  /// of its own with JIT_PANIC set, where it runs the program. Returns the exit code
+  /// and stderr of the process.
+  fn run_panicking(test: &str) -> (Option<i32>, String) {
+    let jit = std::process::Command::new(std::env::current_exe().unwrap())
+      .args(&[&format!("llvm::tests::{}", test), "--exact", "--nocapture"])
+      .env("JIT_PANIC", "1")
+      .output()
+      .unwrap();
+    (jit.status.code(), String::from_utf8(jit.stderr).unwrap())
+  }
+
+  #[test]
+  fn test_panic_matches_interpreter() {
+    let program = Program::try_from(Path::new("tests/samples/panic.rs")).unwrap();
+    if std::env::var("JIT_PANIC").is_ok() {
+      let mut compiler = Compiler::new();
+      let main = compiler.compile_program(&program).unwrap();
+      unsafe {
+        main.call();
+      }
+      unreachable!("The program panics");
+    }
+    let (status, stderr) = run_panicking("test_panic_matches_interpreter");
+    assert_eq!(status, Some(101));
+    let error = program.try_interpret().unwrap_err();
+    assert_eq!(stderr, format!("thread 'main' {}\n", error));
+  }
+
+  #[test]
+  fn test_index_out_of_bounds_matches_interpreter() {
+    let program =
+      Program::try_from(Path::new("tests/samples/array_out_of_bounds.rs")).unwrap();
+    if std::env::var("JIT_PANIC").is_ok() {
+      let mut compiler = Compiler::new();
+      let main = compiler.compile_program(&program).unwrap();
+      unsafe {
+        main.call();
+      }
+      unreachable!("The program panics");
+    }
+    let (status, stderr) = run_panicking("test_index_out_of_bounds_matches_interpreter");
+    assert_eq!(status, Some(101));
+    let error = program.try_interpret().unwrap_err();
+    assert_eq!(stderr, format!("thread 'main' {}\n", error));
+  }
+
+  #[test]
+  fn test_debug_context() {
+    let program = Program::try_from(Path::new("tests/samples/debug_context.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+
+    let main = compiler.compile_program(&program).unwrap();
+    let (result, output) = output::capture(|| unsafe { main.call() });
+    assert_eq!(result, 15);
+    // The locals are dumped the same way as by the interpreter
+    let (_, interpreted) = output::capture(|| program.interpret());
+    assert_eq!(output, interpreted);
+  }
+
+  #[test]
+  fn test_debug_context_shadowing() {
+    let program =
+      Program::try_from(Path::new("tests/samples/debug_context_shadowing.rs")).unwrap();
+
+    let mut compiler = Compiler::new();
+
+    let main = compiler.compile_program(&program).unwrap();
+    let (result, output) = output::capture(|| unsafe { main.call() });
+    assert_eq!(result, 12);
+    let (_, interpreted) = output::capture(|| program.interpret());
+    assert_eq!(output, interpreted);
+  }
+
+  #[test]
+  fn test_traits() {
+    let program = Program::try_from(Path::new("tests/samples/traits.rs")).unwrap();
+    type_check_program(&program).unwrap();
+
+    let mut compiler = Compiler::new();
+
+    let main = compiler.compile_program(&program).unwrap();
+    let result;
+    unsafe {
+      result = main.call();
+    };
+    assert_eq!(result, 50);
   }
 }
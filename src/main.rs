@@ -4,24 +4,92 @@ extern crate lalrpop_util;
 #[macro_use]
 mod util;
 mod errors;
+mod const_eval;
+mod definite_assignment;
+mod exhaustiveness;
 mod interpreter;
 mod llvm;
+mod monomorphize;
+mod output;
 mod parsing;
+mod test_runner;
 mod type_checker;
 mod types;
 
-use std::{convert::TryFrom, path::Path};
+use std::{convert::TryFrom, fs, path::Path};
 
 use llvm::Compiler;
+use parsing::migrate::migrate;
+use test_runner::{report, run_tests};
 use type_checker::type_check_program;
-use types::program::Program;
+use types::{program::Program, value::Value};
 
 fn print_error_header() {
   println!("Errors");
   println!("==============================");
 }
 
+fn print_warning_header() {
+  println!("Warnings");
+  println!("==============================");
+}
+
+// Rewrites deprecated syntax in the given files
+fn migrate_files(paths: &[String]) {
+  for path in paths {
+    let file = match fs::read_to_string(path) {
+      Ok(file) => file,
+      Err(e) => panic!("Could not read {}: {}", path, e),
+    };
+    match migrate(&file) {
+      Ok(migrated) => {
+        if let Err(e) = fs::write(path, migrated) {
+          panic!("Could not write {}: {}", path, e);
+        }
+      }
+      Err(e) => {
+        print_error_header();
+        println!("{}: {}", path, e);
+      }
+    }
+  }
+}
+
+// Runs the test functions of the program whose path contains the filter, exits
+// with Rust's panic exit code if any of them fails
+fn test(filter: &str) {
+  let program = match Program::try_from(Path::new("input.rs")) {
+    Ok(program) => program,
+    Err(e) => {
+      print_error_header();
+      println!("{}", e);
+      return;
+    }
+  };
+  if let Err(errors) = type_check_program(&program) {
+    print_error_header();
+    for error in errors.iter() {
+      println!("- {}", error);
+    }
+    return;
+  }
+
+  let results = run_tests(&program, filter);
+  println!("{}", report(&results, program.tests.len() - results.len()));
+  if results.iter().any(|result| result.failure.is_some()) {
+    std::process::exit(101);
+  }
+}
+
 fn main() {
+  let args: Vec<String> = std::env::args().collect();
+  if args.len() > 1 && args[1] == "--migrate" {
+    return migrate_files(&args[2..]);
+  }
+  if args.len() > 1 && args[1] == "test" {
+    return test(args.get(2).map(|filter| filter.as_str()).unwrap_or(""));
+  }
+
   let program = match Program::try_from(Path::new("input.rs")) {
     Ok(program) => program,
     Err(e) => {
@@ -31,20 +99,41 @@ fn main() {
     }
   };
   let type_res = type_check_program(&program);
-  if let Ok(_) = type_res {
-    println!(
-      "Interpreter exited with code {}",
-      match program.interpret() {
-        Some(value) => (&value).into(),
-        _ => 0.to_string(),
+  if let Ok(warnings) = type_res {
+    if program.warnings.len() + warnings.len() != 0 {
+      print_warning_header();
+      for warning in program.warnings.iter() {
+        println!("- {}", warning);
+      }
+      for warning in warnings.iter() {
+        println!("- {}", warning);
       }
-    );
+    }
+    match program.try_interpret() {
+      Ok(value) => println!(
+        "Interpreter exited with code {}",
+        match value {
+          Value::Unit => 0.to_string(),
+          value => (&value).into(),
+        }
+      ),
+      // A panicking program exits with Rust's panic exit code
+      Err(e) => {
+        eprintln!("thread 'main' {}", e);
+        std::process::exit(101);
+      }
+    }
     let mut compiler = Compiler::new();
+    // Contracts are always checked by the interpreter, in compiled code on request
+    compiler.check_contracts = args.iter().any(|arg| arg == "--check-contracts");
 
-    let main = compiler
-      .compile_program(&program)
-      .ok_or("Unable to JIT compile program")
-      .unwrap();
+    let main = match compiler.compile_program(&program) {
+      Ok(main) => main,
+      Err(e) => {
+        eprintln!("thread 'main' {}", e);
+        std::process::exit(101);
+      }
+    };
 
     unsafe {
       println!("Program exited with exit code {}", main.call());
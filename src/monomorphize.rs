@@ -0,0 +1,446 @@
+use std::collections::HashMap;
+
+use crate::{
+  type_checker::{infer_type_args, push_bindings, type_check_expr, type_check_pattern},
+  types::{
+    _type::Type, context::Context, func::Func, node::Node, pattern::Pattern,
+    program::Program, scope::Scope,
+  },
+};
+
+// Generic functions are compiled once for every instantiation. Monomorphization
+// replaces the calls to generic functions by calls to specializations for the type
+// arguments of the call, which are named after the instantiation, e.g. max<i32>.
+// Only the functions reachable from a non generic function are specialized, the
+// program has to be well typed.
+pub fn monomorphize(program: &Program) -> HashMap<String, Func> {
+  let mut monomorphizer = Monomorphizer {
+    program: program,
+    funcs: HashMap::new(),
+    pending: vec![],
+  };
+  for func in program.funcs.values() {
+    if func.type_params.len() == 0 {
+      monomorphizer.funcs.insert(func.name.clone(), func.clone());
+      monomorphizer
+        .pending
+        .push((func.name.clone(), HashMap::new()));
+    }
+  }
+
+  while let Some((name, type_args)) = monomorphizer.pending.pop() {
+    monomorphizer.specialize_func(&name, &type_args);
+  }
+  monomorphizer.funcs
+}
+
+struct Monomorphizer<'a> {
+  program: &'a Program,
+  // Functions of the monomorphized program, the bodies of the pending functions
+  // still call generic functions
+  funcs: HashMap<String, Func>,
+  // Functions whose bodies have not been specialized yet, along with the type
+  // arguments to substitute in them
+  pending: Vec<(String, HashMap<String, Type>)>,
+}
+
+impl<'a> Monomorphizer<'a> {
+  // Returns the name of the specialization of a generic function, adding it to the
+  // program the first time the type arguments are used
+  fn instantiate(&mut self, func: &Func, type_args: HashMap<String, Type>) -> String {
+    let name = format!(
+      "{}<{}>",
+      func.name,
+      func
+        .type_params
+        .iter()
+        .map(|(param, _)| type_args[param].to_str())
+        .collect::<Vec<String>>()
+        .join(", ")
+    );
+    if !self.funcs.contains_key(&name) {
+      let mut params = func.params.clone();
+      for param in params.iter_mut() {
+        param._type = param._type.substitute(&type_args);
+      }
+      self.funcs.insert(
+        name.clone(),
+        Func {
+          name: name.clone(),
+          type_params: vec![],
+          params: params,
+          ret_type: func.ret_type.substitute(&type_args),
+          body_start: func.body_start.clone(),
+          location: func.location,
+        },
+      );
+      self.pending.push((name.clone(), type_args));
+    }
+    name
+  }
+
+  fn specialize_func(&mut self, name: &str, type_args: &HashMap<String, Type>) {
+    let func = self.funcs[name].clone();
+    let mut context: Context<(Type, bool)> = Context::from(&func);
+    context.structs = self.program.structs.clone();
+    context.enums = self.program.enums.clone();
+    context.traits = self.program.traits.clone();
+    context.impls = self.program.impls.clone();
+    context.globals = Scope::from(&self.program.globals);
+    context.push(Scope::from(func.params.clone()));
+
+    let body = self.specialize(&func.body_start, type_args, &mut context);
+    self.funcs.get_mut(name).unwrap().body_start = body;
+  }
+
+  fn type_of(&self, expr: &Node, context: &mut Context<(Type, bool)>) -> Type {
+    match type_check_expr(expr, context, &self.program.funcs) {
+      Ok(r#type) => r#type,
+      Err(_) => unreachable!("Monomorphizing ill typed expression {:#?}", expr),
+    }
+  }
+
+  // Returns the function called by a call along with the specialized arguments
+  fn specialize_call(
+    &mut self,
+    func: &str,
+    args: &Vec<Node>,
+    type_args: &HashMap<String, Type>,
+    context: &mut Context<(Type, bool)>,
+  ) -> (String, Vec<Node>) {
+    let program = self.program;
+    // Variables holding closures shadow the functions of the program
+    let func = match program.funcs.get(func) {
+      Some(callee)
+        if context.get_var_type(func).is_none() && callee.type_params.len() != 0 =>
+      {
+        let arg_types = args.iter().map(|arg| self.type_of(arg, context)).collect();
+        match infer_type_args(callee, &arg_types) {
+          Ok(callee_type_args) => self.instantiate(callee, callee_type_args),
+          Err(_) => unreachable!("Monomorphizing ill typed call to {}", func),
+        }
+      }
+      _ => func.to_string(),
+    };
+    let args = args
+      .iter()
+      .map(|arg| self.specialize_expr(arg, type_args, context))
+      .collect();
+    (func, args)
+  }
+
+  // Specializes the guards and bodies of the arms of a match, the bodies are
+  // expressions or blocks
+  fn specialize_arms(
+    &mut self,
+    scrutinee: &Node,
+    arms: &Vec<(Pattern, Option<Node>, Node)>,
+    is_expr: bool,
+    type_args: &HashMap<String, Type>,
+    context: &mut Context<(Type, bool)>,
+  ) -> Vec<(Pattern, Option<Node>, Node)> {
+    let scrutinee_type = self.type_of(scrutinee, context);
+    let mut specialized = vec![];
+    for (pattern, guard, body) in arms.iter() {
+      let mut bindings = vec![];
+      if let Err(_) = type_check_pattern(pattern, &scrutinee_type, context, &mut bindings)
+      {
+        unreachable!("Monomorphizing ill typed pattern {}", pattern.to_str());
+      }
+      push_bindings(context, bindings);
+      let guard = guard
+        .as_ref()
+        .map(|guard| self.specialize_expr(guard, type_args, context));
+      let body = if is_expr {
+        self.specialize_expr(body, type_args, context)
+      } else {
+        self.specialize(body, type_args, context)
+      };
+      context.pop();
+      specialized.push((pattern.clone(), guard, body));
+    }
+    specialized
+  }
+
+  fn specialize_exprs(
+    &mut self,
+    exprs: &Vec<Node>,
+    type_args: &HashMap<String, Type>,
+    context: &mut Context<(Type, bool)>,
+  ) -> Vec<Node> {
+    exprs
+      .iter()
+      .map(|expr| self.specialize_expr(expr, type_args, context))
+      .collect()
+  }
+
+  fn specialize_expr(
+    &mut self,
+    expr: &Node,
+    type_args: &HashMap<String, Type>,
+    context: &mut Context<(Type, bool)>,
+  ) -> Node {
+    match expr {
+      Node::Array(elements) => {
+        Node::Array(self.specialize_exprs(elements, type_args, context))
+      }
+      Node::ArrayRepeat(element, len) => Node::ArrayRepeat(
+        Box::new(self.specialize_expr(element, type_args, context)),
+        *len,
+      ),
+      Node::Index(array, index) => Node::Index(
+        Box::new(self.specialize_expr(array, type_args, context)),
+        Box::new(self.specialize_expr(index, type_args, context)),
+      ),
+      Node::Tuple(elements) => {
+        Node::Tuple(self.specialize_exprs(elements, type_args, context))
+      }
+      Node::TupleField(tuple, field) => Node::TupleField(
+        Box::new(self.specialize_expr(tuple, type_args, context)),
+        *field,
+      ),
+      Node::Struct(name, fields) => Node::Struct(
+        name.clone(),
+        fields
+          .iter()
+          .map(|(field, expr)| {
+            (
+              field.clone(),
+              self.specialize_expr(expr, type_args, context),
+            )
+          })
+          .collect(),
+      ),
+      Node::Field(r#struct, field) => Node::Field(
+        Box::new(self.specialize_expr(r#struct, type_args, context)),
+        field.clone(),
+      ),
+      Node::Variant(r#enum, variant, fields) => Node::Variant(
+        r#enum.clone(),
+        variant.clone(),
+        self.specialize_exprs(fields, type_args, context),
+      ),
+      Node::MethodCall(receiver, method, args) => {
+        // Methods of traits are resolved to the functions of the impl for the type
+        // of the receiver, which is passed as the first argument
+        let program = self.program;
+        let receiver_type = self.type_of(receiver, context);
+        let receiver = self.specialize_expr(receiver, type_args, context);
+        let mut args = self.specialize_exprs(args, type_args, context);
+        match program.impls.iter().find(|r#impl| {
+          r#impl.r#type == receiver_type && r#impl.methods.contains(method)
+        }) {
+          Some(r#impl) => {
+            args.insert(0, receiver);
+            Node::FuncCall(r#impl.method_name(method), args, None)
+          }
+          None => Node::MethodCall(Box::new(receiver), method.clone(), args),
+        }
+      }
+      Node::Op(left, op, right) => Node::Op(
+        Box::new(self.specialize_expr(left, type_args, context)),
+        op.clone(),
+        Box::new(self.specialize_expr(right, type_args, context)),
+      ),
+      Node::Match(scrutinee, arms, _) => Node::Match(
+        Box::new(self.specialize_expr(scrutinee, type_args, context)),
+        self.specialize_arms(scrutinee, arms, true, type_args, context),
+        None,
+      ),
+      Node::FuncCall(func, args, _) => {
+        let (func, args) = self.specialize_call(func, args, type_args, context);
+        Node::FuncCall(func, args, None)
+      }
+      // Functions used as values are closures calling them
+      Node::Var(name) if context.get_var_type(name).is_none() => {
+        match self.program.funcs.get(name) {
+          Some(func) => func.as_closure(),
+          None => unreachable!("Monomorphizing unknown variable {}", name),
+        }
+      }
+      // The return type of a closure is inferred from its body
+      Node::Closure(params, _, body, _) => {
+        let mut params = params.clone();
+        for param in params.iter_mut() {
+          param._type = param._type.substitute(type_args);
+        }
+        context.push(Scope::from(params.clone()));
+        let ret_type = self.type_of(body, context);
+        let body = self.specialize_expr(body, type_args, context);
+        context.pop();
+        Node::Closure(params, Some(ret_type), Box::new(body), None)
+      }
+      _ => expr.clone(),
+    }
+  }
+
+  // Specializes a statement and the instructions following it
+  fn specialize(
+    &mut self,
+    node: &Node,
+    type_args: &HashMap<String, Type>,
+    context: &mut Context<(Type, bool)>,
+  ) -> Node {
+    let mut specialized = match node {
+      Node::Let(name, r#type, mutable, expr, _) => {
+        let r#type = r#type.as_ref().map(|r#type| r#type.substitute(type_args));
+        let var_type = match r#type {
+          Some(ref r#type) => r#type.clone(),
+          None => self.type_of(expr, context),
+        };
+        let expr = self.specialize_expr(expr, type_args, context);
+        context.insert_type(name, var_type, *mutable);
+        Node::Let(name.clone(), r#type, *mutable, Box::new(expr), None)
+      }
+      Node::Declare(name, r#type, mutable, _) => {
+        let r#type = r#type.substitute(type_args);
+        context.insert_type(name, r#type.clone(), *mutable);
+        Node::Declare(name.clone(), r#type, *mutable, None)
+      }
+      Node::LetTuple(names, r#type, expr, _) => {
+        let r#type = r#type.as_ref().map(|r#type| r#type.substitute(type_args));
+        let types = match self.type_of(expr, context) {
+          Type::Tuple(types) => types,
+          r#type => unreachable!("Destructuring value of type {}", r#type.to_str()),
+        };
+        let expr = self.specialize_expr(expr, type_args, context);
+        for ((name, mutable), var_type) in names.iter().zip(types) {
+          context.insert_type(name, var_type, *mutable);
+        }
+        Node::LetTuple(names.clone(), r#type, Box::new(expr), None)
+      }
+      Node::Assign(var, expr, _) => Node::Assign(
+        var.clone(),
+        Box::new(self.specialize_expr(expr, type_args, context)),
+        None,
+      ),
+      Node::IndexAssign(array, index, expr, _) => Node::IndexAssign(
+        Box::new(self.specialize_expr(array, type_args, context)),
+        Box::new(self.specialize_expr(index, type_args, context)),
+        Box::new(self.specialize_expr(expr, type_args, context)),
+        None,
+      ),
+      Node::FieldAssign(r#struct, field, expr, _) => Node::FieldAssign(
+        Box::new(self.specialize_expr(r#struct, type_args, context)),
+        field.clone(),
+        Box::new(self.specialize_expr(expr, type_args, context)),
+        None,
+      ),
+      Node::FuncCall(func, args, _) => {
+        let (func, args) = self.specialize_call(func, args, type_args, context);
+        Node::FuncCall(func, args, None)
+      }
+      Node::If(condition, then_body, else_body, _) => {
+        let condition = self.specialize_expr(condition, type_args, context);
+        let then_body = self.specialize_block(then_body, type_args, context);
+        let else_body = else_body.as_ref().map(|else_body| {
+          Box::new(self.specialize_block(else_body, type_args, context))
+        });
+        Node::If(Box::new(condition), Box::new(then_body), else_body, None)
+      }
+      Node::While(condition, body, _) => Node::While(
+        Box::new(self.specialize_expr(condition, type_args, context)),
+        Box::new(self.specialize_block(body, type_args, context)),
+        None,
+      ),
+      Node::Match(scrutinee, arms, _) => Node::Match(
+        Box::new(self.specialize_expr(scrutinee, type_args, context)),
+        self.specialize_arms(scrutinee, arms, false, type_args, context),
+        None,
+      ),
+      Node::Return(expr, _) => Node::Return(
+        Box::new(self.specialize_expr(expr, type_args, context)),
+        None,
+      ),
+      Node::Print(pieces, args, newline, _) => Node::Print(
+        pieces.clone(),
+        self.specialize_exprs(args, type_args, context),
+        *newline,
+        None,
+      ),
+      Node::Check(condition, message, line, _) => Node::Check(
+        Box::new(self.specialize_expr(condition, type_args, context)),
+        message.clone(),
+        *line,
+        None,
+      ),
+      Node::Panic(pieces, args, line, _) => Node::Panic(
+        pieces.clone(),
+        self.specialize_exprs(args, type_args, context),
+        *line,
+        None,
+      ),
+      Node::DebugContext(_) => Node::DebugContext(None),
+      Node::Empty => return Node::Empty,
+      expr => return self.specialize_expr(expr, type_args, context),
+    };
+
+    // The next instruction is specialized after the variables declared by this
+    // statement are in scope
+    if let Some(next) = node.get_next_instruction() {
+      specialized.attach_next_instruction(self.specialize(next, type_args, context));
+    }
+    specialized
+  }
+
+  // Specializes the body of a block, the variables declared in it go out of scope
+  // at its end
+  fn specialize_block(
+    &mut self,
+    body: &Node,
+    type_args: &HashMap<String, Type>,
+    context: &mut Context<(Type, bool)>,
+  ) -> Node {
+    context.push(Scope::new());
+    let body = self.specialize(body, type_args, context);
+    context.pop();
+    body
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::monomorphize;
+  use crate::types::{node::Node, program::Program};
+  use std::{convert::TryFrom, path::Path};
+
+  #[test]
+  fn specializes_each_instantiation() {
+    let program = Program::try_from(Path::new("tests/samples/generics.rs")).unwrap();
+    let mut names: Vec<String> = monomorphize(&program).keys().cloned().collect();
+    names.sort();
+    assert_eq!(
+      names,
+      vec![
+        "identity<&str>",
+        "identity<Point>",
+        "main",
+        "nested<&str>",
+        "pick<char>",
+        "pick<i32>",
+        "swap<bool, i32>",
+      ]
+    );
+  }
+
+  #[test]
+  fn resolves_trait_methods_to_impls() {
+    let program = Program::try_from(Path::new("tests/samples/traits.rs")).unwrap();
+    let funcs = monomorphize(&program);
+    assert_eq!(
+      funcs["doubled_area<i32>"].body_start,
+      Node::Return(
+        Box::new(Node::FuncCall(
+          "<i32 as Area>::area".to_string(),
+          vec![Node::FuncCall(
+            "<i32 as Area>::scaled".to_string(),
+            vec![Node::Var("shape".to_string()), Node::Number(2)],
+            None
+          )],
+          None
+        )),
+        None
+      )
+    );
+  }
+}
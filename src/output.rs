@@ -0,0 +1,25 @@
+// Output of the program being run, written the same way by the interpreter and by
+// compiled code. It goes to stdout unless captured, which tests do to compare what
+// the two print.
+use std::cell::RefCell;
+
+thread_local! {
+  static CAPTURED: RefCell<Option<String>> = RefCell::new(None);
+}
+
+pub fn write(s: &str) {
+  CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+    Some(captured) => captured.push_str(s),
+    None => print!("{}", s),
+  })
+}
+
+// Runs f and returns its result along with what the program printed meanwhile,
+// compiled code has to be called on the same thread
+#[allow(dead_code)]
+pub fn capture<T>(f: impl FnOnce() -> T) -> (T, String) {
+  CAPTURED.with(|captured| *captured.borrow_mut() = Some(String::new()));
+  let result = f();
+  let output = CAPTURED.with(|captured| captured.borrow_mut().take());
+  (result, output.unwrap_or_default())
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use crate::types::{_type::Type, func::Func};
+
+// Type aliases are resolved when parsing, every use of an alias becomes a
+// Type::Alias holding the name as written along with the type it stands for. The
+// type parameters of a generic function shadow aliases of the same name. An alias
+// referring to itself is left unresolved where it refers to itself, the type checker
+// reports the cycle.
+pub fn resolve_type(
+  r#type: &Type,
+  aliases: &HashMap<String, Type>,
+  type_params: &Vec<(String, Vec<String>)>,
+) -> Type {
+  resolve_with(r#type, aliases, type_params, &mut vec![])
+}
+
+fn resolve_with(
+  r#type: &Type,
+  aliases: &HashMap<String, Type>,
+  type_params: &Vec<(String, Vec<String>)>,
+  resolving: &mut Vec<String>,
+) -> Type {
+  match r#type {
+    Type::Named(name) if type_params.iter().any(|(param, _)| param == name) => {
+      r#type.clone()
+    }
+    Type::Named(name) if !resolving.contains(name) => match aliases.get(name) {
+      Some(aliased) => {
+        resolving.push(name.clone());
+        let aliased = resolve_with(aliased, aliases, &vec![], resolving);
+        resolving.pop();
+        Type::Alias(name.clone(), Box::new(aliased))
+      }
+      None => r#type.clone(),
+    },
+    Type::Array(r#type, len) => Type::Array(
+      Box::new(resolve_with(r#type, aliases, type_params, resolving)),
+      *len,
+    ),
+    Type::Tuple(types) => Type::Tuple(
+      types
+        .iter()
+        .map(|r#type| resolve_with(r#type, aliases, type_params, resolving))
+        .collect(),
+    ),
+    Type::Func(params, ret_type) => Type::Func(
+      params
+        .iter()
+        .map(|r#type| resolve_with(r#type, aliases, type_params, resolving))
+        .collect(),
+      Box::new(resolve_with(ret_type, aliases, type_params, resolving)),
+    ),
+    _ => r#type.clone(),
+  }
+}
+
+// Resolves the aliases in the signature of a function and in the types of the
+// variables and closures of its body
+pub fn resolve_func(func: &mut Func, aliases: &HashMap<String, Type>) {
+  for param in func.params.iter_mut() {
+    param._type = resolve_type(&param._type, aliases, &func.type_params);
+  }
+  func.ret_type = resolve_type(&func.ret_type, aliases, &func.type_params);
+  let type_params = &func.type_params;
+  func
+    .body_start
+    .map_types(&|r#type| resolve_type(r#type, aliases, type_params));
+}
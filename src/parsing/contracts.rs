@@ -0,0 +1,190 @@
+use crate::types::{_type::Type, func::Func, item::Item, node::Node};
+
+// Contracts are attributes checked while the program runs, each one becomes a
+// Node::Check when parsing:
+// - #[requires(c)] on a function is checked when the function is called,
+// - #[ensures(c)] on a function whenever it returns, with the value it returns
+//   bound to result,
+// - #[invariant(c)] on a while loop before the loop and after each iteration.
+
+// The variable the value returned by a function is bound to in its postconditions
+const RESULT: &str = "result";
+
+// An attribute, #[name] or #[name(condition)]
+pub struct Attribute {
+  // Byte offset of the attribute in the file
+  pub location: usize,
+  pub name: String,
+  // The condition as a Node::Check, reporting the contract when it does not hold
+  pub condition: Option<Node>,
+}
+
+// The message reported when the condition of a contract does not hold
+pub fn violation_message(name: &str, condition: &str) -> String {
+  match name {
+    "requires" => format!("precondition violated: {}", condition),
+    "ensures" => format!("postcondition violated: {}", condition),
+    "invariant" => format!("loop invariant violated: {}", condition),
+    _ => format!("{} violated: {}", name, condition),
+  }
+}
+
+// The item a function with attributes declares, its contracts checked in its body
+pub fn func_item(
+  attributes: Vec<Attribute>,
+  func: Func,
+) -> Result<Item, (usize, &'static str)> {
+  let mut func = func;
+  let mut is_test = false;
+  let mut requires = vec![];
+  let mut ensures = vec![];
+  for attribute in attributes {
+    match (attribute.name.as_str(), attribute.condition) {
+      ("test", None) => is_test = true,
+      ("requires", Some(check)) => requires.push(check),
+      ("ensures", Some(check)) => ensures.push(check),
+      ("test", Some(_)) => {
+        return Err((attribute.location, "#[test] takes no condition"))
+      }
+      ("requires", None) | ("ensures", None) => {
+        return Err((
+          attribute.location,
+          "Contracts take a condition, e.g. #[requires(n > 0)]",
+        ))
+      }
+      _ => {
+        return Err((
+          attribute.location,
+          "Unknown attribute, expected test, requires or ensures",
+        ))
+      }
+    }
+  }
+
+  if ensures.len() != 0 {
+    let returns_unit = func.ret_type == Type::Unit;
+    check_returns(&mut func.body_start, &ensures, returns_unit);
+    // A function without a return value may return by reaching the end of its body
+    if returns_unit && !ends_with_return(&func.body_start) {
+      func.body_start = append(func.body_start, chain(&ensures, None));
+    }
+  }
+  if requires.len() != 0 {
+    let body = std::mem::replace(&mut func.body_start, Node::Empty);
+    func.body_start = chain(&requires, Some(body));
+  }
+
+  Ok(match is_test {
+    true => Item::Test(func),
+    false => Item::Func(func),
+  })
+}
+
+// A while loop with attributes, its invariants checked before the loop and at the end
+// of each iteration
+pub fn while_loop(
+  attributes: Vec<Attribute>,
+  while_loop: Node,
+) -> Result<Node, (usize, &'static str)> {
+  let mut invariants = vec![];
+  for attribute in attributes {
+    match (attribute.name.as_str(), attribute.condition) {
+      ("invariant", Some(check)) => invariants.push(check),
+      ("invariant", None) => {
+        return Err((
+          attribute.location,
+          "#[invariant] takes a condition, e.g. #[invariant(i <= n)]",
+        ))
+      }
+      _ => return Err((attribute.location, "Unknown attribute, expected invariant")),
+    }
+  }
+
+  match while_loop {
+    Node::While(condition, body, next) => {
+      let body = match ends_with_return(&body) {
+        true => *body,
+        false => append(*body, chain(&invariants, None)),
+      };
+      let while_loop = Node::While(condition, Box::new(body), next);
+      Ok(chain(&invariants, Some(while_loop)))
+    }
+    _ => unreachable!("Invariants are only parsed on while loops"),
+  }
+}
+
+// The checks followed by the given instructions
+fn chain(checks: &Vec<Node>, next: Option<Node>) -> Node {
+  let mut node = next;
+  for check in checks.iter().rev() {
+    let mut check = check.clone();
+    if let Some(next) = node {
+      check.attach_next_instruction(next);
+    }
+    node = Some(check);
+  }
+  node.unwrap_or(Node::Empty)
+}
+
+fn append(body: Node, next: Node) -> Node {
+  match body {
+    Node::Empty => next,
+    mut body => {
+      body.append_instruction(next);
+      body
+    }
+  }
+}
+
+fn ends_with_return(body: &Node) -> bool {
+  if let Node::Empty = body {
+    return false;
+  }
+  let mut last = body;
+  while let Some(next) = last.get_next_instruction() {
+    last = next;
+  }
+  match last {
+    Node::Return(..) => true,
+    _ => false,
+  }
+}
+
+// Checks the postconditions before each return of a function body, the returned
+// value is bound to result first. Nested functions and closures return from
+// themselves and are left as they are.
+fn check_returns(node: &mut Node, ensures: &Vec<Node>, returns_unit: bool) {
+  match node {
+    Node::Return(expr, next) => {
+      if let Some(next) = next {
+        check_returns(next, ensures, returns_unit);
+      }
+      let expr = std::mem::replace(expr, Box::new(Node::Empty));
+      let next = next.take();
+      *node = match returns_unit {
+        true => chain(ensures, Some(Node::Return(expr, next))),
+        false => {
+          let ret = Node::Return(Box::new(Node::Var(RESULT.to_string())), next);
+          Node::Let(
+            RESULT.to_string(),
+            None,
+            false,
+            expr,
+            Some(Box::new(chain(ensures, Some(ret)))),
+          )
+        }
+      };
+    }
+    Node::FuncDec(_, next) => {
+      if let Some(next) = next {
+        check_returns(next, ensures, returns_unit);
+      }
+    }
+    Node::Closure(..) => (),
+    node => {
+      for child in node.children_mut() {
+        check_returns(child, ensures, returns_unit);
+      }
+    }
+  }
+}
@@ -1,7 +1,10 @@
-use crate::{errors::parse_error::ParseError, types::func::Func};
-use std::collections::HashMap;
+use crate::{
+  errors::{parse_error::ParseError, parse_warning::ParseWarning},
+  types::item::Item,
+};
 
-fn get_error_line_from_byte_offset(
+// Line number, text of the line and offset in the line of a byte of a file
+pub fn get_error_line_from_byte_offset(
   file: &str,
   error_index: usize,
 ) -> (usize, String, usize) {
@@ -28,10 +31,14 @@ fn get_error_line_from_byte_offset(
   return (line_number, error_line, error_offset);
 }
 
-pub fn parse(file: &str) -> Result<HashMap<String, Func>, ParseError> {
-  let res = crate::parsing::grammar::FileParser::new().parse(file);
+// Parses the items of a file along with whether they are public
+pub fn parse(
+  file: &str,
+) -> Result<(Vec<(bool, Item)>, Vec<ParseWarning>), ParseError> {
+  let mut warnings = vec![];
+  let res = crate::parsing::grammar::FileParser::new().parse(&mut warnings, file);
   return match res {
-    Ok(s) => Ok(s),
+    Ok(s) => Ok((s, warnings)),
     Err(e) => match e {
       lalrpop_util::ParseError::InvalidToken { location } => {
         let (err_line_num, err_string, err_offset) =
@@ -54,6 +61,16 @@ pub fn parse(file: &str) -> Result<HashMap<String, Func>, ParseError> {
           expected_tokens: expected,
         });
       }
+      lalrpop_util::ParseError::User { error } => {
+        let (location, message) = error;
+        let (err_line_num, err_string, err_offset) =
+          get_error_line_from_byte_offset(&file, location);
+        return Err(ParseError::Invalid{location: err_offset,
+                line: err_string,
+                line_num: err_line_num,
+                message: message.to_string(),
+                });
+      }
       _ => unimplemented!("Unsupported lalrpop error message"),
     },
   };
@@ -78,4 +95,28 @@ mod tests {
     )
     .is_ok())
   }
+
+  #[test]
+  pub fn test_parse_deprecated_mut_param() {
+    let (_, warnings) = parse("fn f(n: mut i32, mut m: i32) {}").unwrap();
+    assert_eq!(warnings.len(), 1);
+  }
+
+  #[test]
+  pub fn test_parse_modules() {
+    let (items, _) = parse(
+      "
+            mod shapes;
+            pub mod util {
+                pub fn double(n: i32) -> i32 { return n * 2; }
+            }
+            use util::double;
+            fn main() -> i32 {
+                return util::double(1) + shapes::Shape::Dot;
+            }",
+    )
+    .unwrap();
+    let public: Vec<bool> = items.iter().map(|(public, _)| *public).collect();
+    assert_eq!(public, vec![false, true, false, false]);
+  }
 }
@@ -0,0 +1,351 @@
+use crate::types::{func::Func, node::Node, pattern::Pattern};
+
+// Functions can be declared in the body of another function, where they are
+// visible in the whole block they are declared in. As they do not capture the
+// variables of the enclosing function they are moved out of it into functions of
+// their own, named after the function they are declared in, e.g. main#helper, which
+// can not be the path of an item. Calls to them are
+// renamed accordingly, unless a variable of the same name shadows them. Names
+// not declared in the function are looked up with items, which gives the path of
+// the item of the module they refer to.
+pub fn hoist_nested_funcs(
+  func: Func,
+  items: &dyn Fn(&str) -> Option<String>,
+) -> Vec<Func> {
+  let mut hoister = Hoister {
+    funcs: vec![],
+    taken: vec![],
+    items: items,
+  };
+  hoister.hoist_func(func, &vec![]);
+  hoister.funcs
+}
+
+// Resolves the names of an expression outside of a function, e.g. the initializer
+// of a constant
+pub fn resolve_names(expr: &Node, items: &dyn Fn(&str) -> Option<String>) -> Node {
+  let mut hoister = Hoister {
+    funcs: vec![],
+    taken: vec![],
+    items: items,
+  };
+  hoister.resolve_expr(expr, "", &mut vec![])
+}
+
+// Names in scope, the names of nested functions map to the name they are hoisted
+// under while variables map to None
+type Names = Vec<(String, Option<String>)>;
+
+struct Hoister<'a> {
+  funcs: Vec<Func>,
+  // Names given to the nested functions so far
+  taken: Vec<String>,
+  items: &'a dyn Fn(&str) -> Option<String>,
+}
+
+impl<'a> Hoister<'a> {
+  // Hoists the functions declared in the body of a function. The function can
+  // call the nested functions in scope where it is declared but can not refer to
+  // the variables there.
+  fn hoist_func(&mut self, mut func: Func, names: &Names) {
+    let mut names: Names = names
+      .iter()
+      .filter(|(_, hoisted)| hoisted.is_some())
+      .cloned()
+      .collect();
+    names.extend(func.params.iter().map(|param| (param.name.clone(), None)));
+    func.body_start = self.hoist_block(&func.body_start, &func.name, &mut names);
+    self.funcs.push(func);
+  }
+
+  // A nested function declared twice under the same path, in different blocks,
+  // is told apart by a number
+  fn unique_name(&mut self, path: &str, name: &str) -> String {
+    let mut unique = format!("{}#{}", path, name);
+    let mut n = 1;
+    while self.taken.contains(&unique) {
+      n += 1;
+      unique = format!("{}#{}#{}", path, name, n);
+    }
+    self.taken.push(unique.clone());
+    unique
+  }
+
+  fn hoist_block(&mut self, body: &Node, path: &str, names: &mut Names) -> Node {
+    let len = names.len();
+
+    // The functions declared in a block are in scope before their declaration
+    let mut nested = vec![];
+    let mut next = Some(body);
+    while let Some(node) = next {
+      if let Node::FuncDec(func, _) = node {
+        let name = self.unique_name(path, &func.name);
+        names.push((func.name.clone(), Some(name.clone())));
+        nested.push((func, name));
+      }
+      next = match node {
+        Node::Empty => None,
+        node => node.get_next_instruction(),
+      };
+    }
+    for (func, name) in nested {
+      let mut func = (**func).clone();
+      func.name = name;
+      self.hoist_func(func, names);
+    }
+
+    let body = self.resolve(body, path, names);
+    names.truncate(len);
+    body
+  }
+
+  // The name a call or variable refers to, the innermost declaration wins
+  fn lookup(&self, name: &str, names: &Names) -> String {
+    match names.iter().rev().find(|(declared, _)| declared == name) {
+      Some((_, Some(hoisted))) => hoisted.clone(),
+      Some((_, None)) => name.to_string(),
+      None => (self.items)(name).unwrap_or(name.to_string()),
+    }
+  }
+
+  fn resolve_arms(
+    &mut self,
+    arms: &Vec<(Pattern, Option<Node>, Node)>,
+    is_expr: bool,
+    path: &str,
+    names: &mut Names,
+  ) -> Vec<(Pattern, Option<Node>, Node)> {
+    let mut resolved = vec![];
+    for (pattern, guard, body) in arms.iter() {
+      let len = names.len();
+      names.extend(pattern.bindings().into_iter().map(|name| (name, None)));
+      let guard = guard
+        .as_ref()
+        .map(|guard| self.resolve_expr(guard, path, names));
+      let body = if is_expr {
+        self.resolve_expr(body, path, names)
+      } else {
+        self.hoist_block(body, path, names)
+      };
+      names.truncate(len);
+      resolved.push((pattern.clone(), guard, body));
+    }
+    resolved
+  }
+
+  fn resolve_exprs(
+    &mut self,
+    exprs: &Vec<Node>,
+    path: &str,
+    names: &mut Names,
+  ) -> Vec<Node> {
+    exprs
+      .iter()
+      .map(|expr| self.resolve_expr(expr, path, names))
+      .collect()
+  }
+
+  fn resolve_expr(&mut self, expr: &Node, path: &str, names: &mut Names) -> Node {
+    match expr {
+      Node::Var(name) => Node::Var(self.lookup(name, names)),
+      Node::FuncCall(func, args, _) => Node::FuncCall(
+        self.lookup(func, names),
+        self.resolve_exprs(args, path, names),
+        None,
+      ),
+      Node::Array(elements) => Node::Array(self.resolve_exprs(elements, path, names)),
+      Node::ArrayRepeat(element, len) => {
+        Node::ArrayRepeat(Box::new(self.resolve_expr(element, path, names)), *len)
+      }
+      Node::Index(array, index) => Node::Index(
+        Box::new(self.resolve_expr(array, path, names)),
+        Box::new(self.resolve_expr(index, path, names)),
+      ),
+      Node::Tuple(elements) => Node::Tuple(self.resolve_exprs(elements, path, names)),
+      Node::TupleField(tuple, field) => {
+        Node::TupleField(Box::new(self.resolve_expr(tuple, path, names)), *field)
+      }
+      Node::Struct(name, fields) => Node::Struct(
+        name.clone(),
+        fields
+          .iter()
+          .map(|(field, expr)| (field.clone(), self.resolve_expr(expr, path, names)))
+          .collect(),
+      ),
+      Node::Field(r#struct, field) => Node::Field(
+        Box::new(self.resolve_expr(r#struct, path, names)),
+        field.clone(),
+      ),
+      Node::Variant(r#enum, variant, fields) => Node::Variant(
+        r#enum.clone(),
+        variant.clone(),
+        self.resolve_exprs(fields, path, names),
+      ),
+      Node::MethodCall(receiver, method, args) => Node::MethodCall(
+        Box::new(self.resolve_expr(receiver, path, names)),
+        method.clone(),
+        self.resolve_exprs(args, path, names),
+      ),
+      Node::Op(left, op, right) => Node::Op(
+        Box::new(self.resolve_expr(left, path, names)),
+        op.clone(),
+        Box::new(self.resolve_expr(right, path, names)),
+      ),
+      Node::Match(scrutinee, arms, _) => Node::Match(
+        Box::new(self.resolve_expr(scrutinee, path, names)),
+        self.resolve_arms(arms, true, path, names),
+        None,
+      ),
+      Node::Closure(params, ret_type, body, captures) => {
+        let len = names.len();
+        names.extend(params.iter().map(|param| (param.name.clone(), None)));
+        let body = self.resolve_expr(body, path, names);
+        names.truncate(len);
+        Node::Closure(
+          params.clone(),
+          ret_type.clone(),
+          Box::new(body),
+          captures.clone(),
+        )
+      }
+      _ => expr.clone(),
+    }
+  }
+
+  // Resolves a statement and the instructions following it, leaving out the
+  // declarations of nested functions
+  fn resolve(&mut self, node: &Node, path: &str, names: &mut Names) -> Node {
+    let mut resolved = match node {
+      Node::FuncDec(_, next) => {
+        return match next {
+          Some(next) => self.resolve(next, path, names),
+          None => Node::Empty,
+        }
+      }
+      Node::Let(name, r#type, mutable, expr, _) => {
+        let expr = self.resolve_expr(expr, path, names);
+        names.push((name.clone(), None));
+        Node::Let(name.clone(), r#type.clone(), *mutable, Box::new(expr), None)
+      }
+      Node::Declare(name, r#type, mutable, _) => {
+        names.push((name.clone(), None));
+        Node::Declare(name.clone(), r#type.clone(), *mutable, None)
+      }
+      Node::LetTuple(vars, r#type, expr, _) => {
+        let expr = self.resolve_expr(expr, path, names);
+        names.extend(vars.iter().map(|(name, _)| (name.clone(), None)));
+        Node::LetTuple(vars.clone(), r#type.clone(), Box::new(expr), None)
+      }
+      Node::Assign(var, expr, _) => Node::Assign(
+        self.lookup(var, names),
+        Box::new(self.resolve_expr(expr, path, names)),
+        None,
+      ),
+      Node::IndexAssign(array, index, expr, _) => Node::IndexAssign(
+        Box::new(self.resolve_expr(array, path, names)),
+        Box::new(self.resolve_expr(index, path, names)),
+        Box::new(self.resolve_expr(expr, path, names)),
+        None,
+      ),
+      Node::FieldAssign(r#struct, field, expr, _) => Node::FieldAssign(
+        Box::new(self.resolve_expr(r#struct, path, names)),
+        field.clone(),
+        Box::new(self.resolve_expr(expr, path, names)),
+        None,
+      ),
+      Node::FuncCall(..) => self.resolve_expr(node, path, names),
+      Node::If(condition, then_body, else_body, _) => {
+        let condition = self.resolve_expr(condition, path, names);
+        let then_body = self.hoist_block(then_body, path, names);
+        let else_body = else_body
+          .as_ref()
+          .map(|else_body| Box::new(self.hoist_block(else_body, path, names)));
+        Node::If(Box::new(condition), Box::new(then_body), else_body, None)
+      }
+      Node::While(condition, body, _) => Node::While(
+        Box::new(self.resolve_expr(condition, path, names)),
+        Box::new(self.hoist_block(body, path, names)),
+        None,
+      ),
+      Node::Match(scrutinee, arms, _) => Node::Match(
+        Box::new(self.resolve_expr(scrutinee, path, names)),
+        self.resolve_arms(arms, false, path, names),
+        None,
+      ),
+      Node::Return(expr, _) => {
+        Node::Return(Box::new(self.resolve_expr(expr, path, names)), None)
+      }
+      Node::Print(pieces, args, newline, _) => Node::Print(
+        pieces.clone(),
+        self.resolve_exprs(args, path, names),
+        *newline,
+        None,
+      ),
+      Node::Check(condition, message, line, _) => Node::Check(
+        Box::new(self.resolve_expr(condition, path, names)),
+        message.clone(),
+        *line,
+        None,
+      ),
+      Node::Panic(pieces, args, line, _) => Node::Panic(
+        pieces.clone(),
+        self.resolve_exprs(args, path, names),
+        *line,
+        None,
+      ),
+      Node::DebugContext(_) => Node::DebugContext(None),
+      Node::Empty => return Node::Empty,
+      expr => return self.resolve_expr(expr, path, names),
+    };
+
+    // A block ending with a nested function resolves to an empty tail, which is
+    // left out
+    if let Some(next) = node.get_next_instruction() {
+      match self.resolve(next, path, names) {
+        Node::Empty => (),
+        next => resolved.attach_next_instruction(next),
+      }
+    }
+    resolved
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::types::{node::Node, program::Program};
+  use std::{convert::TryFrom, path::Path};
+
+  #[test]
+  fn hoists_nested_functions_under_their_path() {
+    let program =
+      Program::try_from(Path::new("tests/samples/nested_functions.rs")).unwrap();
+    let mut names: Vec<&String> = program.funcs.keys().collect();
+    names.sort();
+    assert_eq!(
+      names,
+      vec![
+        "helper",
+        "main",
+        "main#first",
+        "main#helper",
+        "main#helper#2",
+        "main#outer",
+        "main#outer#inner",
+        "main#square",
+        "main::helper",
+      ]
+    );
+    // Calls are renamed to the nested function in scope
+    assert_eq!(
+      program.funcs["main#outer"].body_start,
+      Node::Return(
+        Box::new(Node::FuncCall(
+          "main#outer#inner".to_string(),
+          vec![],
+          None
+        )),
+        None
+      )
+    );
+  }
+}
@@ -0,0 +1,43 @@
+// Resolves the escapes in the contents of a string or char literal
+pub fn unescape(literal: &str) -> Result<String, &'static str> {
+  let mut unescaped = String::new();
+  let mut chars = literal.chars();
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      unescaped.push(c);
+      continue;
+    }
+    match chars.next() {
+      Some('n') => unescaped.push('\n'),
+      Some('r') => unescaped.push('\r'),
+      Some('t') => unescaped.push('\t'),
+      Some('\\') => unescaped.push('\\'),
+      Some('\'') => unescaped.push('\''),
+      Some('"') => unescaped.push('"'),
+      _ => return Err("Unknown character escape"),
+    }
+  }
+  Ok(unescaped)
+}
+
+// Splits a format string into the text around its {} placeholders, so there is one
+// more piece than there are placeholders. Braces are written as {{ and }}.
+pub fn parse_format(format: &str) -> Result<Vec<String>, &'static str> {
+  let mut pieces = vec![String::new()];
+  let mut chars = format.chars().peekable();
+  while let Some(c) = chars.next() {
+    match (c, chars.peek()) {
+      ('{', Some('{')) | ('}', Some('}')) => {
+        chars.next();
+        pieces.last_mut().unwrap().push(c);
+      }
+      ('{', Some('}')) => {
+        chars.next();
+        pieces.push(String::new());
+      }
+      ('{', _) | ('}', _) => return Err("Invalid format string, only {} placeholders are supported"),
+      _ => pieces.last_mut().unwrap().push(c),
+    }
+  }
+  Ok(pieces)
+}
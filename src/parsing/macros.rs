@@ -0,0 +1,850 @@
+use std::collections::HashMap;
+
+use crate::{
+  errors::parse_error::ParseError,
+  parsing::{
+    file_parser::get_error_line_from_byte_offset,
+    grammar::{ExprParser, InstructionsParser},
+  },
+  types::{
+    _type::Type,
+    item::Item,
+    macro_dec::{MacroDec, TokenTree},
+    node::Node,
+    pattern::Pattern,
+  },
+};
+
+// Macros declared with macro_rules! are expanded once the file declaring them is
+// parsed, they can be invoked anywhere in the file. The tokens of an invocation are
+// matched against the matcher of each rule in turn, the first one matching is
+// transcribed and the resulting tokens parsed as an expression or as statements,
+// depending on where the macro is invoked.
+
+// Expansions invoking macros are expanded in turn, up to this depth
+const RECURSION_LIMIT: usize = 64;
+
+// Words that do not match $name:ident
+const KEYWORDS: [&str; 30] = [
+  "Fn",
+  "Self",
+  "bool",
+  "char",
+  "const",
+  "else",
+  "enum",
+  "false",
+  "fn",
+  "for",
+  "i32",
+  "if",
+  "impl",
+  "let",
+  "macro_rules",
+  "match",
+  "mod",
+  "move",
+  "mut",
+  "pub",
+  "return",
+  "self",
+  "static",
+  "str",
+  "struct",
+  "trait",
+  "true",
+  "type",
+  "use",
+  "while",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Fragment {
+  Expr,
+  Ident,
+  Tt,
+}
+
+enum Matcher {
+  Token(String),
+  // Opening delimiter, matchers of the tokens between the delimiters
+  Group(char, Vec<Matcher>),
+  // Name and kind of a fragment, $name:kind
+  Fragment(String, Fragment),
+  // Matchers repeated, separator and repetition operator, $( ... ),*
+  Repeat(Vec<Matcher>, Option<String>, char),
+}
+
+#[derive(Debug, Clone)]
+enum Binding {
+  Fragment(Vec<TokenTree>, Fragment),
+  // The bindings of each repetition of the matchers the fragment is in
+  Repeated(Vec<Bindings>),
+}
+
+type Bindings = HashMap<String, Binding>;
+
+// The matcher of a rule and the tokens it transcribes to
+type Rule = (Vec<Matcher>, Vec<TokenTree>);
+
+// The locals a macro introduces by the placeholders they are transcribed to, along
+// with their name and the fresh name they are given
+type Renamed = HashMap<String, (String, String)>;
+
+struct Expander<'a> {
+  // Matchers and transcribers of the rules of each macro
+  macros: HashMap<String, Vec<Rule>>,
+  file: &'a str,
+  // Number of expansions so far, the locals they introduce are named after it
+  expansions: usize,
+}
+
+// Expands the macro invocations of the items of a file, the declarations of the
+// macros are left out
+pub fn expand_macros(
+  items: Vec<(bool, Item)>,
+  file: &str,
+) -> Result<Vec<(bool, Item)>, ParseError> {
+  let mut expander = Expander {
+    macros: HashMap::new(),
+    file: file,
+    expansions: 0,
+  };
+  if let Err(e) = expander.declare(&items) {
+    return Err(e);
+  }
+  expander.expand_items(items)
+}
+
+fn is_ident(token: &str) -> bool {
+  let mut chars = token.chars();
+  match chars.next() {
+    Some(c) if c.is_alphabetic() || c == '_' => {
+      chars.all(|c| c.is_alphanumeric() || c == '_') && !KEYWORDS.contains(&token)
+    }
+    _ => false,
+  }
+}
+
+fn parse_matchers(tokens: &[TokenTree]) -> Result<Vec<Matcher>, String> {
+  let mut matchers = vec![];
+  let mut i = 0;
+  while i < tokens.len() {
+    let token = |i: usize| match tokens.get(i) {
+      Some(TokenTree::Token(token)) => Some(token.as_str()),
+      _ => None,
+    };
+    match (&tokens[i], tokens.get(i + 1)) {
+      (TokenTree::Token(dollar), Some(TokenTree::Group('(', repeated)))
+        if dollar == "$" =>
+      {
+        let repeated = match parse_matchers(repeated) {
+          Ok(repeated) => repeated,
+          Err(e) => return Err(e),
+        };
+        let (separator, op, len) = match (token(i + 2), token(i + 3)) {
+          (Some(op @ "*"), _) | (Some(op @ "+"), _) | (Some(op @ "?"), _) => {
+            (None, op, 3)
+          }
+          (Some(separator), Some(op @ "*")) | (Some(separator), Some(op @ "+")) => {
+            (Some(separator.to_string()), op, 4)
+          }
+          _ => return Err("Expected * or + after the repetition".to_string()),
+        };
+        matchers.push(Matcher::Repeat(
+          repeated,
+          separator,
+          op.chars().next().unwrap(),
+        ));
+        i += len;
+      }
+      (TokenTree::Token(dollar), _) if dollar == "$" => {
+        let fragment = match (token(i + 1), token(i + 2), token(i + 3)) {
+          (Some(name), Some(":"), Some(kind)) if is_ident(name) => match kind {
+            "expr" => Matcher::Fragment(name.to_string(), Fragment::Expr),
+            "ident" => Matcher::Fragment(name.to_string(), Fragment::Ident),
+            "tt" => Matcher::Fragment(name.to_string(), Fragment::Tt),
+            kind => {
+              return Err(format!(
+                "Unknown fragment specifier {}, expected expr, ident or tt",
+                kind
+              ))
+            }
+          },
+          _ => {
+            return Err(
+              "Expected a fragment, e.g. $x:expr, or a repetition after $".to_string(),
+            )
+          }
+        };
+        matchers.push(fragment);
+        i += 4;
+      }
+      (TokenTree::Token(token), _) => {
+        matchers.push(Matcher::Token(token.clone()));
+        i += 1;
+      }
+      (TokenTree::Group(delimiter, tokens), _) => {
+        match parse_matchers(tokens) {
+          Ok(group) => matchers.push(Matcher::Group(*delimiter, group)),
+          Err(e) => return Err(e),
+        }
+        i += 1;
+      }
+    }
+  }
+  Ok(matchers)
+}
+
+// Names of the fragments bound by matchers
+fn fragment_names(matchers: &[Matcher]) -> Vec<String> {
+  matchers
+    .iter()
+    .flat_map(|matcher| match matcher {
+      Matcher::Fragment(name, _) => vec![name.clone()],
+      Matcher::Group(_, matchers) | Matcher::Repeat(matchers, ..) => {
+        fragment_names(matchers)
+      }
+      Matcher::Token(_) => vec![],
+    })
+    .collect()
+}
+
+// The locals introduced by let statements written in a transcriber, the bindings of
+// a tuple included
+fn introduced_locals(tokens: &[TokenTree]) -> Vec<String> {
+  let mut locals = vec![];
+  for (i, token) in tokens.iter().enumerate() {
+    match token {
+      TokenTree::Token(token) if token == "let" => {
+        let mut binding = &tokens[(i + 1).min(tokens.len())..];
+        if let Some(TokenTree::Token(token)) = binding.first() {
+          if token == "mut" {
+            binding = &binding[1..];
+          }
+        }
+        match binding.first() {
+          Some(TokenTree::Token(name)) if is_ident(name) => locals.push(name.clone()),
+          Some(TokenTree::Group('(', names)) => {
+            locals.extend(names.iter().filter_map(|name| match name {
+              TokenTree::Token(name) if is_ident(name) => Some(name.clone()),
+              _ => None,
+            }))
+          }
+          _ => (),
+        }
+      }
+      TokenTree::Group(_, tokens) => locals.extend(introduced_locals(tokens)),
+      _ => (),
+    }
+  }
+  locals
+}
+
+fn parses_as_expr(tokens: &[TokenTree]) -> bool {
+  ExprParser::new()
+    .parse(&mut vec![], &TokenTree::to_source(&tokens.to_vec()))
+    .is_ok()
+}
+
+// All the ways the matchers match a prefix of the tokens, as the fragments they
+// bind and the number of tokens matched
+fn match_prefix(matchers: &[Matcher], tokens: &[TokenTree]) -> Vec<(Bindings, usize)> {
+  let mut states = vec![(HashMap::new(), 0)];
+  for matcher in matchers {
+    let mut next = vec![];
+    for (bindings, matched) in states {
+      for (bound, len) in match_one(matcher, &tokens[matched..]) {
+        let mut bindings = bindings.clone();
+        bindings.extend(bound);
+        next.push((bindings, matched + len));
+      }
+    }
+    states = next;
+  }
+  states
+}
+
+fn match_one(matcher: &Matcher, tokens: &[TokenTree]) -> Vec<(Bindings, usize)> {
+  let bind = |name: &String, tokens: &[TokenTree], kind: Fragment| {
+    let mut bindings = HashMap::new();
+    bindings.insert(name.clone(), Binding::Fragment(tokens.to_vec(), kind));
+    (bindings, tokens.len())
+  };
+  match (matcher, tokens.first()) {
+    (Matcher::Token(expected), Some(TokenTree::Token(token))) if expected == token => {
+      vec![(HashMap::new(), 1)]
+    }
+    (Matcher::Group(expected, matchers), Some(TokenTree::Group(delimiter, tokens)))
+      if expected == delimiter =>
+    {
+      match_prefix(matchers, tokens)
+        .into_iter()
+        .filter(|(_, len)| *len == tokens.len())
+        .map(|(bindings, _)| (bindings, 1))
+        .collect()
+    }
+    (Matcher::Fragment(name, Fragment::Ident), Some(TokenTree::Token(token)))
+      if is_ident(token) =>
+    {
+      vec![bind(name, &tokens[..1], Fragment::Ident)]
+    }
+    (Matcher::Fragment(name, Fragment::Tt), Some(_)) => {
+      vec![bind(name, &tokens[..1], Fragment::Tt)]
+    }
+    // As in Rust an expression extends up to the first ,, ; or => outside of
+    // delimiters, or to the end of the tokens, and is parsed once
+    (Matcher::Fragment(name, Fragment::Expr), _) => {
+      let len = tokens
+        .iter()
+        .position(|token| match token {
+          TokenTree::Token(token) => token == "," || token == ";" || token == "=>",
+          TokenTree::Group(..) => false,
+        })
+        .unwrap_or(tokens.len());
+      match len != 0 && parses_as_expr(&tokens[..len]) {
+        true => vec![bind(name, &tokens[..len], Fragment::Expr)],
+        false => vec![],
+      }
+    }
+    (Matcher::Repeat(matchers, separator, op), _) => {
+      let names = fragment_names(matchers);
+      let mut matches = vec![];
+      let mut repetitions: Vec<(Vec<Bindings>, usize)> = vec![(vec![], 0)];
+      while repetitions.len() != 0 {
+        let mut next = vec![];
+        for (repeated, matched) in repetitions {
+          if *op != '+' || repeated.len() != 0 {
+            let bindings = names
+              .iter()
+              .map(|name| (name.clone(), Binding::Repeated(repeated.clone())))
+              .collect();
+            matches.push((bindings, matched));
+          }
+          if *op == '?' && repeated.len() == 1 {
+            continue;
+          }
+          let mut start = matched;
+          if let (Some(separator), true) = (separator, repeated.len() != 0) {
+            match tokens.get(start) {
+              Some(TokenTree::Token(token)) if token == separator => start += 1,
+              _ => continue,
+            }
+          }
+          // Each repetition matches at least one token, which ends the loop
+          for (bindings, len) in match_prefix(matchers, &tokens[start..]) {
+            if len != 0 {
+              let mut repeated = repeated.clone();
+              repeated.push(bindings);
+              next.push((repeated, start + len));
+            }
+          }
+        }
+        repetitions = next;
+      }
+      matches
+    }
+    _ => vec![],
+  }
+}
+
+impl<'a> Expander<'a> {
+  fn error(&self, start: usize, end: usize, message: String) -> ParseError {
+    let (line_num, line, start_offset) =
+      get_error_line_from_byte_offset(self.file, start);
+    ParseError::Expansion {
+      start: start_offset,
+      end: (start_offset + end - start).min(line.len()),
+      line: line,
+      line_num: line_num,
+      message: message,
+    }
+  }
+
+  // Collects the macros declared in the items and the modules declared inline
+  fn declare(&mut self, items: &Vec<(bool, Item)>) -> Result<(), ParseError> {
+    for (_, item) in items.iter() {
+      match item {
+        Item::Macro(MacroDec {
+          name,
+          rules,
+          start,
+          end,
+        }) => {
+          let mut compiled = vec![];
+          for (matcher, transcriber) in rules.iter() {
+            match parse_matchers(matcher) {
+              Ok(matcher) => compiled.push((matcher, transcriber.clone())),
+              Err(message) => return Err(self.error(*start, *end, message)),
+            }
+          }
+          self.macros.insert(name.clone(), compiled);
+        }
+        Item::Mod(_, Some(items)) => {
+          if let Err(e) = self.declare(items) {
+            return Err(e);
+          }
+        }
+        _ => (),
+      }
+    }
+    Ok(())
+  }
+
+  fn expand_items(
+    &mut self,
+    items: Vec<(bool, Item)>,
+  ) -> Result<Vec<(bool, Item)>, ParseError> {
+    let mut expanded = vec![];
+    for (public, item) in items {
+      let mut item = item;
+      let res = match &mut item {
+        Item::Macro(_) => continue,
+        Item::Func(func) | Item::Test(func) => self.expand(&mut func.body_start, 0, None),
+        Item::Impl(_, _, methods) => methods
+          .iter_mut()
+          .map(|method| self.expand(&mut method.body_start, 0, None))
+          .collect(),
+        Item::Global(global) => self.expand(&mut global.expr, 0, None),
+        Item::Mod(name, Some(items)) => match self.expand_items(std::mem::take(items)) {
+          Ok(items) => {
+            item = Item::Mod(name.clone(), Some(items));
+            Ok(())
+          }
+          Err(e) => Err(e),
+        },
+        _ => Ok(()),
+      };
+      if let Err(e) = res {
+        return Err(e);
+      }
+      expanded.push((public, item));
+    }
+    Ok(expanded)
+  }
+
+  // Expands the invocations in a node and the nodes below it. Invocations found in
+  // an expansion are reported at the invocation of the outermost macro, given by
+  // span.
+  fn expand(
+    &mut self,
+    node: &mut Node,
+    depth: usize,
+    span: Option<(usize, usize)>,
+  ) -> Result<(), ParseError> {
+    if let Node::MacroCall(name, tokens, start, end, is_statement, next) = node {
+      let (start, end) = span.unwrap_or((*start, *end));
+      if depth == RECURSION_LIMIT {
+        return Err(self.error(
+          start,
+          end,
+          format!("Recursion limit reached while expanding {}!", name),
+        ));
+      }
+      let (mut expansion, renamed) = match self.transcribe(name, tokens, *is_statement)
+      {
+        Ok(expansion) => expansion,
+        Err(message) => return Err(self.error(start, end, message)),
+      };
+      // Panics in the expansion report the line of the invocation
+      let (line, _, _) = get_error_line_from_byte_offset(self.file, start);
+      set_panic_lines(&mut expansion, line + 1);
+      if let Err(e) = self.expand(&mut expansion, depth + 1, Some((start, end))) {
+        return Err(e);
+      }
+      // The placeholders may be passed to the macros invoked in the expansion, they
+      // are renamed once these are expanded
+      rename_locals(&mut expansion, &renamed);
+      expansion.map_types(&|r#type| restore_type(r#type, &renamed));
+
+      if let Some(mut next) = next.take() {
+        if let Err(e) = self.expand(&mut next, depth, span) {
+          return Err(e);
+        }
+        match expansion {
+          Node::Empty => expansion = *next,
+          _ => expansion.append_instruction(*next),
+        }
+      }
+      *node = expansion;
+      return Ok(());
+    }
+
+    for child in node.children_mut() {
+      if let Err(e) = self.expand(child, depth, span) {
+        return Err(e);
+      }
+    }
+    Ok(())
+  }
+
+  // Transcribes the first rule of a macro matching the tokens it is invoked with
+  // and parses the expansion, the locals it introduces are left to rename
+  fn transcribe(
+    &mut self,
+    name: &str,
+    tokens: &Vec<TokenTree>,
+    is_statement: bool,
+  ) -> Result<(Node, Renamed), String> {
+    let rules = match self.macros.get(name) {
+      Some(rules) => rules,
+      None => return Err(format!("Macro {}! is not declared in this file", name)),
+    };
+    let (bindings, transcriber) = match rules.iter().find_map(|(matcher, transcriber)| {
+      match_prefix(matcher, tokens)
+        .into_iter()
+        .find(|(_, len)| *len == tokens.len())
+        .map(|(bindings, _)| (bindings, transcriber))
+    }) {
+      Some(rule) => rule,
+      None => return Err(format!("No rule of {}! matches its arguments", name)),
+    };
+
+    // The locals introduced by the macro are renamed so that they can not clash
+    // with the variables of the code it is invoked in, or the ones passed to it.
+    // They are transcribed to placeholders which do not appear in the file, and
+    // given a fresh name the lexer can not produce, e.g. t#swap#1, once the
+    // expansion is parsed.
+    self.expansions += 1;
+    let mut placeholders = HashMap::new();
+    let mut renamed = HashMap::new();
+    for local in introduced_locals(transcriber) {
+      let mut placeholder = format!("{}__{}", local, self.expansions);
+      while self.file.contains(&placeholder) {
+        placeholder.push('_');
+      }
+      let fresh = format!("{}#{}#{}", local, name, self.expansions);
+      placeholders.insert(local.clone(), placeholder.clone());
+      renamed.insert(placeholder, (local, fresh));
+    }
+    let mut expansion = vec![];
+    if let Err(e) = transcribe(transcriber, &bindings, &placeholders, &mut expansion) {
+      return Err(e);
+    }
+
+    let source = TokenTree::to_source(&expansion);
+    let expansion = if is_statement {
+      if expansion.len() == 0 {
+        return Ok((Node::Empty, renamed));
+      }
+      // A statement may expand to a call or print without its semicolon
+      let parser = InstructionsParser::new();
+      match parser.parse(&mut vec![], &source) {
+        Ok(instructions) => *instructions,
+        Err(_) => match parser.parse(&mut vec![], &format!("{};", source)) {
+          Ok(instructions) => *instructions,
+          Err(_) => {
+            return Err(format!(
+              "{}! expands to `{}`, which are not valid statements",
+              name, source
+            ))
+          }
+        },
+      }
+    } else {
+      match ExprParser::new().parse(&mut vec![], &source) {
+        Ok(expr) => *expr,
+        Err(_) => {
+          return Err(format!(
+            "{}! expands to `{}`, which is not a valid expression",
+            name, source
+          ))
+        }
+      }
+    };
+    Ok((expansion, renamed))
+  }
+}
+
+fn transcribe(
+  transcriber: &[TokenTree],
+  bindings: &Bindings,
+  placeholders: &HashMap<String, String>,
+  expansion: &mut Vec<TokenTree>,
+) -> Result<(), String> {
+  let mut i = 0;
+  while i < transcriber.len() {
+    match (&transcriber[i], transcriber.get(i + 1)) {
+      (TokenTree::Token(dollar), Some(TokenTree::Token(name)))
+        if dollar == "$" && bindings.contains_key(name) =>
+      {
+        match &bindings[name] {
+          // An expression is kept together where it is used, unless it is a
+          // single token which might be assigned to
+          Binding::Fragment(tokens, Fragment::Expr) if tokens.len() > 1 => {
+            expansion.push(TokenTree::Group('(', tokens.clone()))
+          }
+          Binding::Fragment(tokens, _) => expansion.extend(tokens.iter().cloned()),
+          Binding::Repeated(_) => {
+            return Err(format!(
+              "${} is repeated and must be used in a repetition",
+              name
+            ))
+          }
+        }
+        i += 2;
+      }
+      (TokenTree::Token(dollar), Some(TokenTree::Group('(', repeated)))
+        if dollar == "$" =>
+      {
+        let (separator, len) = match (transcriber.get(i + 2), transcriber.get(i + 3)) {
+          (Some(TokenTree::Token(op)), _) if op == "*" || op == "+" || op == "?" => {
+            (None, 3)
+          }
+          (Some(TokenTree::Token(separator)), Some(TokenTree::Token(op)))
+            if op == "*" || op == "+" =>
+          {
+            (Some(separator.clone()), 4)
+          }
+          _ => return Err("Expected * or + after the repetition".to_string()),
+        };
+
+        // The repetition is repeated as many times as the fragments it uses
+        let mut used = vec![];
+        collect_used(repeated, bindings, &mut used);
+        let counts: Vec<usize> = used
+          .iter()
+          .map(|name| match &bindings[name] {
+            Binding::Repeated(repetitions) => repetitions.len(),
+            Binding::Fragment(..) => 0,
+          })
+          .collect();
+        let count = match counts.first() {
+          Some(count) if counts.iter().all(|c| c == count) => *count,
+          Some(_) => {
+            return Err("Fragments repeated a different number of times are used in the same repetition".to_string())
+          }
+          None => return Err("A repetition must use a repeated fragment".to_string()),
+        };
+
+        for n in 0..count {
+          if let (Some(separator), true) = (&separator, n != 0) {
+            expansion.push(TokenTree::Token(separator.clone()));
+          }
+          let mut repetition = bindings.clone();
+          for name in used.iter() {
+            if let Binding::Repeated(repetitions) = &bindings[name] {
+              repetition.extend(repetitions[n].clone());
+            }
+          }
+          if let Err(e) = transcribe(repeated, &repetition, placeholders, expansion) {
+            return Err(e);
+          }
+        }
+        i += len;
+      }
+      (TokenTree::Group(delimiter, tokens), _) => {
+        let mut group = vec![];
+        if let Err(e) = transcribe(tokens, bindings, placeholders, &mut group) {
+          return Err(e);
+        }
+        expansion.push(TokenTree::Group(*delimiter, group));
+        i += 1;
+      }
+      (TokenTree::Token(token), _) => {
+        let token = placeholders.get(token).unwrap_or(token);
+        expansion.push(TokenTree::Token(token.clone()));
+        i += 1;
+      }
+    }
+  }
+  Ok(())
+}
+
+// Renames the placeholders of the locals a macro introduces where they are
+// variables, to the fresh name of the local, and where they are the name of a
+// field or method or a segment of a path back to the name of the local
+fn rename_locals(node: &mut Node, renamed: &Renamed) {
+  let fresh = |name: &mut String| {
+    if let Some((_, fresh)) = renamed.get(name) {
+      *name = fresh.clone();
+    }
+  };
+  match node {
+    Node::Var(name)
+    | Node::Let(name, ..)
+    | Node::Declare(name, ..)
+    | Node::Assign(name, ..) => fresh(name),
+    Node::LetTuple(vars, ..) => vars.iter_mut().for_each(|(name, _)| fresh(name)),
+    // A local closure is called by its name
+    Node::FuncCall(name, ..) => match renamed.get(name) {
+      Some((_, fresh)) => *name = fresh.clone(),
+      None => *name = restore(name, renamed),
+    },
+    Node::Closure(params, ..) => params.iter_mut().for_each(|param| fresh(&mut param.name)),
+    Node::Struct(_, fields) => {
+      for (field, _) in fields.iter_mut() {
+        *field = restore(field, renamed);
+      }
+    }
+    Node::Field(_, field) | Node::FieldAssign(_, field, ..) | Node::MethodCall(_, field, _) => {
+      *field = restore(field, renamed)
+    }
+    Node::Match(_, arms, _) => {
+      for (pattern, _, _) in arms.iter_mut() {
+        rename_pattern(pattern, renamed);
+      }
+    }
+    _ => (),
+  }
+  for child in node.children_mut() {
+    rename_locals(child, renamed);
+  }
+}
+
+fn rename_pattern(pattern: &mut Pattern, renamed: &Renamed) {
+  match pattern {
+    Pattern::Binding(name) => {
+      if let Some((_, fresh)) = renamed.get(name) {
+        *name = fresh.clone();
+      }
+    }
+    Pattern::Variant(_, _, patterns) | Pattern::Or(patterns) => {
+      for pattern in patterns.iter_mut() {
+        rename_pattern(pattern, renamed);
+      }
+    }
+    _ => (),
+  }
+}
+
+// A name or path with the placeholders among its segments replaced by the name of
+// the local
+fn restore(path: &str, renamed: &Renamed) -> String {
+  path
+    .split("::")
+    .map(|segment| match renamed.get(segment) {
+      Some((local, _)) => local.as_str(),
+      None => segment,
+    })
+    .collect::<Vec<&str>>()
+    .join("::")
+}
+
+fn restore_type(r#type: &Type, renamed: &Renamed) -> Type {
+  match r#type {
+    Type::Named(name) => Type::Named(restore(name, renamed)),
+    Type::Array(element, len) => Type::Array(Box::new(restore_type(element, renamed)), *len),
+    Type::Tuple(types) => Type::Tuple(
+      types
+        .iter()
+        .map(|r#type| restore_type(r#type, renamed))
+        .collect(),
+    ),
+    Type::Func(params, ret) => Type::Func(
+      params
+        .iter()
+        .map(|param| restore_type(param, renamed))
+        .collect(),
+      Box::new(restore_type(ret, renamed)),
+    ),
+    Type::Alias(name, r#type) => {
+      Type::Alias(name.clone(), Box::new(restore_type(r#type, renamed)))
+    }
+    r#type => r#type.clone(),
+  }
+}
+
+fn set_panic_lines(node: &mut Node, line: usize) {
+  if let Node::Panic(_, _, panic_line, _) = node {
+    *panic_line = line;
+  }
+  for child in node.children_mut() {
+    set_panic_lines(child, line);
+  }
+}
+
+// Names of the repeated fragments used in a transcriber
+fn collect_used(tokens: &[TokenTree], bindings: &Bindings, used: &mut Vec<String>) {
+  for (i, token) in tokens.iter().enumerate() {
+    match (token, tokens.get(i + 1)) {
+      (TokenTree::Token(dollar), Some(TokenTree::Token(name)))
+        if dollar == "$" && !used.contains(name) =>
+      {
+        if let Some(Binding::Repeated(_)) = bindings.get(name) {
+          used.push(name.clone());
+        }
+      }
+      (TokenTree::Group(_, tokens), _) => collect_used(tokens, bindings, used),
+      _ => (),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::expand_macros;
+  use crate::{
+    parsing::file_parser::parse,
+    types::{item::Item, node::Node},
+  };
+
+  fn expand(file: &str) -> Result<Node, String> {
+    let (items, _) = parse(file).unwrap();
+    match expand_macros(items, file) {
+      Ok(items) => match items.into_iter().last() {
+        Some((_, Item::Func(func))) => Ok(func.body_start),
+        _ => panic!("Expected a function last"),
+      },
+      Err(e) => Err(e.to_string()),
+    }
+  }
+
+  #[test]
+  fn renames_the_locals_a_macro_introduces() {
+    let body = expand(
+      "
+      macro_rules! twice {
+        ($x:expr) => { let t = $x; t + t };
+      }
+      macro_rules! swap {
+        ($a:ident, $b:ident) => { let t = $a; $a = $b; $b = t; };
+      }
+      fn main() {
+        let t = 1;
+        swap!(t, u);
+      }",
+    )
+    .unwrap();
+    let var = |name: &str| Box::new(Node::Var(name.to_string()));
+    let expected = Node::Let(
+      "t".to_string(),
+      None,
+      false,
+      Box::new(Node::Number(1)),
+      Some(Box::new(Node::Let(
+        "t#swap#1".to_string(),
+        None,
+        false,
+        var("t"),
+        Some(Box::new(Node::Assign(
+          "t".to_string(),
+          var("u"),
+          Some(Box::new(Node::Assign("u".to_string(), var("t#swap#1"), None))),
+        ))),
+      ))),
+    );
+    assert_eq!(body, expected);
+  }
+
+  #[test]
+  fn reports_errors_at_the_invocation() {
+    let error = expand(
+      "macro_rules! first {
+  ($x:expr $(, $rest:expr)*) => { $x };
+}
+fn main() -> i32 {
+  return first!(;);
+}",
+    )
+    .unwrap_err();
+    assert_eq!(
+      error,
+      format!(
+        "No rule of first! matches its arguments on line 4:   return first!(;);\n{}{}",
+        " ".repeat(60),
+        "^~~~~~~~^"
+      )
+    );
+  }
+}
@@ -0,0 +1,48 @@
+use crate::{
+  errors::{parse_error::ParseError, parse_warning::ParseWarning},
+  parsing::file_parser::parse,
+};
+
+// Rewrites the deprecated syntax of a file into its current form, parameters
+// declared as `name: mut T` become `mut name: T`
+pub fn migrate(file: &str) -> Result<String, ParseError> {
+  let warnings = match parse(file) {
+    Ok((_, warnings)) => warnings,
+    Err(e) => return Err(e),
+  };
+
+  // The warnings are found in the order they appear in the file
+  let mut migrated = String::new();
+  let mut copied = 0;
+  for warning in warnings.iter() {
+    match warning {
+      ParseWarning::DeprecatedMutParam {
+        name,
+        start,
+        type_start,
+        end,
+      } => {
+        migrated += &file[copied..*start];
+        migrated += &format!("mut {}: {}", name, &file[*type_start..*end]);
+        copied = *end;
+      }
+      // Files are migrated one at a time, their modules on their own
+      ParseWarning::InFile { .. } => (),
+    }
+  }
+  migrated += &file[copied..];
+  Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::migrate;
+
+  #[test]
+  fn moves_mut_in_front_of_parameters() {
+    assert_eq!(
+      migrate("fn f(a: mut [i32; 2], b: i32, c: mut (i32, bool)) {}\n").unwrap(),
+      "fn f(mut a: [i32; 2], b: i32, mut c: (i32, bool)) {}\n"
+    );
+  }
+}
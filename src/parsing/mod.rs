@@ -1,11 +1,18 @@
 lalrpop_mod!(pub grammar);
 
+pub mod aliases;
 // pub mod assign_parser;
 // pub mod body_parser;
+pub mod contracts;
 // pub mod expr_parser;
 pub mod file_parser;
 // pub mod func_call_parser;
 // pub mod func_dec_parser;
+pub mod hoist;
 // pub mod if_parser;
 // pub mod let_parser;
+pub mod literal;
+pub mod macros;
+pub mod migrate;
+pub mod modules;
 // pub mod return_parser;
@@ -0,0 +1,391 @@
+use std::{
+  cell::RefCell,
+  collections::HashMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::{
+  errors::{
+    parse_error::ParseError, parse_warning::ParseWarning, resolve_error::ResolveError,
+  },
+  parsing::{file_parser::parse, macros::expand_macros},
+  types::{_type::Type, func::Func, item::Item},
+};
+
+// A program is split into modules declared inline, mod name { .. }, or in a file
+// of their own, mod name;, which is looked up relative to the root file as in Rust:
+// name.rs or name/mod.rs for a module of the root module, parent/name.rs for one
+// of the module parent. The items of a module are known by their path, e.g.
+// shapes::area, those of the root module by their name.
+pub struct Modules {
+  modules: HashMap<String, Module>,
+  // Items other than modules and use declarations, along with the path of the
+  // module they are declared in
+  pub items: Vec<(String, Item)>,
+  pub warnings: Vec<ParseWarning>,
+  root: String,
+  dir: PathBuf,
+  // Use declarations, along with the module they are in and whether they are
+  // public
+  imports: Vec<(String, String, bool)>,
+  // Errors found when resolving paths, along with the module they are used in
+  errors: RefCell<Vec<(String, ResolveError)>>,
+}
+
+struct Module {
+  // File the module is written in
+  file: String,
+  // Names declared in or imported into the module, along with whether they are
+  // visible outside of it
+  names: HashMap<String, (Target, bool)>,
+}
+
+// What a name of a module refers to
+#[derive(Debug, Clone)]
+enum Target {
+  // The path of an item
+  Item(String),
+  // The path of a module
+  Module(String),
+}
+
+// The path of an item declared in a module
+pub fn qualify(module: &str, name: &str) -> String {
+  match module {
+    "" => name.to_string(),
+    module => format!("{}::{}", module, name),
+  }
+}
+
+// The path of the module a module is declared in, None for the root module
+fn parent(module: &str) -> Option<String> {
+  match module.rfind("::") {
+    Some(i) => Some(module[..i].to_string()),
+    None if module.is_empty() => None,
+    None => Some(String::new()),
+  }
+}
+
+// The private items of a module are visible in it and the modules nested in it
+fn is_within(module: &str, ancestor: &str) -> bool {
+  ancestor.is_empty()
+    || module == ancestor
+    || module.starts_with(&format!("{}::", ancestor))
+}
+
+fn item_name(item: &Item) -> Option<&String> {
+  match item {
+    Item::Func(func) | Item::Test(func) => Some(&func.name),
+    Item::Struct(r#struct) => Some(&r#struct.name),
+    Item::Enum(r#enum) => Some(&r#enum.name),
+    Item::Trait(r#trait) => Some(&r#trait.name),
+    Item::Global(global) => Some(&global.name),
+    Item::Alias(name, _) => Some(name),
+    Item::Impl(..) | Item::Mod(..) | Item::Use(_) | Item::Macro(_) => None,
+  }
+}
+
+impl Modules {
+  // Reads the root file of a program along with the files of its modules
+  pub fn load(root: &Path) -> Result<Modules, ParseError> {
+    let file = match fs::read_to_string(root) {
+      Ok(file) => file,
+      Err(e) => panic!("Could not open input file: {}", e),
+    };
+    let (items, warnings) = match parse(&file) {
+      Ok(parsed) => parsed,
+      Err(e) => return Err(e),
+    };
+    let items = match expand_macros(items, &file) {
+      Ok(items) => items,
+      Err(e) => return Err(e),
+    };
+
+    let mut modules = Modules {
+      modules: HashMap::new(),
+      items: vec![],
+      warnings: warnings,
+      root: root.display().to_string(),
+      dir: root.parent().unwrap_or(Path::new("")).to_path_buf(),
+      imports: vec![],
+      errors: RefCell::new(vec![]),
+    };
+    if let Err(e) = modules.add_module(String::new(), modules.root.clone(), items) {
+      return Err(e);
+    }
+    modules.resolve_imports();
+    Ok(modules)
+  }
+
+  fn add_module(
+    &mut self,
+    module: String,
+    file: String,
+    items: Vec<(bool, Item)>,
+  ) -> Result<(), ParseError> {
+    let mut names = HashMap::new();
+    for (public, item) in items {
+      match item {
+        Item::Mod(name, items) => {
+          let nested = qualify(&module, &name);
+          names.insert(name.clone(), (Target::Module(nested.clone()), public));
+          let res = match items {
+            Some(items) => self.add_module(nested, file.clone(), items),
+            None => match self.read_module(&nested, &name, &file) {
+              Ok((file, items)) => self.add_module(nested, file, items),
+              Err(e) => Err(e),
+            },
+          };
+          if let Err(e) = res {
+            return Err(e);
+          }
+        }
+        Item::Use(path) => self.imports.push((module.clone(), path, public)),
+        item => {
+          if let Some(name) = item_name(&item) {
+            names.insert(name.clone(), (Target::Item(qualify(&module, name)), public));
+          }
+          self.items.push((module.clone(), item));
+        }
+      }
+    }
+    self.modules.insert(module, Module { file, names });
+    Ok(())
+  }
+
+  // Reads the file of a module declared with mod name; in the given file
+  fn read_module(
+    &mut self,
+    module: &str,
+    name: &str,
+    declared_in: &str,
+  ) -> Result<(String, Vec<(bool, Item)>), ParseError> {
+    let relative = module.replace("::", "/");
+    let candidates = vec![
+      self.dir.join(format!("{}.rs", relative)),
+      self.dir.join(&relative).join("mod.rs"),
+    ];
+    let path = match candidates.iter().find(|path| path.is_file()) {
+      Some(path) => path.display().to_string(),
+      None => {
+        let error = ParseError::MissingModule {
+          name: name.to_string(),
+          path: candidates[0].display().to_string(),
+        };
+        return Err(match declared_in == self.root {
+          true => error,
+          false => ParseError::InFile {
+            file: declared_in.to_string(),
+            error: Box::new(error),
+          },
+        });
+      }
+    };
+
+    let file = match fs::read_to_string(&path) {
+      Ok(file) => file,
+      Err(e) => panic!("Could not read module file {}: {}", path, e),
+    };
+    match parse(&file).and_then(|(items, warnings)| {
+      expand_macros(items, &file).map(|items| (items, warnings))
+    }) {
+      Ok((items, warnings)) => {
+        self
+          .warnings
+          .extend(warnings.into_iter().map(|warning| ParseWarning::InFile {
+            file: path.clone(),
+            warning: Box::new(warning),
+          }));
+        Ok((path, items))
+      }
+      Err(e) => Err(ParseError::InFile {
+        file: path,
+        error: Box::new(e),
+      }),
+    }
+  }
+
+  // Use declarations can import names brought into scope by others, they are
+  // resolved until none of the remaining ones can be
+  fn resolve_imports(&mut self) {
+    let mut pending = std::mem::replace(&mut self.imports, vec![]);
+    loop {
+      let mut resolved = vec![];
+      pending.retain(|(module, path, public)| match self.lookup(module, path) {
+        Some(target) => {
+          resolved.push((module.clone(), path.clone(), target, *public));
+          false
+        }
+        None => true,
+      });
+      if resolved.is_empty() {
+        break;
+      }
+      for (module, path, target, public) in resolved {
+        let name = path.rsplit("::").next().unwrap().to_string();
+        if let Some(module) = self.modules.get_mut(&module) {
+          module.names.insert(name, (target, public));
+        }
+      }
+    }
+    for (module, path, _) in pending {
+      self.error(&module, ResolveError::UnresolvedImport { path });
+    }
+  }
+
+  fn error(&self, module: &str, error: ResolveError) {
+    let error = (module.to_string(), error);
+    let mut errors = self.errors.borrow_mut();
+    if !errors.contains(&error) {
+      errors.push(error);
+    }
+  }
+
+  // What a path refers to from a module. Paths are relative to the module unless
+  // they start with crate, the root module, or super, the parent module.
+  fn lookup(&self, module: &str, path: &str) -> Option<Target> {
+    let mut target = Target::Module(module.to_string());
+    for (i, segment) in path.split("::").enumerate() {
+      let scope = match target {
+        Target::Module(scope) => scope,
+        Target::Item(_) => return None,
+      };
+      target = match segment {
+        "crate" if i == 0 => Target::Module(String::new()),
+        "super" => match parent(&scope) {
+          Some(parent) => Target::Module(parent),
+          None => return None,
+        },
+        name => match self.modules.get(&scope).and_then(|m| m.names.get(name)) {
+          Some((target, public)) => {
+            if !public && !is_within(module, &scope) {
+              self.error(
+                module,
+                ResolveError::PrivateItem {
+                  name: qualify(&scope, name),
+                },
+              );
+            }
+            target.clone()
+          }
+          None => return None,
+        },
+      };
+    }
+    Some(target)
+  }
+
+  // The path of the item a name used in a module refers to, None when it does not
+  // name an item, e.g. a variable or a type parameter
+  pub fn resolve(&self, module: &str, name: &str) -> Option<String> {
+    match self.lookup(module, name) {
+      Some(Target::Item(path)) => Some(path),
+      _ => None,
+    }
+  }
+
+  // Replaces the names in a type written in a module by the paths of the types
+  // they refer to, type parameters are left as they are
+  pub fn resolve_type(
+    &self,
+    module: &str,
+    r#type: &Type,
+    type_params: &Vec<(String, Vec<String>)>,
+  ) -> Type {
+    match r#type {
+      Type::Named(name) if type_params.iter().any(|(param, _)| param == name) => {
+        r#type.clone()
+      }
+      Type::Named(name) => match self.resolve(module, name) {
+        Some(path) => Type::Named(path),
+        None => r#type.clone(),
+      },
+      Type::Array(r#type, len) => Type::Array(
+        Box::new(self.resolve_type(module, r#type, type_params)),
+        *len,
+      ),
+      Type::Tuple(types) => Type::Tuple(
+        types
+          .iter()
+          .map(|r#type| self.resolve_type(module, r#type, type_params))
+          .collect(),
+      ),
+      Type::Func(params, ret_type) => Type::Func(
+        params
+          .iter()
+          .map(|r#type| self.resolve_type(module, r#type, type_params))
+          .collect(),
+        Box::new(self.resolve_type(module, ret_type, type_params)),
+      ),
+      _ => r#type.clone(),
+    }
+  }
+
+  // Resolves the types of a function declared in a module, in its signature, the
+  // traits bounding its type parameters and its body
+  pub fn resolve_func(&self, module: &str, func: &mut Func) {
+    for bound in func.type_params.iter_mut().flat_map(|(_, bounds)| bounds) {
+      if let Some(path) = self.resolve(module, bound) {
+        *bound = path;
+      }
+    }
+    for param in func.params.iter_mut() {
+      param._type = self.resolve_type(module, &param._type, &func.type_params);
+    }
+    func.ret_type = self.resolve_type(module, &func.ret_type, &func.type_params);
+    let type_params = &func.type_params;
+    func
+      .body_start
+      .map_types(&|r#type| self.resolve_type(module, r#type, type_params));
+  }
+
+  // The file a module is written in, None for the root file
+  pub fn file_of(&self, module: &str) -> Option<String> {
+    match self.modules.get(module) {
+      Some(module) if module.file != self.root => Some(module.file.clone()),
+      _ => None,
+    }
+  }
+
+  // Paths of the modules of the program
+  pub fn paths(&self) -> Vec<&String> {
+    self.modules.keys().collect()
+  }
+
+  pub fn take_errors(&self) -> Vec<(String, ResolveError)> {
+    self.errors.replace(vec![])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Modules;
+  use std::path::Path;
+
+  #[test]
+  fn resolves_paths_relative_to_modules() {
+    let modules = Modules::load(Path::new("tests/samples/modules/main.rs")).unwrap();
+    let mut paths = modules.paths();
+    paths.sort();
+    assert_eq!(paths, vec!["", "geometry", "geometry::shapes", "util"]);
+    assert_eq!(
+      modules.resolve("", "geometry::shapes::area"),
+      Some("geometry::shapes::area".to_string())
+    );
+    assert_eq!(
+      modules.resolve("geometry::shapes", "super::square"),
+      Some("geometry::square".to_string())
+    );
+    assert_eq!(
+      modules.resolve("util", "crate::geometry::Point"),
+      Some("geometry::Point".to_string())
+    );
+    assert_eq!(
+      modules.file_of("geometry::shapes"),
+      Some("tests/samples/modules/geometry.rs".to_string())
+    );
+    assert_eq!(modules.file_of(""), None);
+    assert_eq!(modules.take_errors(), vec![]);
+  }
+}
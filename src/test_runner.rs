@@ -0,0 +1,116 @@
+use crate::types::program::Program;
+
+// Outcome of a test function, along with the panic it failed with
+pub struct TestResult {
+  pub name: String,
+  pub failure: Option<String>,
+}
+
+// Runs the tests whose path contains the filter, in declaration order. Each test
+// is interpreted on its own, a test panicking does not stop the others.
+pub fn run_tests(program: &Program, filter: &str) -> Vec<TestResult> {
+  program
+    .tests
+    .iter()
+    .filter(|name| name.contains(filter))
+    .map(|name| {
+      let failure = match program.run_test(name) {
+        Ok(_) => None,
+        Err(e) => Some(e.to_string()),
+      };
+      TestResult {
+        name: name.clone(),
+        failure: failure,
+      }
+    })
+    .collect()
+}
+
+// Summary of a test run in the format of cargo test
+pub fn report(results: &Vec<TestResult>, filtered_out: usize) -> String {
+  let mut report = format!(
+    "running {} test{}\n",
+    results.len(),
+    if results.len() == 1 { "" } else { "s" }
+  );
+  for result in results.iter() {
+    let outcome = match result.failure {
+      Some(_) => "FAILED",
+      None => "ok",
+    };
+    report.push_str(&format!("test {} ... {}\n", result.name, outcome));
+  }
+
+  let failed: Vec<&TestResult> = results.iter().filter(|r| r.failure.is_some()).collect();
+  if failed.len() != 0 {
+    report.push_str("\nfailures:\n");
+    for result in failed.iter() {
+      report.push_str(&format!(
+        "\n---- {} ----\nthread '{}' {}\n",
+        result.name,
+        result.name,
+        result.failure.as_ref().unwrap()
+      ));
+    }
+    report.push_str("\nfailures:\n");
+    for result in failed.iter() {
+      report.push_str(&format!("    {}\n", result.name));
+    }
+  }
+
+  report.push_str(&format!(
+    "\ntest result: {}. {} passed; {} failed; {} filtered out",
+    if failed.len() == 0 { "ok" } else { "FAILED" },
+    results.len() - failed.len(),
+    failed.len(),
+    filtered_out
+  ));
+  report
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{report, run_tests};
+  use crate::{type_checker::type_check_program, types::program::Program};
+  use std::{convert::TryFrom, path::Path};
+
+  #[test]
+  fn runs_each_test_on_its_own() {
+    let program = Program::try_from(Path::new("tests/samples/unit_tests.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    let results = run_tests(&program, "");
+    assert_eq!(
+      report(&results, 0),
+      "running 4 tests
+test adds_numbers ... ok
+test counts_to_three ... ok
+test indexes_out_of_bounds ... FAILED
+test math::halves ... FAILED
+
+failures:
+
+---- indexes_out_of_bounds ----
+thread 'indexes_out_of_bounds' panicked at index out of bounds: the len is 2 but the index is 2, in first_missing <- indexes_out_of_bounds
+
+---- math::halves ----
+thread 'math::halves' panicked at assertion `left == right` failed
+  left: 3
+ right: 4, line 36, in math::halves
+
+failures:
+    indexes_out_of_bounds
+    math::halves
+
+test result: FAILED. 2 passed; 2 failed; 0 filtered out"
+    );
+  }
+
+  #[test]
+  fn filters_tests_by_name() {
+    let program = Program::try_from(Path::new("tests/samples/unit_tests.rs")).unwrap();
+    let results = run_tests(&program, "math::");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].name, "math::halves");
+    assert!(results[0].failure.is_some());
+  }
+}
@@ -1,61 +1,566 @@
 use crate::{
+  const_eval::eval_globals,
+  definite_assignment::check_definite_assignment,
   errors::{
-    type_error::TypeError, unknown_func_error::UnknownFuncError,
-    unknown_var_error::UnknownVarError,
+    type_error::TypeError, type_warning::TypeWarning,
+    unknown_func_error::UnknownFuncError, unknown_var_error::UnknownVarError,
   },
+  exhaustiveness::MatchChecker,
+  parsing::aliases::resolve_type,
   types::{
-    _type::Type, context::Context, func::Func, node::Node, opcode::Opcode,
-    program::Program, scope::Scope,
+    _type::Type, context::Context, enum_dec::EnumDec, func::Func, func_param::FuncParam,
+    global_dec::GlobalDec, impl_dec::ImplDec, location::Location, node::Node,
+    opcode::Opcode, pattern::Pattern, program::Program, scope::Scope,
+    struct_dec::StructDec, symbol_table::SymbolKind, trait_dec::TraitDec,
   },
 };
 use std::collections::HashMap;
 
+// Type checks the program, returning the warnings found if it is well typed
 pub fn type_check_program(
   program: &Program,
+) -> Result<Vec<TypeWarning>, Vec<Box<dyn std::error::Error>>> {
+  let mut errors: Vec<Box<dyn std::error::Error>> = vec![];
+  let mut warnings: Vec<TypeWarning> = vec![];
+
+  for (module, error) in program.resolve_errors.iter() {
+    errors.append(&mut in_file(program, module, vec![Box::new(error.clone())]));
+  }
+
+  for (kind, name, first, second) in program.symbols.duplicates.iter() {
+    let error = TypeError::DuplicateItem {
+      kind: *kind,
+      name: name.clone(),
+      first: *first,
+      second: *second,
+    };
+    errors.append(&mut in_file(program, name, vec![Box::new(error)]));
+  }
+
+  // Items are checked in declaration order so that errors are reported in that order
+  for r#struct in program
+    .symbols
+    .paths(SymbolKind::Struct)
+    .map(|path| &program.structs[path])
+  {
+    let types = r#struct.fields.iter().map(|(_, r#type)| r#type).collect();
+    if let Err(e) =
+      type_check_type_dec(&r#struct.name, types, &program.structs, &program.enums)
+    {
+      errors.append(&mut in_file(program, &r#struct.name, e));
+    }
+  }
+  for r#enum in program
+    .symbols
+    .paths(SymbolKind::Enum)
+    .map(|path| &program.enums[path])
+  {
+    let types = r#enum
+      .variants
+      .iter()
+      .flat_map(|(_, types)| types)
+      .collect();
+    if let Err(e) =
+      type_check_type_dec(&r#enum.name, types, &program.structs, &program.enums)
+    {
+      errors.append(&mut in_file(program, &r#enum.name, e));
+    }
+  }
+
+  for r#trait in program
+    .symbols
+    .paths(SymbolKind::Trait)
+    .map(|path| &program.traits[path])
+  {
+    for method in r#trait.methods.iter() {
+      let mut e = check_duplicate_params(&method.params, Some(&method.name));
+      if let Err(mut signature_errors) =
+        check_signature_types(method, &program.structs, &program.enums)
+      {
+        e.append(&mut signature_errors);
+      }
+      errors.append(&mut in_file(program, &r#trait.name, e));
+    }
+  }
+  for (i, r#impl) in program.impls.iter().enumerate() {
+    if let Err(e) = type_check_impl(r#impl, &program.impls[..i], program) {
+      // The methods of an impl are declared in the file of the impl
+      let method = match r#impl.methods.first() {
+        Some(method) => r#impl.method_name(method),
+        None => String::new(),
+      };
+      errors.append(&mut in_file(program, &method, e));
+    }
+  }
+  let checked = errors.len();
+  for global in program.globals.iter() {
+    if let Err(e) = type_check_global(global, program) {
+      errors.append(&mut in_file(program, &global.name, e));
+    }
+  }
+  // Initializers that type check are evaluated here, once, rather than every time
+  // the program runs
+  if errors.len() == checked {
+    match eval_globals(&program.globals) {
+      Ok(values) => {
+        program.global_values.replace(values);
+      }
+      Err((name, error)) => {
+        let error = TypeError::FailingInitializer {
+          name: name.clone(),
+          error: error,
+        };
+        errors.append(&mut in_file(program, &name, vec![Box::new(error)]));
+      }
+    }
+  }
+  for name in program.symbols.paths(SymbolKind::Alias) {
+    if let Err(e) = type_check_alias(name, &program.aliases[name], program) {
+      errors.append(&mut in_file(program, name, e));
+    }
+  }
+
+  // Tests are called without arguments and their result is not looked at
+  for name in program.tests.iter() {
+    let func = &program.funcs[name];
+    if func.params.len() != 0
+      || func.type_params.len() != 0
+      || func.ret_type != Type::Unit
+    {
+      errors.append(&mut in_file(
+        program,
+        name,
+        vec![Box::new(TypeError::InvalidTest { name: name.clone() })],
+      ));
+    }
+  }
+
+  for func in program
+    .symbols
+    .paths(SymbolKind::Func)
+    .map(|path| &program.funcs[path])
+  {
+    match type_check_function(
+      func,
+      &program.funcs,
+      &program.structs,
+      &program.enums,
+      &program.traits,
+      &program.impls,
+      &program.globals,
+    ) {
+      Ok(w) => match program.files.get(&func.name) {
+        Some(file) => warnings.extend(w.into_iter().map(|warning| TypeWarning::InFile {
+          file: file.clone(),
+          warning: Box::new(warning),
+        })),
+        None => warnings.extend(w),
+      },
+      Err(e) => errors.append(&mut in_file(program, &func.name, e)),
+    }
+  }
+
+  return if errors.len() == 0 {
+    Ok(warnings)
+  } else {
+    Err(errors)
+  };
+}
+// Errors found in an item declared outside of the root file name the file of the
+// item
+fn in_file(
+  program: &Program,
+  item: &str,
+  errors: Vec<Box<dyn std::error::Error>>,
+) -> Vec<Box<dyn std::error::Error>> {
+  match program.files.get(item) {
+    Some(file) => errors
+      .into_iter()
+      .map(|error| {
+        Box::new(TypeError::InFile {
+          file: file.clone(),
+          error: error,
+        }) as Box<dyn std::error::Error>
+      })
+      .collect(),
+    None => errors,
+  }
+}
+
+// Checks that all structs and enums named by a type are declared, the type
+// parameters in scope are names of types as well
+fn check_type_exists(
+  r#type: &Type,
+  type_params: &Vec<(String, Vec<String>)>,
+  structs: &HashMap<String, StructDec>,
+  enums: &HashMap<String, EnumDec>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  match r#type {
+    Type::Array(r#type, _) => check_type_exists(r#type, type_params, structs, enums),
+    Type::Tuple(types) => {
+      for r#type in types {
+        if let Err(e) = check_type_exists(r#type, type_params, structs, enums) {
+          return Err(e);
+        }
+      }
+      Ok(())
+    }
+    Type::Func(params, ret_type) => {
+      for r#type in params.iter().chain(std::iter::once(&**ret_type)) {
+        if let Err(e) = check_type_exists(r#type, type_params, structs, enums) {
+          return Err(e);
+        }
+      }
+      Ok(())
+    }
+    Type::Alias(_, r#type) => check_type_exists(r#type, type_params, structs, enums),
+    Type::Named(name) if type_params.iter().any(|(param, _)| param == name) => Ok(()),
+    Type::Named(name) if !structs.contains_key(name) && !enums.contains_key(name) => {
+      Err(vec![Box::new(TypeError::UnknownType {
+        name: name.clone(),
+      })])
+    }
+    _ => Ok(()),
+  }
+}
+
+// Whether a value of the type contains a value of the named type, the visited
+// types stop the search from looping on other recursive types.
+fn contains_named<'a>(
+  r#type: &'a Type,
+  name: &str,
+  structs: &'a HashMap<String, StructDec>,
+  enums: &'a HashMap<String, EnumDec>,
+  visited: &mut Vec<&'a str>,
+) -> bool {
+  match r#type {
+    Type::Array(r#type, _) | Type::Alias(_, r#type) => {
+      contains_named(r#type, name, structs, enums, visited)
+    }
+    Type::Tuple(types) => types
+      .iter()
+      .any(|r#type| contains_named(r#type, name, structs, enums, visited)),
+    Type::Named(named) if named == name => true,
+    Type::Named(named) if !visited.contains(&named.as_str()) => {
+      visited.push(named);
+      let types: Vec<&Type> = match (structs.get(named), enums.get(named)) {
+        (Some(r#struct), _) => r#struct.fields.iter().map(|(_, r#type)| r#type).collect(),
+        (_, Some(r#enum)) => r#enum
+          .variants
+          .iter()
+          .flat_map(|(_, types)| types)
+          .collect(),
+        _ => vec![],
+      };
+      types
+        .into_iter()
+        .any(|r#type| contains_named(r#type, name, structs, enums, visited))
+    }
+    _ => false,
+  }
+}
+
+// Checks that the types of the parameters and the return type of a function exist
+fn check_signature_types(
+  func: &Func,
+  structs: &HashMap<String, StructDec>,
+  enums: &HashMap<String, EnumDec>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  for r#type in func
+    .params
+    .iter()
+    .map(|param| &param._type)
+    .chain(std::iter::once(&func.ret_type))
+  {
+    if let Err(e) = check_type_exists(r#type, &func.type_params, structs, enums) {
+      return Err(e);
+    }
+  }
+  Ok(())
+}
+
+// Checks that an impl implements a declared trait for a declared type, providing
+// the methods of the trait with the signatures declared by the trait
+fn type_check_impl(
+  r#impl: &ImplDec,
+  earlier_impls: &[ImplDec],
+  program: &Program,
 ) -> Result<(), Vec<Box<dyn std::error::Error>>> {
   let mut errors: Vec<Box<dyn std::error::Error>> = vec![];
+  if let Err(mut e) =
+    check_type_exists(&r#impl.r#type, &vec![], &program.structs, &program.enums)
+  {
+    errors.append(&mut e);
+  }
+  if earlier_impls
+    .iter()
+    .any(|other| other.r#trait == r#impl.r#trait && other.r#type == r#impl.r#type)
+  {
+    errors.push(Box::new(TypeError::DuplicateImpl {
+      r#trait: r#impl.r#trait.clone(),
+      r#type: r#impl.r#type.clone(),
+    }));
+  }
 
-  // Iterate over the values of the hashmap (i.e. the second element)
-  for func in program.funcs.iter().map(|pair| pair.1) {
-    if let Err(ref mut e) = type_check_function(func, &program.funcs) {
-      errors.append(e);
+  let r#trait = match program.traits.get(&r#impl.r#trait) {
+    Some(r#trait) => r#trait,
+    None => {
+      errors.push(Box::new(TypeError::UnknownTrait {
+        name: r#impl.r#trait.clone(),
+      }));
+      return Err(errors);
+    }
+  };
+
+  // Self stands for the implementing type in the signatures of the trait
+  let mut type_args = HashMap::new();
+  type_args.insert("Self".to_string(), r#impl.r#type.clone());
+  for method in r#trait.methods.iter() {
+    match program.funcs.get(&r#impl.method_name(&method.name)) {
+      Some(func) => {
+        let expected: Vec<Type> = method
+          .params
+          .iter()
+          .map(|param| param._type.substitute(&type_args))
+          .collect();
+        let found: Vec<Type> = func
+          .params
+          .iter()
+          .map(|param| param._type.clone())
+          .collect();
+        if expected != found || method.ret_type.substitute(&type_args) != func.ret_type {
+          errors.push(Box::new(TypeError::MethodSignatureMissmatch {
+            r#trait: r#trait.name.clone(),
+            r#type: r#impl.r#type.clone(),
+            method: method.name.clone(),
+          }));
+        }
+      }
+      None => errors.push(Box::new(TypeError::MissingTraitMethod {
+        r#trait: r#trait.name.clone(),
+        r#type: r#impl.r#type.clone(),
+        method: method.name.clone(),
+      })),
+    }
+  }
+  for method in r#impl.methods.iter() {
+    if r#trait.get_method(method).is_none() {
+      errors.push(Box::new(TypeError::UnknownTraitMethod {
+        r#trait: r#trait.name.clone(),
+        method: method.clone(),
+      }));
+    }
+  }
+
+  return if errors.len() == 0 {
+    Ok(())
+  } else {
+    Err(errors)
+  };
+}
+
+// Whether the type refers to the named alias, directly or through other aliases
+fn refers_to_alias(
+  r#type: &Type,
+  name: &str,
+  aliases: &HashMap<String, Type>,
+  visited: &mut Vec<String>,
+) -> bool {
+  match r#type {
+    Type::Array(r#type, _) => refers_to_alias(r#type, name, aliases, visited),
+    Type::Tuple(types) => types
+      .iter()
+      .any(|r#type| refers_to_alias(r#type, name, aliases, visited)),
+    Type::Func(params, ret_type) => params
+      .iter()
+      .chain(std::iter::once(&**ret_type))
+      .any(|r#type| refers_to_alias(r#type, name, aliases, visited)),
+    Type::Named(named) if named == name => true,
+    Type::Named(named) if !visited.contains(named) => {
+      visited.push(named.clone());
+      match aliases.get(named) {
+        Some(aliased) => refers_to_alias(aliased, name, aliases, visited),
+        None => false,
+      }
+    }
+    _ => false,
+  }
+}
+
+fn type_check_alias(
+  name: &str,
+  aliased: &Type,
+  program: &Program,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  if refers_to_alias(aliased, name, &program.aliases, &mut vec![]) {
+    return Err(vec![Box::new(TypeError::RecursiveAlias {
+      name: name.to_string(),
+    })]);
+  }
+  check_type_exists(
+    &resolve_type(aliased, &program.aliases, &vec![]),
+    &vec![],
+    &program.structs,
+    &program.enums,
+  )
+}
+
+// Checks the types of the fields of a struct, or of the variants of an enum
+fn type_check_type_dec(
+  name: &str,
+  types: Vec<&Type>,
+  structs: &HashMap<String, StructDec>,
+  enums: &HashMap<String, EnumDec>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  let mut errors: Vec<Box<dyn std::error::Error>> = vec![];
+
+  for r#type in types.iter() {
+    if let Err(mut e) = check_type_exists(r#type, &vec![], structs, enums) {
+      errors.append(&mut e);
     }
   }
 
+  // A value of the type has to be able to be stored without indirection
+  if types
+    .into_iter()
+    .any(|r#type| contains_named(r#type, name, structs, enums, &mut vec![]))
+  {
+    errors.push(Box::new(TypeError::RecursiveType {
+      name: name.to_string(),
+    }));
+  }
+
   return if errors.len() == 0 {
     Ok(())
   } else {
     Err(errors)
   };
 }
+
+// Constants and statics are initialized with constant expressions, which are built
+// from literals, operators and other constants
+fn check_constant_expr(
+  expr: &Node,
+  globals: &Vec<GlobalDec>,
+  funcs: &HashMap<String, Func>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  let operands: Vec<&Node> = match expr {
+    Node::Number(_) | Node::Bool(_) | Node::Char(_) | Node::Str(_) | Node::Unit => {
+      vec![]
+    }
+    // Unknown variables are reported when type checking the initializer
+    Node::Var(name) => {
+      return match globals.iter().find(|global| &global.name == name) {
+        Some(global) if !global.is_static => Ok(()),
+        None if !funcs.contains_key(name) => Ok(()),
+        _ => Err(vec![Box::new(TypeError::NotConstant {
+          expr: expr.clone(),
+        })]),
+      }
+    }
+    Node::Array(elements) | Node::Tuple(elements) | Node::Variant(_, _, elements) => {
+      elements.iter().collect()
+    }
+    Node::Struct(_, fields) => fields.iter().map(|(_, expr)| expr).collect(),
+    Node::ArrayRepeat(element, _) => vec![element],
+    Node::TupleField(expr, _) | Node::Field(expr, _) => vec![expr],
+    Node::Index(array, index) => vec![array, index],
+    Node::Op(left, _, right) => vec![left, right],
+    _ => {
+      return Err(vec![Box::new(TypeError::NotConstant {
+        expr: expr.clone(),
+      })])
+    }
+  };
+  for operand in operands {
+    if let Err(e) = check_constant_expr(operand, globals, funcs) {
+      return Err(e);
+    }
+  }
+  Ok(())
+}
+
+// Whether an initializer refers to the named constant, directly or through the
+// initializers of other constants
+fn refers_to(
+  expr: &Node,
+  name: &str,
+  globals: &Vec<GlobalDec>,
+  visited: &mut Vec<String>,
+) -> bool {
+  expr.free_variables().into_iter().any(|var| {
+    if var == name {
+      return true;
+    }
+    if visited.contains(&var) {
+      return false;
+    }
+    visited.push(var.clone());
+    match globals.iter().find(|global| global.name == var) {
+      Some(global) => refers_to(&global.expr, name, globals, visited),
+      None => false,
+    }
+  })
+}
+
+fn type_check_global(
+  global: &GlobalDec,
+  program: &Program,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  if let Err(e) =
+    check_type_exists(&global._type, &vec![], &program.structs, &program.enums)
+  {
+    return Err(e);
+  }
+  if let Err(e) = check_constant_expr(&global.expr, &program.globals, &program.funcs) {
+    return Err(e);
+  }
+  if refers_to(&global.expr, &global.name, &program.globals, &mut vec![]) {
+    return Err(vec![Box::new(TypeError::RecursiveConstant {
+      name: global.name.clone(),
+    })]);
+  }
+
+  let mut context: Context<(Type, bool)> = Context::from(&global.initializer());
+  context.structs = program.structs.clone();
+  context.enums = program.enums.clone();
+  context.globals = Scope::from(&program.globals);
+  context.push(Scope::new());
+  let expr_type = match type_check_expr(&global.expr, &mut context, &program.funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
+  return if expr_type != global._type {
+    Err(vec![Box::new(TypeError::GlobalMissmatch {
+      name: global.name.clone(),
+      r#type: global._type.clone(),
+      expr_type: expr_type,
+    })])
+  } else {
+    Ok(())
+  };
+}
+
 fn type_check_while(
   condition: &Node,
   then_body: &Node,
   context: &mut Context<(Type, bool)>,
   funcs: &HashMap<String, Func>,
-) -> Result<Option<Type>, Vec<Box<dyn std::error::Error>>> {
-  let mut cond_type = type_check(condition, context, funcs);
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  let mut cond_type = type_check_expr(condition, context, funcs);
   let mut errors: Vec<Box<dyn std::error::Error>> = vec![];
 
   //Make sure the condition evaluates to a boolean
   match cond_type {
-    Ok(res) => match res {
-      Some(r#type) => {
-        if let Type::Bool = r#type {
-        } else {
-          errors.push(Box::new(TypeError::NonBooleanExpr {
-            expr: (*condition).clone(),
-            r#type: Some(r#type),
-          }));
-        }
-      }
-      None => {
+    Ok(r#type) => {
+      if let Type::Bool = r#type {
+      } else {
         errors.push(Box::new(TypeError::NonBooleanExpr {
           expr: (*condition).clone(),
-          r#type: None,
+          r#type: r#type,
         }));
       }
-    },
+    }
     Err(ref mut e) => {
       errors.append(e);
     }
@@ -68,7 +573,7 @@ fn type_check_while(
   }
 
   return if errors.len() == 0 {
-    Ok(None)
+    Ok(())
   } else {
     Err(errors)
   };
@@ -81,12 +586,9 @@ fn type_check_let(
   expr: &Node,
   context: &mut Context<(Type, bool)>,
   funcs: &HashMap<String, Func>,
-) -> Result<Option<Type>, Vec<Box<dyn std::error::Error>>> {
-  let expr_type = match type_check(expr, context, funcs) {
-    Ok(res) => match res {
-      Some(r#type) => r#type,
-      None => return Err(vec![Box::new(TypeError::NonTypeExpression {})]),
-    },
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  let expr_type = match type_check_expr(expr, context, funcs) {
+    Ok(r#type) => r#type,
     Err(e) => {
       return Err(e);
     }
@@ -95,8 +597,8 @@ fn type_check_let(
   return if let Some(r#type) = r#type {
     // If variable type was specified
     if expr_type == *r#type {
-      context.insert_type(name, expr_type.clone(), mutable);
-      Ok(None)
+      context.insert_type(name, r#type.clone(), mutable);
+      Ok(())
     } else {
       Err(vec![Box::new(TypeError::LetMissmatch {
         r#type: (*r#type).clone(),
@@ -105,776 +607,2753 @@ fn type_check_let(
     }
   } else {
     context.insert_type(name, expr_type.clone(), mutable);
-    Ok(None)
+    Ok(())
+  };
+}
+fn type_check_let_tuple(
+  names: &Vec<(String, bool)>,
+  r#type: &Option<Type>,
+  expr: &Node,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  let expr_type = match type_check_expr(expr, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
   };
+
+  // If variable type was specified
+  if let Some(r#type) = r#type {
+    if expr_type != *r#type {
+      return Err(vec![Box::new(TypeError::LetMissmatch {
+        r#type: (*r#type).clone(),
+        expr_type: expr_type,
+      })]);
+    }
+  }
+
+  // Every variable in the pattern binds one element of the tuple
+  match expr_type {
+    Type::Tuple(types) if types.len() == names.len() => {
+      for ((name, mutable), r#type) in names.iter().zip(types) {
+        context.insert_type(name, r#type, *mutable);
+      }
+      Ok(())
+    }
+    r#type => Err(vec![Box::new(TypeError::TupleArityMissmatch {
+      expected: names.len(),
+      r#type: r#type,
+    })]),
+  }
 }
+
 fn type_check_func_call(
   func: &str,
   args: &Vec<Node>,
   context: &mut Context<(Type, bool)>,
   funcs: &HashMap<String, Func>,
-) -> Result<Option<Type>, Vec<Box<dyn std::error::Error>>> {
-  let func = match funcs.get(func) {
-    Some(func) => func,
-    None => {
+) -> Result<Type, Vec<Box<dyn std::error::Error>>> {
+  // Variables holding closures shadow the functions of the program
+  let signature;
+  let var_type = context
+    .get_var_type(func)
+    .map(|(r#type, _)| r#type.resolved());
+  let func = match (var_type, funcs.get(func)) {
+    (Some(Type::Func(params, ret_type)), _) => {
+      signature = func_type_signature(func, params, *ret_type);
+      &signature
+    }
+    (Some(r#type), _) => {
+      return Err(vec![Box::new(TypeError::NotCallable {
+        name: func.to_string(),
+        r#type: r#type,
+      })])
+    }
+    (None, Some(func)) => func,
+    (None, None) => {
       return Err(vec![Box::new(UnknownFuncError {
         func_name: func.to_string(),
       })])
     }
   };
 
-  // Check argument/parameter length
-  if args.len() != func.params.len() {
-    if args.len() > func.params.len() {
-      return Err(vec![Box::new(TypeError::TooManyArgs {
-        func: func.name.clone(),
-        expected: func.params.len(),
-        received: args.len(),
-      })]);
-    } else {
-      let missing = func.params[args.len()..func.params.len()].to_vec();
-      return Err(vec![Box::new(TypeError::MissingArgs {
-        func: func.name.clone(),
-        missing: missing,
-      })]);
-    }
+  if let Err(e) = check_arg_count(&func.name, &func.params, args.len()) {
+    return Err(e);
   }
 
   // Check argument types
-  for (arg, param) in args.iter().zip(&func.params) {
-    let arg_type = match type_check(arg, context, funcs) {
-      Ok(res) => match res {
-        Some(r#type) => r#type,
-        None => {
-          return Err(vec![Box::new(TypeError::ArgMissmatch {
-            arg_type: None,
-            param: (*param).clone(),
-          })])
-        }
-      },
+  let mut arg_types = vec![];
+  for arg in args.iter() {
+    match type_check_expr(arg, context, funcs) {
+      Ok(r#type) => arg_types.push(r#type),
       Err(e) => return Err(e),
-    };
-
-    if arg_type != param._type {
-      return Err(vec![Box::new(TypeError::ArgMissmatch {
-        arg_type: Some(arg_type),
-        param: (*param).clone(),
-      })]);
     }
   }
 
-  return if let Some(ret_type) = func.ret_type.clone() {
-    Ok(Some(ret_type.clone()))
-  } else {
-    Ok(None)
+  // The return type of a generic function depends on the type arguments of the call
+  let type_args = match infer_type_args(func, &arg_types) {
+    Ok(type_args) => type_args,
+    Err(e) => return Err(e),
   };
+  match check_bounds(func, &type_args, context) {
+    Ok(()) => Ok(func.ret_type.substitute(&type_args)),
+    Err(e) => Err(e),
+  }
 }
 
-fn type_check_assign(
-  expr: &Node,
-  var: &str,
-  context: &mut Context<(Type, bool)>,
-  funcs: &HashMap<String, Func>,
-) -> Result<Option<Type>, Vec<Box<dyn std::error::Error>>> {
-  // Check the type of the right hand side of assignment
-  let expr_type = match type_check(expr, context, funcs) {
-    Ok(res) => match res {
-      Some(r#type) => r#type,
-      None => return Err(vec![Box::new(TypeError::NonTypeExpression)]),
-    },
-    Err(e) => return Err(e),
+// The signature of a call through a variable of a function type, the parameters
+// of a function type are unnamed
+fn func_type_signature(name: &str, params: Vec<Type>, ret_type: Type) -> Func {
+  Func {
+    name: name.to_string(),
+    type_params: vec![],
+    params: params
+      .into_iter()
+      .map(|r#type| FuncParam {
+        name: "_".to_string(),
+        _type: r#type,
+        mutable: false,
+        location: Location::default(),
+      })
+      .collect(),
+    ret_type: ret_type,
+    body_start: Node::Empty,
+    location: Location::default(),
+  }
+}
+
+// The type of a function used as a value, the type arguments of a generic function
+// can not be inferred without a call
+fn type_of_func(func: &Func) -> Result<Type, Vec<Box<dyn std::error::Error>>> {
+  return match func.type_params.first() {
+    Some((param, _)) => Err(vec![Box::new(TypeError::UninferableTypeParam {
+      func: func.name.clone(),
+      param: param.clone(),
+    })]),
+    None => Ok(Type::Func(
+      func
+        .params
+        .iter()
+        .map(|param| param._type.clone())
+        .collect(),
+      Box::new(func.ret_type.clone()),
+    )),
   };
+}
 
-  match context.get_var_type(&var) {
-    Some((r#type, mutable)) => {
-      if !mutable {
-        return Err(vec![Box::new(TypeError::ImmutableAssignment {
-          var: var.to_string(),
-        })]);
-      }
-      return if *r#type != expr_type {
-        Err(vec![Box::new(TypeError::AssignMissmatch {
-          var: var.to_string(),
-          r#type: r#type.clone(),
-          expr_type: expr_type.clone(),
-        })])
-      } else {
-        Ok(None)
-      };
-    }
-    None => {
-      return Err(vec![Box::new(UnknownVarError {
-        name: var.to_string(),
-      })]);
+// Reports each parameter declared again after an earlier parameter of the same name,
+// the function is None for a closure
+fn check_duplicate_params(
+  params: &Vec<FuncParam>,
+  func: Option<&str>,
+) -> Vec<Box<dyn std::error::Error>> {
+  let mut errors: Vec<Box<dyn std::error::Error>> = vec![];
+  for (i, param) in params.iter().enumerate() {
+    if let Some(first) = params[..i].iter().find(|first| first.name == param.name) {
+      errors.push(Box::new(TypeError::DuplicateParam {
+        name: param.name.clone(),
+        func: func.map(|func| func.to_string()),
+        first: first.location,
+        second: param.location,
+      }));
     }
   }
+  errors
 }
 
-fn type_check_function(
-  func: &Func,
+// The body of a closure is checked in the scope it is declared in, extended by the
+// parameters of the closure
+fn type_check_closure(
+  params: &Vec<FuncParam>,
+  body: &Node,
+  context: &mut Context<(Type, bool)>,
   funcs: &HashMap<String, Func>,
-) -> Result<(), Vec<Box<dyn std::error::Error>>> {
-  let mut context: Context<(Type, bool)> = Context::from(func);
-
-  context.push(Scope::from(func.params.clone()));
+) -> Result<Type, Vec<Box<dyn std::error::Error>>> {
+  for param in params.iter() {
+    if let Err(e) = check_type_exists(
+      &param._type,
+      &context.current_func.type_params,
+      &context.structs,
+      &context.enums,
+    ) {
+      return Err(e);
+    }
+  }
 
-  // Corner case: If the function is empty
-  if let Node::Empty = func.body_start {
-    return if let Some(_) = func.ret_type {
-      Err(vec![Box::new(TypeError::MissingReturn {
-        func_name: func.name.clone(),
-        ret_type: func.ret_type.clone().unwrap(),
-      })])
-    } else {
-      Ok(())
-    };
+  let errors = check_duplicate_params(params, None);
+  if errors.len() != 0 {
+    return Err(errors);
   }
+  context.push(Scope::from(params.clone()));
+  let ret_type = type_check_expr(body, context, funcs);
+  context.pop();
+  match ret_type {
+    Ok(ret_type) => Ok(Type::Func(
+      params.iter().map(|param| param._type.clone()).collect(),
+      Box::new(ret_type),
+    )),
+    Err(e) => Err(e),
+  }
+}
 
-  match type_check(&func.body_start, &mut context, &funcs) {
-    Ok(res) => {
-      if let None = res {
-        if let Some(_) = func.ret_type {
-          return Err(vec![Box::new(TypeError::MissingReturn {
-            func_name: func.name.clone(),
-            ret_type: func.ret_type.clone().unwrap(),
-          })]);
-        }
+// Check argument/parameter length
+fn check_arg_count(
+  func: &str,
+  params: &[FuncParam],
+  received: usize,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  return if received > params.len() {
+    Err(vec![Box::new(TypeError::TooManyArgs {
+      func: func.to_string(),
+      expected: params.len(),
+      received: received,
+    })])
+  } else if received < params.len() {
+    Err(vec![Box::new(TypeError::MissingArgs {
+      func: func.to_string(),
+      missing: params[received..].to_vec(),
+    })])
+  } else {
+    Ok(())
+  };
+}
+
+// Whether a type implements a trait, the type parameters of the function being
+// checked implement the traits bounding them
+fn implements(r#type: &Type, r#trait: &str, context: &Context<(Type, bool)>) -> bool {
+  match r#type {
+    Type::Named(name) if context.current_func.is_type_param(name) => context
+      .current_func
+      .get_bounds(name)
+      .map_or(false, |bounds| bounds.iter().any(|bound| bound == r#trait)),
+    _ => context
+      .impls
+      .iter()
+      .any(|r#impl| r#impl.r#trait == r#trait && r#impl.r#type == *r#type),
+  }
+}
+
+// Checks that the type arguments of a call implement the traits bounding the type
+// parameters of the function
+fn check_bounds(
+  func: &Func,
+  type_args: &HashMap<String, Type>,
+  context: &Context<(Type, bool)>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  let mut errors: Vec<Box<dyn std::error::Error>> = vec![];
+  for (param, bounds) in func.type_params.iter() {
+    for bound in bounds.iter() {
+      if !implements(&type_args[param], bound, context) {
+        errors.push(Box::new(TypeError::MissingImpl {
+          r#trait: bound.clone(),
+          r#type: type_args[param].clone(),
+        }));
       }
-      Ok(())
     }
-    Err(errors) => Err(errors),
   }
-}
 
-fn type_check_op(
-  left: &Node,
-  op: &Opcode,
-  right: &Node,
-  context: &mut Context<(Type, bool)>,
-  funcs: &HashMap<String, Func>,
-) -> Result<Option<Type>, Vec<Box<dyn std::error::Error>>> {
-  // Extract the types out of the operands.
-  // We don't care about the second value of the tuple (mutable)
-  // as an expression is always immutable.
-  let type1 = match type_check(left, context, funcs) {
-    Ok(res) => match res {
-      Some(r#type) => Some(r#type),
-      None => None,
-    },
-    Err(e) => return Err(e),
+  return if errors.len() == 0 {
+    Ok(())
+  } else {
+    Err(errors)
   };
-  let type2 = match type_check(right, context, funcs) {
-    Ok(res) => match res {
-      Some(r#type) => Some(r#type),
-      None => None,
+}
+
+// Matches the type of a parameter against the type of an argument, binding the type
+// parameters of the function it contains. Returns whether the types match.
+fn bind_type_args(
+  param_type: &Type,
+  arg_type: &Type,
+  func: &Func,
+  type_args: &mut HashMap<String, Type>,
+) -> Result<bool, Vec<Box<dyn std::error::Error>>> {
+  match (param_type, arg_type) {
+    (Type::Named(name), _) if func.is_type_param(name) => match type_args.get(name) {
+      Some(bound) if bound != arg_type => {
+        Err(vec![Box::new(TypeError::TypeParamMissmatch {
+          func: func.name.clone(),
+          param: name.clone(),
+          first: bound.clone(),
+          second: arg_type.clone(),
+        })])
+      }
+      Some(_) => Ok(true),
+      None => {
+        type_args.insert(name.clone(), arg_type.clone());
+        Ok(true)
+      }
     },
-    Err(e) => return Err(e),
-  };
+    (Type::Array(param_type, param_len), Type::Array(arg_type, arg_len))
+      if param_len == arg_len =>
+    {
+      bind_type_args(param_type, arg_type, func, type_args)
+    }
+    (Type::Tuple(param_types), Type::Tuple(arg_types))
+      if param_types.len() == arg_types.len() =>
+    {
+      for (param_type, arg_type) in param_types.iter().zip(arg_types) {
+        match bind_type_args(param_type, arg_type, func, type_args) {
+          Ok(true) => (),
+          res => return res,
+        }
+      }
+      Ok(true)
+    }
+    (Type::Func(param_params, param_ret), Type::Func(arg_params, arg_ret))
+      if param_params.len() == arg_params.len() =>
+    {
+      for (param_type, arg_type) in param_params.iter().zip(arg_params) {
+        match bind_type_args(param_type, arg_type, func, type_args) {
+          Ok(true) => (),
+          res => return res,
+        }
+      }
+      bind_type_args(param_ret, arg_ret, func, type_args)
+    }
+    _ => Ok(param_type == arg_type),
+  }
+}
 
-  return if type1 == type2 {
-    match op {
-      // This match is pretty ugly but is needed since arithmetic operations
-      // evaluate to the type of their operands where as logical operations always
-      // evaluate to booleans.
-      Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Div => Ok(match type1 {
-        Some(r#type) => Some(r#type),
-        None => None,
-      }),
-      _ => Ok(Some(Type::Bool)),
+// Infers the type arguments of a call from the types of its arguments, every type
+// parameter of the function has to be bound by the type of some parameter.
+pub(crate) fn infer_type_args(
+  func: &Func,
+  arg_types: &Vec<Type>,
+) -> Result<HashMap<String, Type>, Vec<Box<dyn std::error::Error>>> {
+  let mut type_args = HashMap::new();
+  for (arg_type, param) in arg_types.iter().zip(&func.params) {
+    match bind_type_args(&param._type, arg_type, func, &mut type_args) {
+      Ok(true) => (),
+      Ok(false) => {
+        return Err(vec![Box::new(TypeError::ArgMissmatch {
+          arg_type: arg_type.clone(),
+          param: (*param).clone(),
+        })])
+      }
+      Err(e) => return Err(e),
     }
+  }
+
+  let errors: Vec<Box<dyn std::error::Error>> = func
+    .type_params
+    .iter()
+    .filter(|(param, _)| !type_args.contains_key(param))
+    .map(|(param, _)| -> Box<dyn std::error::Error> {
+      Box::new(TypeError::UninferableTypeParam {
+        func: func.name.clone(),
+        param: param.clone(),
+      })
+    })
+    .collect();
+  return if errors.len() == 0 {
+    Ok(type_args)
   } else {
-    Err(vec![Box::new(TypeError::OperatorMissmatch {
-      expr: Node::Op(
-        Box::new((*left).clone()),
-        (*op).clone(),
-        Box::new((*right).clone()),
-      ),
-      op: (*op).clone(),
-      type_left: if let Some(r#type) = type1 {
-        Some(r#type)
-      } else {
-        None
-      },
-      type_right: if let Some(r#type) = type2 {
-        Some(r#type)
-      } else {
-        None
-      },
-    })])
+    Err(errors)
   };
 }
 
-fn type_check_return(
+fn type_check_assign(
   expr: &Node,
+  var: &str,
   context: &mut Context<(Type, bool)>,
   funcs: &HashMap<String, Func>,
-) -> Result<Option<Type>, Vec<Box<dyn std::error::Error>>> {
-  let expr_type = match type_check(expr, context, funcs) {
-    Ok(res) => match res {
-      Some(r#type) => r#type,
-      None => return Err(vec![Box::new(TypeError::NonTypeExpression {})]),
-    },
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  // Check the type of the right hand side of assignment
+  let expr_type = match type_check_expr(expr, context, funcs) {
+    Ok(r#type) => r#type,
     Err(e) => return Err(e),
   };
-  match &context.current_func.ret_type {
-    Some(r#type) => {
-      if *r#type == expr_type {
-        return Ok(Some(r#type.clone()));
-      } else {
-        return Err(vec![Box::new(TypeError::InvalidReturnType {
-          func: context.current_func.clone(),
-          expr_type: expr_type,
+
+  match context.get_var_type(&var) {
+    Some((r#type, mutable)) => {
+      if !mutable {
+        return Err(vec![Box::new(TypeError::ImmutableAssignment {
+          var: var.to_string(),
         })]);
       }
+      return if *r#type != expr_type {
+        Err(vec![Box::new(TypeError::AssignMissmatch {
+          var: var.to_string(),
+          r#type: r#type.clone(),
+          expr_type: expr_type.clone(),
+        })])
+      } else {
+        Ok(())
+      };
     }
     None => {
-      return Err(vec![Box::new(TypeError::InvalidReturnType {
-        func: context.current_func.clone(),
-        expr_type: expr_type,
+      return Err(vec![Box::new(UnknownVarError {
+        name: var.to_string(),
       })]);
     }
   }
 }
 
-fn type_check(
-  node: &Node,
+fn type_check_array(
+  elements: &Vec<Node>,
   context: &mut Context<(Type, bool)>,
   funcs: &HashMap<String, Func>,
-) -> Result<Option<Type>, Vec<Box<dyn std::error::Error>>> {
-  let mut next_instr: &Option<Box<Node>> = &None;
-  let res: Result<Option<Type>, Vec<Box<dyn std::error::Error>>> = match node {
-    Node::Number(_) => Ok(Some(Type::Int)),
-    Node::Bool(_) => Ok(Some(Type::Bool)),
-    Node::Var(var) => match context.get_var_type(&var) {
-      Some((r#type, _)) => Ok(Some((*r#type).clone())),
-      None => Err(vec![Box::new(UnknownVarError { name: var.clone() })]),
-    },
-    Node::Op(left, op, right) => type_check_op(left, op, right, context, funcs),
-    Node::Assign(var, expr, next_node) => {
-      next_instr = next_node;
-      type_check_assign(expr, var, context, funcs)
-    }
-    Node::Let(name, r#type, mutable, expr, next_node) => {
-      next_instr = next_node;
-      type_check_let(name, r#type, *mutable, expr, context, funcs)
-    }
-    Node::FuncCall(func_name, args, next_node) => {
-      next_instr = next_node;
-      type_check_func_call(func_name, args, context, funcs)
+) -> Result<Type, Vec<Box<dyn std::error::Error>>> {
+  let mut errors: Vec<Box<dyn std::error::Error>> = vec![];
+
+  // All elements must be of the same type as the first element.
+  // The grammar does not allow empty array literals.
+  let element_type = match type_check_expr(&elements[0], context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
+  for element in elements.iter().skip(1) {
+    match type_check_expr(element, context, funcs) {
+      Ok(r#type) => {
+        if r#type != element_type {
+          errors.push(Box::new(TypeError::ArrayElementMissmatch {
+            expr: element.clone(),
+            expected: element_type.clone(),
+            found: r#type,
+          }));
+        }
+      }
+      Err(mut e) => errors.append(&mut e),
     }
-    Node::If(condition, then_body, else_body, next_node) => {
-      next_instr = next_node;
-      let mut errors = vec![];
+  }
 
-      //Continue: Implement if statement branch checks
+  return if errors.len() == 0 {
+    Ok(Type::Array(Box::new(element_type), elements.len()))
+  } else {
+    Err(errors)
+  };
+}
 
-      // Type check condition
-      let res = type_check(condition, context, funcs);
-      if let Err(mut e) = res {
-        errors.append(&mut e);
-      }
+fn type_check_index(
+  array: &Node,
+  index: &Node,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Type, Vec<Box<dyn std::error::Error>>> {
+  let array_type = match type_check_expr(array, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
+  let index_type = match type_check_expr(index, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
 
-      // Type check then body
-      if let Err(mut e) = type_check(then_body, context, funcs) {
-        errors.append(&mut e);
-      }
+  if index_type != Type::Int {
+    return Err(vec![Box::new(TypeError::NonIntegerIndex {
+      expr: index.clone(),
+      r#type: index_type,
+    })]);
+  }
+  match array_type {
+    Type::Array(element_type, _) => Ok(*element_type),
+    r#type => Err(vec![Box::new(TypeError::NonArrayIndex {
+      expr: array.clone(),
+      r#type: r#type,
+    })]),
+  }
+}
 
-      // Type check else body
-      if let Some(else_body) = else_body {
-        if let Err(mut e) = type_check(else_body, context, funcs) {
-          errors.append(&mut e);
-        }
-      }
+fn type_check_tuple_field(
+  tuple: &Node,
+  field: usize,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Type, Vec<Box<dyn std::error::Error>>> {
+  let tuple_type = match type_check_expr(tuple, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
 
-      if errors.len() != 0 {
-        Err(errors)
-      } else {
-        Ok(None) //TODO: This should not return none
+  match tuple_type {
+    Type::Tuple(mut types) if field < types.len() => Ok(types.swap_remove(field)),
+    r#type => Err(vec![Box::new(TypeError::UnknownTupleField {
+      expr: tuple.clone(),
+      field: field,
+      r#type: r#type,
+    })]),
+  }
+}
+
+fn type_check_struct(
+  name: &str,
+  fields: &Vec<(String, Node)>,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Type, Vec<Box<dyn std::error::Error>>> {
+  let r#struct = match context.structs.get(name) {
+    Some(r#struct) => r#struct.clone(),
+    None => {
+      return Err(vec![Box::new(TypeError::UnknownType {
+        name: name.to_string(),
+      })])
+    }
+  };
+  let mut errors: Vec<Box<dyn std::error::Error>> = vec![];
+
+  // Every field has to be initialized exactly once with a value of its type
+  let mut initialized: Vec<&str> = vec![];
+  for (field, expr) in fields.iter() {
+    if initialized.contains(&field.as_str()) {
+      errors.push(Box::new(TypeError::DuplicateField {
+        r#struct: name.to_string(),
+        field: field.clone(),
+      }));
+      continue;
+    }
+    initialized.push(field);
+
+    let field_type = match r#struct.get_field(field) {
+      Some((_, r#type)) => r#type,
+      None => {
+        errors.push(Box::new(TypeError::UnknownField {
+          r#type: Type::Named(name.to_string()),
+          field: field.clone(),
+        }));
+        continue;
+      }
+    };
+    match type_check_expr(expr, context, funcs) {
+      Ok(r#type) => {
+        if r#type != *field_type {
+          errors.push(Box::new(TypeError::FieldMissmatch {
+            r#struct: name.to_string(),
+            field: field.clone(),
+            expected: field_type.clone(),
+            found: r#type,
+          }));
+        }
       }
+      Err(mut e) => errors.append(&mut e),
     }
-    Node::While(condition, then_body, next_node) => {
-      next_instr = next_node;
-      type_check_while(condition, then_body, context, funcs)
+  }
+
+  let missing: Vec<String> = r#struct
+    .fields
+    .iter()
+    .filter(|(field, _)| !initialized.contains(&field.as_str()))
+    .map(|(field, _)| field.clone())
+    .collect();
+  if missing.len() != 0 {
+    errors.push(Box::new(TypeError::MissingFields {
+      r#struct: name.to_string(),
+      missing: missing,
+    }));
+  }
+
+  return if errors.len() == 0 {
+    Ok(Type::Named(name.to_string()))
+  } else {
+    Err(errors)
+  };
+}
+
+fn type_check_field(
+  r#struct: &Node,
+  field: &str,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Type, Vec<Box<dyn std::error::Error>>> {
+  let struct_type = match type_check_expr(r#struct, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
+
+  if let Type::Named(name) = &struct_type {
+    if let Some((_, r#type)) = context
+      .structs
+      .get(name)
+      .and_then(|r#struct| r#struct.get_field(field))
+    {
+      return Ok(r#type.clone());
     }
-    Node::Return(expr, _) => type_check_return(expr, context, funcs),
-    Node::Empty => Ok(None),
-    _ => unimplemented!(),
+  }
+  Err(vec![Box::new(TypeError::UnknownField {
+    r#type: struct_type,
+    field: field.to_string(),
+  })])
+}
+
+fn type_check_method_call(
+  receiver: &Node,
+  method: &str,
+  args: &Vec<Node>,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Type, Vec<Box<dyn std::error::Error>>> {
+  let receiver_type = match type_check_expr(receiver, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
   };
 
-  //If there is a next node
-  if let Some(next_instr) = next_instr {
-    let mut res_next = type_check(next_instr, context, funcs);
-    //If both this node and and the right subtree resulted in type errors
-    //combine the errors.
-    if let Err(ref mut type_errors) = res_next {
-      if let Err(mut errors) = res {
-        errors.append(type_errors);
-        return Err(errors);
+  match (&receiver_type, method) {
+    (Type::Array(..), "len") | (Type::Str, "len") => {
+      if args.len() != 0 {
+        return Err(vec![Box::new(TypeError::TooManyArgs {
+          func: method.to_string(),
+          expected: 0,
+          received: args.len(),
+        })]);
       }
-      return Err(type_errors.drain(..).collect());
+      Ok(Type::Int)
     }
+    _ => {
+      let signature = match lookup_method(&receiver_type, method, context) {
+        Ok(signature) => signature,
+        Err(e) => return Err(e),
+      };
+      if let Err(e) = check_arg_count(method, &signature.params[1..], args.len()) {
+        return Err(e);
+      }
 
-    if let Err(errors) = res {
-      return Err(errors);
+      // The receiver is the first argument, binding Self
+      let mut arg_types = vec![receiver_type];
+      for arg in args.iter() {
+        match type_check_expr(arg, context, funcs) {
+          Ok(r#type) => arg_types.push(r#type),
+          Err(e) => return Err(e),
+        }
+      }
+      match infer_type_args(&signature, &arg_types) {
+        Ok(type_args) => Ok(signature.ret_type.substitute(&type_args)),
+        Err(e) => Err(e),
+      }
     }
+  }
+}
 
-    return res_next;
-  } else {
-    return res;
+// Returns the signature of a method of a trait implemented by the type of the
+// receiver, the traits of a type parameter are the ones bounding it
+fn lookup_method(
+  receiver_type: &Type,
+  method: &str,
+  context: &Context<(Type, bool)>,
+) -> Result<Func, Vec<Box<dyn std::error::Error>>> {
+  let traits: Vec<&String> = match receiver_type {
+    Type::Named(name) if context.current_func.is_type_param(name) => context
+      .current_func
+      .get_bounds(name)
+      .unwrap()
+      .iter()
+      .collect(),
+    _ => context
+      .impls
+      .iter()
+      .filter(|r#impl| r#impl.r#type == *receiver_type)
+      .map(|r#impl| &r#impl.r#trait)
+      .collect(),
+  };
+  let mut methods: Vec<(&String, &Func)> = traits
+    .into_iter()
+    .filter_map(|r#trait| context.traits.get(r#trait))
+    .filter_map(|r#trait| r#trait.get_method(method).map(|func| (&r#trait.name, func)))
+    .collect();
+
+  return match methods.len() {
+    0 => Err(vec![Box::new(TypeError::UnknownMethod {
+      r#type: receiver_type.clone(),
+      method: method.to_string(),
+    })]),
+    1 => Ok(methods.remove(0).1.clone()),
+    _ => Err(vec![Box::new(TypeError::AmbiguousMethod {
+      r#type: receiver_type.clone(),
+      method: method.to_string(),
+      traits: methods
+        .iter()
+        .map(|(r#trait, _)| (*r#trait).clone())
+        .collect(),
+    })]),
+  };
+}
+
+// The variable that a part of is assigned to has to be mutable
+fn check_place_mutable(
+  place: &Node,
+  context: &Context<(Type, bool)>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  match place {
+    Node::Index(place, _) | Node::Field(place, _) => check_place_mutable(place, context),
+    Node::Var(var) => match context.get_var_type(var) {
+      Some((_, false)) => Err(vec![Box::new(TypeError::ImmutableAssignment {
+        var: var.to_string(),
+      })]),
+      _ => Ok(()),
+    },
+    _ => Ok(()),
   }
 }
 
-#[cfg(test)]
-mod tests {
-  use super::*;
-  use crate::types::{func_param::FuncParam, opcode::Opcode};
+fn type_check_index_assign(
+  array: &Node,
+  index: &Node,
+  expr: &Node,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  let expr_type = match type_check_expr(expr, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
+  let element_type = match type_check_index(array, index, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
 
-  #[test]
-  pub fn test_number() {
+  if let Err(e) = check_place_mutable(array, context) {
+    return Err(e);
+  }
+
+  return if element_type != expr_type {
+    Err(vec![Box::new(TypeError::AssignMissmatch {
+      var: Node::Index(Box::new(array.clone()), Box::new(index.clone()))
+        .expr_into_string(),
+      r#type: element_type,
+      expr_type: expr_type,
+    })])
+  } else {
+    Ok(())
+  };
+}
+
+fn type_check_field_assign(
+  r#struct: &Node,
+  field: &str,
+  expr: &Node,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  let expr_type = match type_check_expr(expr, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
+  let field_type = match type_check_field(r#struct, field, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
+
+  if let Err(e) = check_place_mutable(r#struct, context) {
+    return Err(e);
+  }
+
+  return if field_type != expr_type {
+    Err(vec![Box::new(TypeError::AssignMissmatch {
+      var: Node::Field(Box::new(r#struct.clone()), field.to_string()).expr_into_string(),
+      r#type: field_type,
+      expr_type: expr_type,
+    })])
+  } else {
+    Ok(())
+  };
+}
+
+fn type_check_variant(
+  r#enum: &str,
+  variant: &str,
+  fields: &Vec<Node>,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Type, Vec<Box<dyn std::error::Error>>> {
+  let types = match lookup_variant(r#enum, variant, context) {
+    Ok(types) => types,
+    Err(e) => return Err(e),
+  };
+  if fields.len() != types.len() {
+    return Err(vec![Box::new(TypeError::VariantArityMissmatch {
+      r#enum: r#enum.to_string(),
+      variant: variant.to_string(),
+      expected: types.len(),
+      found: fields.len(),
+    })]);
+  }
+
+  let mut errors: Vec<Box<dyn std::error::Error>> = vec![];
+  for (field, r#type) in fields.iter().zip(types) {
+    match type_check_expr(field, context, funcs) {
+      Ok(field_type) => {
+        if field_type != r#type {
+          errors.push(Box::new(TypeError::VariantFieldMissmatch {
+            r#enum: r#enum.to_string(),
+            variant: variant.to_string(),
+            expected: r#type,
+            found: field_type,
+          }));
+        }
+      }
+      Err(mut e) => errors.append(&mut e),
+    }
+  }
+
+  return if errors.len() == 0 {
+    Ok(Type::Named(r#enum.to_string()))
+  } else {
+    Err(errors)
+  };
+}
+
+// Returns the field types of a variant of an enum
+fn lookup_variant(
+  r#enum: &str,
+  variant: &str,
+  context: &Context<(Type, bool)>,
+) -> Result<Vec<Type>, Vec<Box<dyn std::error::Error>>> {
+  match context.enums.get(r#enum) {
+    Some(enum_dec) => match enum_dec.get_variant(variant) {
+      Some((_, types)) => Ok(types.clone()),
+      None => Err(vec![Box::new(TypeError::UnknownVariant {
+        r#enum: r#enum.to_string(),
+        variant: variant.to_string(),
+      })]),
+    },
+    None => Err(vec![Box::new(TypeError::UnknownType {
+      name: r#enum.to_string(),
+    })]),
+  }
+}
+
+// Checks that a pattern can match values of the type, collecting the variables it
+// binds along with their types.
+pub(crate) fn type_check_pattern(
+  pattern: &Pattern,
+  r#type: &Type,
+  context: &Context<(Type, bool)>,
+  bindings: &mut Vec<(String, Type)>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  match pattern {
+    Pattern::Wildcard => Ok(()),
+    Pattern::Binding(name) => {
+      if bindings.iter().any(|(bound, _)| bound == name) {
+        return Err(vec![Box::new(TypeError::DuplicateBinding {
+          name: name.clone(),
+        })]);
+      }
+      bindings.push((name.clone(), r#type.clone()));
+      Ok(())
+    }
+    Pattern::Variant(r#enum, variant, patterns) => {
+      if *r#type != Type::Named(r#enum.clone()) {
+        return Err(vec![Box::new(TypeError::PatternMissmatch {
+          pattern: pattern.to_str(),
+          r#type: r#type.clone(),
+        })]);
+      }
+      let types = match lookup_variant(r#enum, variant, context) {
+        Ok(types) => types,
+        Err(e) => return Err(e),
+      };
+      if patterns.len() != types.len() {
+        return Err(vec![Box::new(TypeError::VariantArityMissmatch {
+          r#enum: r#enum.clone(),
+          variant: variant.clone(),
+          expected: types.len(),
+          found: patterns.len(),
+        })]);
+      }
+      for (pattern, r#type) in patterns.iter().zip(types.iter()) {
+        if let Err(e) = type_check_pattern(pattern, r#type, context, bindings) {
+          return Err(e);
+        }
+      }
+      Ok(())
+    }
+    Pattern::Int(_) | Pattern::Range(..) | Pattern::Bool(_) => {
+      let expected = if let Pattern::Bool(_) = pattern {
+        Type::Bool
+      } else {
+        Type::Int
+      };
+      if *r#type != expected {
+        return Err(vec![Box::new(TypeError::PatternMissmatch {
+          pattern: pattern.to_str(),
+          r#type: r#type.clone(),
+        })]);
+      }
+      match pattern {
+        Pattern::Range(start, end) if start > end => {
+          Err(vec![Box::new(TypeError::EmptyRange {
+            start: *start,
+            end: *end,
+          })])
+        }
+        _ => Ok(()),
+      }
+    }
+    Pattern::Or(alternatives) => {
+      // Every alternative has to bind the same variables with the same types
+      let mut alternative_bindings: Option<Vec<(String, Type)>> = None;
+      for alternative in alternatives.iter() {
+        let mut bound = vec![];
+        if let Err(e) = type_check_pattern(alternative, r#type, context, &mut bound) {
+          return Err(e);
+        }
+        bound.sort_by(|(left, _), (right, _)| left.cmp(right));
+        match alternative_bindings {
+          Some(ref expected) if *expected != bound => {
+            return Err(vec![Box::new(TypeError::AlternativeBindingMissmatch {
+              pattern: pattern.to_str(),
+            })]);
+          }
+          Some(_) => (),
+          None => alternative_bindings = Some(bound),
+        }
+      }
+      for (name, r#type) in alternative_bindings.unwrap_or(vec![]) {
+        if bindings.iter().any(|(bound, _)| *bound == name) {
+          return Err(vec![Box::new(TypeError::DuplicateBinding { name: name })]);
+        }
+        bindings.push((name, r#type));
+      }
+      Ok(())
+    }
+  }
+}
+
+// Type checks the patterns and guards of the arms of a match, returning the
+// variables bound by each arm. The arms without guards have to cover all values of
+// the scrutinee, arms that can not be reached are reported as warnings.
+fn type_check_match_arms(
+  scrutinee_type: &Type,
+  arms: &Vec<(Pattern, Option<Node>, Node)>,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Vec<Vec<(String, Type)>>, Vec<Box<dyn std::error::Error>>> {
+  let mut errors: Vec<Box<dyn std::error::Error>> = vec![];
+  let mut arm_bindings = vec![];
+  for (pattern, guard, _) in arms.iter() {
+    let mut bindings = vec![];
+    if let Err(mut e) =
+      type_check_pattern(pattern, scrutinee_type, context, &mut bindings)
+    {
+      errors.append(&mut e);
+    }
+
+    // The guard can refer to the variables bound by the pattern
+    if let Some(guard) = guard {
+      push_bindings(context, bindings.clone());
+      match type_check_expr(guard, context, funcs) {
+        Ok(Type::Bool) => (),
+        Ok(r#type) => errors.push(Box::new(TypeError::NonBooleanExpr {
+          expr: guard.clone(),
+          r#type: r#type,
+        })),
+        Err(mut e) => errors.append(&mut e),
+      }
+      context.pop();
+    }
+    arm_bindings.push(bindings);
+  }
+  if errors.len() != 0 {
+    return Err(errors);
+  }
+
+  let checker = MatchChecker::new(&context.enums);
+  let unguarded: Vec<Pattern> = arms
+    .iter()
+    .filter(|(_, guard, _)| guard.is_none())
+    .map(|(pattern, _, _)| pattern.clone())
+    .collect();
+  if let Some(missing) = checker.missing_pattern(&unguarded, scrutinee_type) {
+    return Err(vec![Box::new(TypeError::NonExhaustiveMatch {
+      missing: missing.to_str(),
+    })]);
+  }
+  let patterns: Vec<(Pattern, bool)> = arms
+    .iter()
+    .map(|(pattern, guard, _)| (pattern.clone(), guard.is_some()))
+    .collect();
+  for i in checker.unreachable_patterns(&patterns, scrutinee_type) {
+    context.warnings.push(TypeWarning::UnreachablePattern {
+      pattern: patterns[i].0.to_str(),
+    });
+  }
+  Ok(arm_bindings)
+}
+
+// Pushes a scope with the immutable variables bound by a pattern
+pub(crate) fn push_bindings(
+  context: &mut Context<(Type, bool)>,
+  bindings: Vec<(String, Type)>,
+) {
+  context.push(Scope::new());
+  for (name, r#type) in bindings {
+    context.insert_type(&name, r#type, false);
+  }
+}
+
+fn type_check_match_expr(
+  scrutinee: &Node,
+  arms: &Vec<(Pattern, Option<Node>, Node)>,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Type, Vec<Box<dyn std::error::Error>>> {
+  let scrutinee_type = match type_check_expr(scrutinee, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
+  let arm_bindings = match type_check_match_arms(&scrutinee_type, arms, context, funcs) {
+    Ok(arm_bindings) => arm_bindings,
+    Err(e) => return Err(e),
+  };
+
+  // All arms must evaluate to the same type
+  let mut errors: Vec<Box<dyn std::error::Error>> = vec![];
+  let mut match_type: Option<Type> = None;
+  for ((_, _, expr), bindings) in arms.iter().zip(arm_bindings) {
+    push_bindings(context, bindings);
+    let res = type_check_expr(expr, context, funcs);
+    context.pop();
+    match (res, &match_type) {
+      (Ok(r#type), None) => match_type = Some(r#type),
+      (Ok(r#type), Some(expected)) => {
+        if r#type != *expected {
+          errors.push(Box::new(TypeError::MatchArmMissmatch {
+            expr: expr.clone(),
+            expected: expected.clone(),
+            found: r#type,
+          }));
+        }
+      }
+      (Err(mut e), _) => errors.append(&mut e),
+    }
+  }
+
+  return if errors.len() == 0 {
+    // A match without arms is on a type without values and never evaluates
+    Ok(match_type.unwrap_or(Type::Unit))
+  } else {
+    Err(errors)
+  };
+}
+
+fn type_check_match_stmt(
+  scrutinee: &Node,
+  arms: &Vec<(Pattern, Option<Node>, Node)>,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  let scrutinee_type = match type_check_expr(scrutinee, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
+  let arm_bindings = match type_check_match_arms(&scrutinee_type, arms, context, funcs) {
+    Ok(arm_bindings) => arm_bindings,
+    Err(e) => return Err(e),
+  };
+
+  let mut errors: Vec<Box<dyn std::error::Error>> = vec![];
+  for ((_, _, body), bindings) in arms.iter().zip(arm_bindings) {
+    push_bindings(context, bindings);
+    if let Err(mut e) = type_check(body, context, funcs) {
+      errors.append(&mut e);
+    }
+    context.pop();
+  }
+
+  return if errors.len() == 0 {
+    Ok(())
+  } else {
+    Err(errors)
+  };
+}
+
+// Type checks a function, returning the warnings found if it is well typed
+fn type_check_function(
+  func: &Func,
+  funcs: &HashMap<String, Func>,
+  structs: &HashMap<String, StructDec>,
+  enums: &HashMap<String, EnumDec>,
+  traits: &HashMap<String, TraitDec>,
+  impls: &Vec<ImplDec>,
+  globals: &Vec<GlobalDec>,
+) -> Result<Vec<TypeWarning>, Vec<Box<dyn std::error::Error>>> {
+  let mut context: Context<(Type, bool)> = Context::from(func);
+  context.structs = structs.clone();
+  context.enums = enums.clone();
+  context.traits = traits.clone();
+  context.impls = impls.clone();
+  context.globals = Scope::from(globals);
+
+  // The signature may only refer to declared types and traits
+  if let Err(e) = check_signature_types(func, structs, enums) {
+    return Err(e);
+  }
+  for bound in func.type_params.iter().flat_map(|(_, bounds)| bounds) {
+    if !traits.contains_key(bound) {
+      return Err(vec![Box::new(TypeError::UnknownTrait {
+        name: bound.clone(),
+      })]);
+    }
+  }
+
+  let errors = check_duplicate_params(&func.params, Some(&func.name));
+  if errors.len() != 0 {
+    return Err(errors);
+  }
+  context.push(Scope::from(func.params.clone()));
+
+  match type_check(&func.body_start, &mut context, &funcs) {
+    Ok(()) => {
+      // A unit function may run to the end of its body without returning
+      if func.ret_type != Type::Unit {
+        if let Some(path) = missing_return_path(&func.body_start) {
+          return Err(vec![Box::new(TypeError::MissingReturn {
+            func_name: func.name.clone(),
+            ret_type: func.ret_type.clone(),
+            path: path,
+          })]);
+        }
+      }
+      let errors = check_definite_assignment(func);
+      return if errors.len() == 0 {
+        Ok(context.warnings)
+      } else {
+        Err(errors)
+      };
+    }
+    Err(errors) => Err(errors),
+  }
+}
+
+// Decides whether a function returns on every path: returns a path through the
+// instructions starting with the node that reaches their end without returning or
+// panicking, described by the conditions taken along it, or None if there is none
+fn missing_return_path(node: &Node) -> Option<Vec<String>> {
+  let mut path = vec![];
+  let mut node = node;
+  loop {
+    match node {
+      Node::Return(..) | Node::Panic(..) => return None,
+      Node::If(condition, then_body, else_body, _) => {
+        let condition = condition.expr_into_string();
+        let branch = match (missing_return_path(then_body), else_body) {
+          (Some(then_path), _) => Some((format!("{} is true", condition), then_path)),
+          (None, Some(else_body)) => missing_return_path(else_body)
+            .map(|else_path| (format!("{} is false", condition), else_path)),
+          (None, None) => Some((format!("{} is false", condition), vec![])),
+        };
+        match branch {
+          Some((taken, mut branch_path)) => {
+            path.push(taken);
+            path.append(&mut branch_path);
+          }
+          None => return None,
+        }
+      }
+      // The body of a loop may not run at all
+      Node::While(condition, ..) => path.push(format!(
+        "the while {} loop ends",
+        condition.expr_into_string()
+      )),
+      Node::Match(scrutinee, arms, _) => {
+        let scrutinee = scrutinee.expr_into_string();
+        let arm = arms.iter().find_map(|(pattern, _, body)| {
+          missing_return_path(body).map(|arm_path| {
+            (
+              format!("{} matches {}", scrutinee, pattern.to_str()),
+              arm_path,
+            )
+          })
+        });
+        match arm {
+          Some((taken, mut arm_path)) => {
+            path.push(taken);
+            path.append(&mut arm_path);
+          }
+          None => return None,
+        }
+      }
+      Node::Let(..)
+      | Node::LetTuple(..)
+      | Node::Declare(..)
+      | Node::FuncCall(..)
+      | Node::Assign(..)
+      | Node::IndexAssign(..)
+      | Node::FieldAssign(..)
+      | Node::Print(..)
+      | Node::Check(..)
+      | Node::FuncDec(..)
+      | Node::DebugContext(_) => (),
+      _ => return Some(path),
+    }
+    node = match node.get_next_instruction() {
+      Some(next) => next,
+      None => return Some(path),
+    };
+  }
+}
+
+fn type_check_op(
+  left: &Node,
+  op: &Opcode,
+  right: &Node,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Type, Vec<Box<dyn std::error::Error>>> {
+  // Extract the types out of the operands.
+  // We don't care about the second value of the tuple (mutable)
+  // as an expression is always immutable.
+  let type1 = match type_check_expr(left, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
+  let type2 = match type_check_expr(right, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
+
+  // Operators are only defined for scalar operands of the same type, chars and
+  // strings can only be compared for equality
+  let defined = match (&type1, op) {
+    (Type::Int, _) | (Type::Bool, _) => true,
+    (Type::Char, Opcode::Eq) | (Type::Char, Opcode::Neq) => true,
+    (Type::Str, Opcode::Eq) | (Type::Str, Opcode::Neq) => true,
+    _ => false,
+  };
+  return if type1 == type2 && defined {
+    match op {
+      // This match is pretty ugly but is needed since arithmetic operations
+      // evaluate to the type of their operands where as logical operations always
+      // evaluate to booleans.
+      Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Div => Ok(type1),
+      _ => Ok(Type::Bool),
+    }
+  } else {
+    Err(vec![Box::new(TypeError::OperatorMissmatch {
+      expr: Node::Op(
+        Box::new((*left).clone()),
+        (*op).clone(),
+        Box::new((*right).clone()),
+      ),
+      op: (*op).clone(),
+      type_left: type1,
+      type_right: type2,
+    })])
+  };
+}
+
+// The condition of a contract is a boolean expression over the variables in scope
+fn type_check_contract(
+  condition: &Node,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  match type_check_expr(condition, context, funcs) {
+    Ok(Type::Bool) => Ok(()),
+    Ok(r#type) => Err(vec![Box::new(TypeError::NonBooleanExpr {
+      expr: condition.clone(),
+      r#type: r#type,
+    })]),
+    Err(e) => Err(e),
+  }
+}
+
+// Only scalars and strings can be formatted by the {} placeholders
+fn type_check_print(
+  args: &Vec<Node>,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  let mut errors: Vec<Box<dyn std::error::Error>> = vec![];
+  for arg in args.iter() {
+    match type_check_expr(arg, context, funcs) {
+      Ok(Type::Int) | Ok(Type::Bool) | Ok(Type::Char) | Ok(Type::Str) => (),
+      Ok(r#type) => errors.push(Box::new(TypeError::NotPrintable {
+        expr: arg.clone(),
+        r#type: r#type,
+      })),
+      Err(mut e) => errors.append(&mut e),
+    }
+  }
+
+  return if errors.len() == 0 {
+    Ok(())
+  } else {
+    Err(errors)
+  };
+}
+
+fn type_check_return(
+  expr: &Node,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  let expr_type = match type_check_expr(expr, context, funcs) {
+    Ok(r#type) => r#type,
+    Err(e) => return Err(e),
+  };
+  let ret_type = &context.current_func.ret_type;
+  return if *ret_type == expr_type {
+    Ok(())
+  } else {
+    Err(vec![Box::new(TypeError::InvalidReturnType {
+      func: context.current_func.clone(),
+      expr_type: expr_type,
+    })])
+  };
+}
+
+// Type checks an expression, every expression evaluates to a value of some type
+// (unit for calls to functions without a return type).
+pub(crate) fn type_check_expr(
+  node: &Node,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<Type, Vec<Box<dyn std::error::Error>>> {
+  // Declarations keep the aliases they are written with for diagnostics, the types
+  // of expressions are resolved so that they can be taken apart
+  let r#type: Result<Type, Vec<Box<dyn std::error::Error>>> = match node {
+    Node::Number(_) => Ok(Type::Int),
+    Node::Bool(_) => Ok(Type::Bool),
+    Node::Char(_) => Ok(Type::Char),
+    Node::Str(_) => Ok(Type::Str),
+    Node::Unit => Ok(Type::Unit),
+    Node::Var(var) => match (context.get_var_type(&var), funcs.get(var)) {
+      (Some((r#type, _)), _) => Ok((*r#type).clone()),
+      (None, Some(func)) => type_of_func(func),
+      (None, None) => Err(vec![Box::new(UnknownVarError { name: var.clone() })]),
+    },
+    Node::Op(left, op, right) => type_check_op(left, op, right, context, funcs),
+    Node::FuncCall(func_name, args, _) => {
+      type_check_func_call(func_name, args, context, funcs)
+    }
+    Node::Array(elements) => type_check_array(elements, context, funcs),
+    Node::ArrayRepeat(element, len) => match type_check_expr(element, context, funcs) {
+      Ok(r#type) => Ok(Type::Array(Box::new(r#type), *len)),
+      Err(e) => Err(e),
+    },
+    Node::Index(array, index) => type_check_index(array, index, context, funcs),
+    Node::Tuple(elements) => {
+      let mut types = vec![];
+      for element in elements.iter() {
+        match type_check_expr(element, context, funcs) {
+          Ok(r#type) => types.push(r#type),
+          Err(e) => return Err(e),
+        }
+      }
+      Ok(Type::Tuple(types))
+    }
+    Node::TupleField(tuple, field) => {
+      type_check_tuple_field(tuple, *field, context, funcs)
+    }
+    Node::Struct(name, fields) => type_check_struct(name, fields, context, funcs),
+    Node::Field(r#struct, field) => type_check_field(r#struct, field, context, funcs),
+    Node::Variant(r#enum, variant, fields) => {
+      type_check_variant(r#enum, variant, fields, context, funcs)
+    }
+    Node::Match(scrutinee, arms, _) => {
+      type_check_match_expr(scrutinee, arms, context, funcs)
+    }
+    Node::MethodCall(receiver, method, args) => {
+      type_check_method_call(receiver, method, args, context, funcs)
+    }
+    Node::Closure(params, _, body, _) => type_check_closure(params, body, context, funcs),
+    _ => unreachable!("Cannot type check node {:#?} as an expression", node),
+  };
+  r#type.map(|r#type| r#type.resolved())
+}
+
+// Type checks a statement and the instructions following it.
+// Statements do not evaluate to a value, the result is instead the type returned
+// by a return statement (if any) reached from this statement.
+fn type_check(
+  node: &Node,
+  context: &mut Context<(Type, bool)>,
+  funcs: &HashMap<String, Func>,
+) -> Result<(), Vec<Box<dyn std::error::Error>>> {
+  let mut next_instr: &Option<Box<Node>> = &None;
+  let res: Result<(), Vec<Box<dyn std::error::Error>>> = match node {
+    Node::Number(_)
+    | Node::Bool(_)
+    | Node::Char(_)
+    | Node::Str(_)
+    | Node::Unit
+    | Node::Var(_)
+    | Node::Array(_)
+    | Node::ArrayRepeat(..)
+    | Node::Index(..)
+    | Node::Tuple(_)
+    | Node::TupleField(..)
+    | Node::Struct(..)
+    | Node::Field(..)
+    | Node::Variant(..)
+    | Node::MethodCall(..)
+    | Node::Closure(..)
+    | Node::Op(..) => type_check_expr(node, context, funcs).map(|_| ()),
+    Node::Assign(var, expr, next_node) => {
+      next_instr = next_node;
+      type_check_assign(expr, var, context, funcs)
+    }
+    Node::IndexAssign(array, index, expr, next_node) => {
+      next_instr = next_node;
+      type_check_index_assign(array, index, expr, context, funcs)
+    }
+    Node::FieldAssign(r#struct, field, expr, next_node) => {
+      next_instr = next_node;
+      type_check_field_assign(r#struct, field, expr, context, funcs)
+    }
+    Node::Let(name, r#type, mutable, expr, next_node) => {
+      next_instr = next_node;
+      type_check_let(name, r#type, *mutable, expr, context, funcs)
+    }
+    // A variable declared without a value can be assigned once even if it is not
+    // mutable, which is checked by the definite assignment analysis
+    Node::Declare(name, r#type, _, next_node) => {
+      next_instr = next_node;
+      context.insert_type(name, r#type.clone(), true);
+      Ok(())
+    }
+    Node::LetTuple(names, r#type, expr, next_node) => {
+      next_instr = next_node;
+      type_check_let_tuple(names, r#type, expr, context, funcs)
+    }
+    Node::FuncCall(func_name, args, next_node) => {
+      next_instr = next_node;
+      // The value of a function call statement is discarded
+      type_check_func_call(func_name, args, context, funcs).map(|_| ())
+    }
+    Node::If(condition, then_body, else_body, next_node) => {
+      next_instr = next_node;
+      let mut errors = vec![];
+
+      // Type check condition
+      let res = type_check_expr(condition, context, funcs);
+      if let Err(mut e) = res {
+        errors.append(&mut e);
+      }
+
+      if let Err(mut e) = type_check(then_body, context, funcs) {
+        errors.append(&mut e);
+      }
+      if let Some(else_body) = else_body {
+        if let Err(mut e) = type_check(else_body, context, funcs) {
+          errors.append(&mut e);
+        }
+      }
+
+      if errors.len() == 0 {
+        Ok(())
+      } else {
+        Err(errors)
+      }
+    }
+    Node::While(condition, then_body, next_node) => {
+      next_instr = next_node;
+      type_check_while(condition, then_body, context, funcs)
+    }
+    Node::Match(scrutinee, arms, next_node) => {
+      next_instr = next_node;
+      type_check_match_stmt(scrutinee, arms, context, funcs)
+    }
+    Node::Return(expr, _) => type_check_return(expr, context, funcs),
+    Node::Print(_, args, _, next_node) => {
+      next_instr = next_node;
+      type_check_print(args, context, funcs)
+    }
+    Node::Check(condition, _, _, next_node) => {
+      next_instr = next_node;
+      type_check_contract(condition, context, funcs)
+    }
+    Node::Panic(_, args, _, next_node) => {
+      next_instr = next_node;
+      type_check_print(args, context, funcs)
+    }
+    Node::DebugContext(next_node) => {
+      next_instr = next_node;
+      Ok(())
+    }
+    Node::Empty => Ok(()),
+    Node::FuncDec(..) => unreachable!("Nested functions are hoisted when parsing"),
+    Node::MacroCall(..) => unreachable!("Macros are expanded when parsing"),
+  };
+
+  //If there is a next node
+  if let Some(next_instr) = next_instr {
+    let mut res_next = type_check(next_instr, context, funcs);
+    //If both this node and and the right subtree resulted in type errors
+    //combine the errors.
+    if let Err(ref mut type_errors) = res_next {
+      if let Err(mut errors) = res {
+        errors.append(type_errors);
+        return Err(errors);
+      }
+      return Err(type_errors.drain(..).collect());
+    }
+
+    if let Err(errors) = res {
+      return Err(errors);
+    }
+    return res_next;
+  } else {
+    return res;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::types::{func_param::FuncParam, opcode::Opcode, struct_dec::StructDec};
+
+  #[test]
+  pub fn test_number() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    assert_eq!(
+      type_check_expr(&Node::Number(2), &mut context, &HashMap::new()).unwrap(),
+      Type::Int
+    );
+  }
+
+  #[test]
+  pub fn test_bool() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    assert_eq!(
+      type_check_expr(&Node::Bool(true), &mut context, &HashMap::new()).unwrap(),
+      Type::Bool
+    );
+  }
+
+  #[test]
+  pub fn test_operation_int_int() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    assert_eq!(
+      type_check_expr(
+        &Node::Op(
+          Box::new(Node::Number(2)),
+          Opcode::Add,
+          Box::new(Node::Number(2))
+        ),
+        &mut context,
+        &HashMap::new()
+      )
+      .unwrap(),
+      Type::Int
+    );
+  }
+
+  #[test]
+  pub fn test_operation_int_bool() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    assert!(!type_check_expr(
+      &Node::Op(
+        Box::new(Node::Number(2)),
+        Opcode::Add,
+        Box::new(Node::Bool(true))
+      ),
+      &mut context,
+      &HashMap::new()
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn test_operation_bool_int() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    assert!(!type_check_expr(
+      &Node::Op(
+        Box::new(Node::Bool(true)),
+        Opcode::And,
+        Box::new(Node::Number(2))
+      ),
+      &mut context,
+      &HashMap::new()
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn test_operation_bool_bool() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    assert_eq!(
+      type_check_expr(
+        &Node::Op(
+          Box::new(Node::Bool(true)),
+          Opcode::And,
+          Box::new(Node::Bool(true))
+        ),
+        &mut context,
+        &HashMap::new()
+      )
+      .unwrap(),
+      Type::Bool
+    );
+  }
+
+  #[test]
+  pub fn test_func_call_int() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs: HashMap<String, Func> = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    assert_eq!(
+      type_check_expr(
+        &Node::FuncCall("foo".to_string(), vec!(), None),
+        &mut context,
+        &funcs
+      )
+      .unwrap(),
+      Type::Int
+    );
+  }
+
+  #[test]
+  pub fn test_func_call_bool() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Bool,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    assert_eq!(
+      type_check_expr(
+        &Node::FuncCall("foo".to_string(), vec!(), None),
+        &mut context,
+        &funcs
+      )
+      .unwrap(),
+      Type::Bool
+    );
+  }
+
+  #[test]
+  pub fn test_func_call_args_int() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![FuncParam {
+        name: "a".to_string(),
+        _type: Type::Int,
+        mutable: false,
+        location: Location::default(),
+      }],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    assert_eq!(
+      type_check_expr(
+        &Node::FuncCall("foo".to_string(), vec!(Node::Number(2)), None),
+        &mut context,
+        &funcs
+      )
+      .unwrap(),
+      Type::Int
+    );
+  }
+
+  #[test]
+  pub fn test_func_call_args_bool() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![FuncParam {
+        name: "a".to_string(),
+        _type: Type::Bool,
+        mutable: false,
+        location: Location::default(),
+      }],
+      ret_type: Type::Bool,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    assert_eq!(
+      type_check_expr(
+        &Node::FuncCall("foo".to_string(), vec!(Node::Bool(true)), None),
+        &mut context,
+        &funcs
+      )
+      .unwrap(),
+      Type::Bool
+    );
+  }
+
+  #[test]
+  pub fn test_func_call_args_invalid_type() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![FuncParam {
+        name: "a".to_string(),
+        _type: Type::Int,
+        mutable: false,
+        location: Location::default(),
+      }],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    assert!(!type_check_expr(
+      &Node::FuncCall("foo".to_string(), vec!(Node::Bool(true)), None),
+      &mut context,
+      &funcs
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn test_func_return_int() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    assert!(type_check(
+      &Node::Return(Box::new(Node::Number(5)), None),
+      &mut context,
+      &funcs
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn debug_context_is_a_statement() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![FuncParam {
+        name: "n".to_string(),
+        _type: Type::Int,
+        mutable: false,
+        location: Location::default(),
+      }],
+      ret_type: Type::Unit,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    context.push(Scope::from(func_dec.params.clone()));
+    let print = Node::Print(
+      vec!["".to_string(), "".to_string()],
+      vec![Node::Var("n".to_string())],
+      true,
+      None,
+    );
+    let mut debug_context = Node::DebugContext(None);
+    debug_context.attach_next_instruction(print);
+    assert!(type_check(&debug_context, &mut context, &HashMap::new()).is_ok());
+  }
+
+  #[test]
+  pub fn test_func_return_int_expecting_bool() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Bool,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    assert!(!type_check(
+      &Node::Return(Box::new(Node::Number(5)), None),
+      &mut context,
+      &funcs
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn test_func_return_bool() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Bool,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    assert!(type_check(
+      &Node::Return(Box::new(Node::Bool(true)), None),
+      &mut context,
+      &funcs
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn test_func_missing_arg() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![FuncParam {
+        name: "a".to_string(),
+        _type: Type::Int,
+        mutable: true,
+        location: Location::default(),
+      }],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    assert!(!type_check_expr(
+      &Box::new(Node::FuncCall("foo".to_string(), vec!(), None)),
+      &mut context,
+      &funcs
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn test_func_too_many_args() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    assert!(!type_check_expr(
+      &Box::new(Node::FuncCall(
+        "foo".to_string(),
+        vec!(Node::Bool(false)),
+        None
+      )),
+      &mut context,
+      &funcs
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn test_func_return_bool_expecting_int() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    assert!(!type_check(
+      &Node::Return(Box::new(Node::Bool(false)), None),
+      &mut context,
+      &funcs
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn test_func_return_func_call() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let func_dec_2 = Func {
+      name: "bar".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    funcs.insert("bar".to_string(), func_dec_2);
+
+    assert!(type_check(
+      &Node::Return(
+        Box::new(Node::FuncCall("bar".to_string(), vec!(), None)),
+        None
+      ),
+      &mut context,
+      &funcs
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn test_return_with_missing_return_type() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Unit,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    assert!(!type_check(
+      &Node::Return(Box::new(Node::Number(2)), None),
+      &mut context,
+      &funcs
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn can_assign_to_mutable() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Unit,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec.clone());
+    context.push(Scope::from(func_dec.params));
+    context.insert_type("a", Type::Int, true);
+    assert!(type_check(
+      &Node::Assign("a".to_string(), Box::new(Node::Number(3)), None),
+      &mut context,
+      &funcs
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn can_not_assign_to_mutable() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Unit,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec.clone());
+    context.push(Scope::from(func_dec.params));
+    context.insert_type("a", Type::Int, false);
+    assert!(!type_check(
+      &Node::Assign("a".to_string(), Box::new(Node::Number(3)), None),
+      &mut context,
+      &funcs
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn no_return_in_returning_function() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
+    };
+    let mut context = Context::from(&func_dec);
+    let mut funcs: HashMap<String, Func> = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec.clone());
+    context.push(Scope::from(func_dec.params.clone()));
+    context.insert_type("a", Type::Int, false);
+    assert!(!type_check_function(
+      &func_dec,
+      &funcs,
+      &HashMap::new(),
+      &HashMap::new(),
+      &HashMap::new(),
+      &vec![],
+      &vec![]
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn empty_function_no_ret_type() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Int),
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    assert_eq!(
-      type_check(&Node::Number(2), &mut context, &HashMap::new()).unwrap(),
-      Some(Type::Int)
-    );
+    let mut funcs: HashMap<String, Func> = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec.clone());
+    context.push(Scope::from(func_dec.params.clone()));
+    context.insert_type("a", Type::Int, false);
+    assert!(type_check_function(
+      &func_dec,
+      &funcs,
+      &HashMap::new(),
+      &HashMap::new(),
+      &HashMap::new(),
+      &vec![],
+      &vec![]
+    )
+    .is_ok());
   }
 
   #[test]
-  pub fn test_bool() {
+  pub fn while_non_boolean_condition() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Int),
-      body_start: Node::Empty,
+      ret_type: Type::Unit,
+      body_start: Node::While(Box::new(Node::Number(4)), Box::new(Node::Empty), None),
+      location: Location::default(),
     };
-    let mut context = Context::from(&func_dec);
-    assert_eq!(
-      type_check(&Node::Bool(true), &mut context, &HashMap::new()).unwrap(),
-      Some(Type::Bool)
-    );
+    let mut funcs: HashMap<String, Func> = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec.clone());
+    assert!(!type_check_function(
+      &func_dec,
+      &funcs,
+      &HashMap::new(),
+      &HashMap::new(),
+      &HashMap::new(),
+      &vec![],
+      &vec![]
+    )
+    .is_ok());
   }
 
   #[test]
-  pub fn test_operation_int_int() {
+  pub fn test_non_mutability_in_if_statement() {
+    let assign = Box::new(Node::Assign(
+      "a".to_string(),
+      Box::new(Node::Number(3)),
+      None,
+    ));
+    let if_statement = Box::new(Node::If(Box::new(Node::Bool(true)), assign, None, None));
+    let let_statement = Node::Let(
+      "a".to_string(),
+      None,
+      false,
+      Box::new(Node::Number(2)),
+      Some(if_statement),
+    );
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Int),
-      body_start: Node::Empty,
+      ret_type: Type::Unit,
+      body_start: let_statement,
+      location: Location::default(),
     };
-    let mut context = Context::from(&func_dec);
-    assert_eq!(
-      type_check(
-        &Node::Op(
-          Box::new(Node::Number(2)),
-          Opcode::Add,
-          Box::new(Node::Number(2))
-        ),
-        &mut context,
-        &HashMap::new()
-      )
-      .unwrap(),
-      Some(Type::Int)
-    );
+    let mut funcs: HashMap<String, Func> = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec.clone());
+    assert!(!type_check_function(
+      &func_dec,
+      &funcs,
+      &HashMap::new(),
+      &HashMap::new(),
+      &HashMap::new(),
+      &vec![],
+      &vec![]
+    )
+    .is_ok());
   }
 
   #[test]
-  pub fn test_operation_int_bool() {
+  pub fn test_non_mutability_in_while_statement() {
+    let assign = Box::new(Node::Assign(
+      "a".to_string(),
+      Box::new(Node::Number(3)),
+      None,
+    ));
+    let while_statement = Box::new(Node::While(Box::new(Node::Bool(true)), assign, None));
+    let let_statement = Node::Let(
+      "a".to_string(),
+      None,
+      false,
+      Box::new(Node::Number(2)),
+      Some(while_statement),
+    );
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Int),
-      body_start: Node::Empty,
+      ret_type: Type::Unit,
+      body_start: let_statement,
+      location: Location::default(),
     };
-    let mut context = Context::from(&func_dec);
-    assert!(!type_check(
-      &Node::Op(
-        Box::new(Node::Number(2)),
-        Opcode::Add,
-        Box::new(Node::Bool(true))
-      ),
-      &mut context,
-      &HashMap::new()
+    let mut funcs: HashMap<String, Func> = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec.clone());
+    assert!(!type_check_function(
+      &func_dec,
+      &funcs,
+      &HashMap::new(),
+      &HashMap::new(),
+      &HashMap::new(),
+      &vec![],
+      &vec![]
     )
     .is_ok());
   }
 
   #[test]
-  pub fn test_operation_bool_int() {
+  pub fn let_unit_function_call() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Int),
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
-    let mut context = Context::from(&func_dec);
-    assert!(!type_check(
-      &Node::Op(
-        Box::new(Node::Bool(true)),
-        Opcode::And,
-        Box::new(Node::Number(2))
-      ),
-      &mut context,
-      &HashMap::new()
+    let let_statement = Node::Let(
+      "a".to_string(),
+      Some(Type::Unit),
+      false,
+      Box::new(Node::FuncCall("foo".to_string(), vec![], None)),
+      None,
+    );
+    let main = Func {
+      name: "main".to_string(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Unit,
+      body_start: let_statement,
+      location: Location::default(),
+    };
+    let mut funcs: HashMap<String, Func> = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    funcs.insert("main".to_string(), main.clone());
+    assert!(type_check_function(
+      &main,
+      &funcs,
+      &HashMap::new(),
+      &HashMap::new(),
+      &HashMap::new(),
+      &vec![],
+      &vec![]
     )
     .is_ok());
   }
 
   #[test]
-  pub fn test_operation_bool_bool() {
+  pub fn return_unit_in_unit_function() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Int),
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    assert_eq!(
-      type_check(
-        &Node::Op(
-          Box::new(Node::Bool(true)),
-          Opcode::And,
-          Box::new(Node::Bool(true))
-        ),
-        &mut context,
-        &HashMap::new()
-      )
-      .unwrap(),
-      Some(Type::Bool)
-    );
+    let mut funcs = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec);
+    assert!(type_check(
+      &Node::Return(Box::new(Node::Unit), None),
+      &mut context,
+      &funcs
+    )
+    .is_ok());
   }
 
   #[test]
-  pub fn test_func_call_int() {
+  pub fn return_unit_in_returning_function() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Int),
+      ret_type: Type::Int,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    let mut funcs: HashMap<String, Func> = HashMap::new();
+    let mut funcs = HashMap::new();
     funcs.insert("foo".to_string(), func_dec);
-    assert_eq!(
-      type_check(
-        &Node::FuncCall("foo".to_string(), vec!(), None),
-        &mut context,
-        &funcs
-      )
-      .unwrap(),
-      Some(Type::Int)
-    );
+    assert!(!type_check(
+      &Node::Return(Box::new(Node::Unit), None),
+      &mut context,
+      &funcs
+    )
+    .is_ok());
   }
 
   #[test]
-  pub fn test_func_call_bool() {
+  pub fn func_call_statement_is_not_a_return() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Bool),
-      body_start: Node::Empty,
+      ret_type: Type::Int,
+      body_start: Node::FuncCall("foo".to_string(), vec![], None),
+      location: Location::default(),
     };
-    let mut context = Context::from(&func_dec);
-    let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec);
-    assert_eq!(
-      type_check(
-        &Node::FuncCall("foo".to_string(), vec!(), None),
-        &mut context,
-        &funcs
-      )
-      .unwrap(),
-      Some(Type::Bool)
-    );
+    let mut funcs: HashMap<String, Func> = HashMap::new();
+    funcs.insert("foo".to_string(), func_dec.clone());
+    assert!(!type_check_function(
+      &func_dec,
+      &funcs,
+      &HashMap::new(),
+      &HashMap::new(),
+      &HashMap::new(),
+      &vec![],
+      &vec![]
+    )
+    .is_ok());
   }
 
   #[test]
-  pub fn test_func_call_args_int() {
+  pub fn array_non_integer_index() {
     let func_dec = Func {
       name: "foo".to_string(),
-      params: vec![FuncParam {
-        name: "a".to_string(),
-        _type: Type::Int,
-        mutable: false,
-      }],
-      ret_type: Some(Type::Int),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec);
-    assert_eq!(
-      type_check(
-        &Node::FuncCall("foo".to_string(), vec!(Node::Number(2)), None),
-        &mut context,
-        &funcs
-      )
-      .unwrap(),
-      Some(Type::Int)
-    );
+    let array = Node::Array(vec![Node::Number(1), Node::Number(2)]);
+    assert!(!type_check_expr(
+      &Node::Index(Box::new(array), Box::new(Node::Bool(true))),
+      &mut context,
+      &HashMap::new()
+    )
+    .is_ok());
   }
 
   #[test]
-  pub fn test_func_call_args_bool() {
+  pub fn array_element_missmatch() {
     let func_dec = Func {
       name: "foo".to_string(),
-      params: vec![FuncParam {
-        name: "a".to_string(),
-        _type: Type::Bool,
-        mutable: false,
-      }],
-      ret_type: Some(Type::Bool),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec);
-    assert_eq!(
-      type_check(
-        &Node::FuncCall("foo".to_string(), vec!(Node::Bool(true)), None),
-        &mut context,
-        &funcs
-      )
-      .unwrap(),
-      Some(Type::Bool)
-    );
+    assert!(!type_check_expr(
+      &Node::Array(vec![Node::Number(1), Node::Bool(false)]),
+      &mut context,
+      &HashMap::new()
+    )
+    .is_ok());
   }
 
   #[test]
-  pub fn test_func_call_args_invalid_type() {
+  pub fn can_not_assign_to_immutable_array_element() {
     let func_dec = Func {
       name: "foo".to_string(),
-      params: vec![FuncParam {
-        name: "a".to_string(),
-        _type: Type::Int,
-        mutable: false,
-      }],
-      ret_type: Some(Type::Int),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec);
+    context.push(Scope::from(func_dec.params.clone()));
+    context.insert_type("xs", Type::Array(Box::new(Type::Int), 2), false);
     assert!(!type_check(
-      &Node::FuncCall("foo".to_string(), vec!(Node::Bool(true)), None),
+      &Node::IndexAssign(
+        Box::new(Node::Var("xs".to_string())),
+        Box::new(Node::Number(0)),
+        Box::new(Node::Number(1)),
+        None
+      ),
       &mut context,
-      &funcs
+      &HashMap::new()
     )
     .is_ok());
   }
 
   #[test]
-  pub fn test_func_return_int() {
+  pub fn tuple_field_out_of_range() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Int),
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec);
-    assert!(type_check(
-      &Node::Return(Box::new(Node::Number(5)), None),
+    let tuple = Node::Tuple(vec![Node::Number(1), Node::Bool(true)]);
+    assert_eq!(
+      type_check_expr(
+        &Node::TupleField(Box::new(tuple.clone()), 1),
+        &mut context,
+        &HashMap::new()
+      )
+      .unwrap(),
+      Type::Bool
+    );
+    assert!(!type_check_expr(
+      &Node::TupleField(Box::new(tuple), 2),
       &mut context,
-      &funcs
+      &HashMap::new()
     )
     .is_ok());
   }
 
   #[test]
-  pub fn test_func_return_int_expecting_bool() {
+  pub fn let_tuple_arity_missmatch() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Bool),
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec);
+    context.push(Scope::from(func_dec.params.clone()));
     assert!(!type_check(
-      &Node::Return(Box::new(Node::Number(5)), None),
+      &Node::LetTuple(
+        vec![("a".to_string(), false), ("b".to_string(), false)],
+        None,
+        Box::new(Node::Tuple(vec![
+          Node::Number(1),
+          Node::Number(2),
+          Node::Number(3)
+        ])),
+        None
+      ),
       &mut context,
-      &funcs
+      &HashMap::new()
     )
     .is_ok());
   }
 
+  fn point_struct() -> StructDec {
+    StructDec {
+      name: "Point".to_string(),
+      fields: vec![("x".to_string(), Type::Int), ("y".to_string(), Type::Int)],
+      location: Location::default(),
+    }
+  }
+
   #[test]
-  pub fn test_func_return_bool() {
+  pub fn struct_missing_and_unknown_fields() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Bool),
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec);
-    assert!(type_check(
-      &Node::Return(Box::new(Node::Bool(true)), None),
+    context.structs.insert("Point".to_string(), point_struct());
+    let res = type_check_expr(
+      &Node::Struct(
+        "Point".to_string(),
+        vec![
+          ("x".to_string(), Node::Number(1)),
+          ("z".to_string(), Node::Number(2)),
+        ],
+      ),
       &mut context,
-      &funcs
-    )
-    .is_ok());
+      &HashMap::new(),
+    );
+    // Both the unknown field z and the missing field y are reported
+    assert_eq!(res.err().unwrap().len(), 2);
   }
 
   #[test]
-  pub fn test_func_missing_arg() {
+  pub fn struct_field_access() {
     let func_dec = Func {
       name: "foo".to_string(),
-      params: vec![FuncParam {
-        name: "a".to_string(),
-        _type: Type::Int,
-        mutable: true,
-      }],
-      ret_type: Some(Type::Int),
+      type_params: vec![],
+      params: vec![],
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec);
-    assert!(!type_check(
-      &Box::new(Node::FuncCall("foo".to_string(), vec!(), None)),
+    context.structs.insert("Point".to_string(), point_struct());
+    context.push(Scope::from(func_dec.params.clone()));
+    context.insert_type("p", Type::Named("Point".to_string()), false);
+    assert_eq!(
+      type_check_expr(
+        &Node::Field(Box::new(Node::Var("p".to_string())), "y".to_string()),
+        &mut context,
+        &HashMap::new()
+      )
+      .unwrap(),
+      Type::Int
+    );
+    assert!(!type_check_expr(
+      &Node::Field(Box::new(Node::Var("p".to_string())), "z".to_string()),
       &mut context,
-      &funcs
+      &HashMap::new()
     )
     .is_ok());
   }
 
   #[test]
-  pub fn test_func_too_many_args() {
+  pub fn can_not_assign_to_field_of_immutable_struct() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Int),
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec);
+    context.structs.insert("Point".to_string(), point_struct());
+    context.push(Scope::from(func_dec.params.clone()));
+    context.insert_type("p", Type::Named("Point".to_string()), false);
     assert!(!type_check(
-      &Box::new(Node::FuncCall(
-        "foo".to_string(),
-        vec!(Node::Bool(false)),
+      &Node::FieldAssign(
+        Box::new(Node::Var("p".to_string())),
+        "x".to_string(),
+        Box::new(Node::Number(1)),
         None
-      )),
+      ),
       &mut context,
-      &funcs
+      &HashMap::new()
     )
     .is_ok());
   }
 
   #[test]
-  pub fn test_func_return_bool_expecting_int() {
+  pub fn recursive_struct() {
+    let mut structs = HashMap::new();
+    let list = StructDec {
+      name: "List".to_string(),
+      fields: vec![
+        ("head".to_string(), Type::Int),
+        ("tail".to_string(), Type::Named("List".to_string())),
+      ],
+      location: Location::default(),
+    };
+    structs.insert("List".to_string(), list.clone());
+    let enums = HashMap::new();
+    let types = list.fields.iter().map(|(_, r#type)| r#type).collect();
+    assert!(!type_check_type_dec("List", types, &structs, &enums).is_ok());
+    let point = point_struct();
+    structs.insert("Point".to_string(), point.clone());
+    let types = point.fields.iter().map(|(_, r#type)| r#type).collect();
+    assert!(type_check_type_dec("Point", types, &structs, &enums).is_ok());
+  }
+
+  fn shape_enum() -> EnumDec {
+    EnumDec {
+      name: "Shape".to_string(),
+      variants: vec![
+        ("Circle".to_string(), vec![Type::Int]),
+        ("Rect".to_string(), vec![Type::Int, Type::Int]),
+      ],
+      location: Location::default(),
+    }
+  }
+
+  fn shape_match(patterns: Vec<Pattern>) -> Node {
+    Node::Match(
+      Box::new(Node::Var("s".to_string())),
+      patterns
+        .into_iter()
+        .map(|pattern| (pattern, None, Node::Number(0)))
+        .collect(),
+      None,
+    )
+  }
+
+  fn shape_context(func_dec: &Func) -> Context<(Type, bool)> {
+    let mut context = Context::from(func_dec);
+    context.enums.insert("Shape".to_string(), shape_enum());
+    context.push(Scope::from(func_dec.params.clone()));
+    context.insert_type("s", Type::Named("Shape".to_string()), false);
+    context
+  }
+
+  #[test]
+  pub fn non_exhaustive_match() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Int),
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
-    let mut context = Context::from(&func_dec);
-    let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec);
-    assert!(!type_check(
-      &Node::Return(Box::new(Node::Bool(false)), None),
+    let mut context = shape_context(&func_dec);
+    let circle = Pattern::Variant(
+      "Shape".to_string(),
+      "Circle".to_string(),
+      vec![Pattern::Binding("r".to_string())],
+    );
+    assert!(!type_check_expr(
+      &shape_match(vec![circle.clone()]),
       &mut context,
-      &funcs
+      &HashMap::new()
     )
     .is_ok());
+    assert_eq!(
+      type_check_expr(
+        &shape_match(vec![circle, Pattern::Wildcard]),
+        &mut context,
+        &HashMap::new()
+      )
+      .unwrap(),
+      Type::Int
+    );
   }
 
   #[test]
-  pub fn test_func_return_func_call() {
+  pub fn unreachable_match_arm_is_a_warning() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Int),
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
-    let func_dec_2 = Func {
-      name: "bar".to_string(),
+    let mut context = shape_context(&func_dec);
+    let res = type_check_expr(
+      &shape_match(vec![Pattern::Binding("x".to_string()), Pattern::Wildcard]),
+      &mut context,
+      &HashMap::new(),
+    );
+    assert!(res.is_ok());
+    assert_eq!(context.warnings.len(), 1);
+  }
+
+  #[test]
+  pub fn pattern_of_other_enum() {
+    let func_dec = Func {
+      name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: Some(Type::Int),
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
-    let mut context = Context::from(&func_dec);
-    let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec);
-    funcs.insert("bar".to_string(), func_dec_2);
-
-    assert!(type_check(
-      &Node::Return(
-        Box::new(Node::FuncCall("bar".to_string(), vec!(), None)),
-        None
-      ),
+    let mut context = shape_context(&func_dec);
+    let pattern = Pattern::Variant("Color".to_string(), "Red".to_string(), vec![]);
+    assert!(!type_check_expr(
+      &shape_match(vec![pattern, Pattern::Wildcard]),
       &mut context,
-      &funcs
+      &HashMap::new()
     )
     .is_ok());
   }
 
+  fn int_match(arms: Vec<(Pattern, Option<Node>)>) -> Node {
+    Node::Match(
+      Box::new(Node::Var("n".to_string())),
+      arms
+        .into_iter()
+        .map(|(pattern, guard)| (pattern, guard, Node::Number(0)))
+        .collect(),
+      None,
+    )
+  }
+
   #[test]
-  pub fn test_return_with_missing_return_type() {
+  pub fn int_match_needs_wildcard() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: None,
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec);
-    assert!(!type_check(
-      &Node::Return(Box::new(Node::Number(2)), None),
-      &mut context,
-      &funcs
-    )
-    .is_ok());
+    context.push(Scope::from(func_dec.params.clone()));
+    context.insert_type("n", Type::Int, false);
+    let ranges = vec![
+      (Pattern::Range(-2147483648, 0), None),
+      (Pattern::Range(1, 2147483647), None),
+    ];
+    assert!(!type_check_expr(&int_match(ranges), &mut context, &HashMap::new()).is_ok());
+    // Guarded arms do not count towards exhaustiveness
+    let guarded = vec![(Pattern::Wildcard, Some(Node::Bool(true)))];
+    assert!(!type_check_expr(&int_match(guarded), &mut context, &HashMap::new()).is_ok());
+    let arms = vec![
+      (Pattern::Or(vec![Pattern::Int(1), Pattern::Int(2)]), None),
+      (Pattern::Binding("x".to_string()), None),
+    ];
+    assert!(type_check_expr(&int_match(arms), &mut context, &HashMap::new()).is_ok());
   }
 
   #[test]
-  pub fn can_assign_to_mutable() {
+  pub fn bool_match_is_exhaustive_without_wildcard() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: None,
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec.clone());
-    context.push(Scope::from(func_dec.params));
-    context.insert_type("a", Type::Int, true);
-    assert!(type_check(
-      &Node::Assign("a".to_string(), Box::new(Node::Number(3)), None),
-      &mut context,
-      &funcs
-    )
-    .is_ok());
+    let arms = vec![
+      (Pattern::Bool(true), None, Node::Number(1)),
+      (Pattern::Bool(false), None, Node::Number(0)),
+    ];
+    let node = Node::Match(Box::new(Node::Bool(true)), arms, None);
+    assert_eq!(
+      type_check_expr(&node, &mut context, &HashMap::new()).unwrap(),
+      Type::Int
+    );
+    assert_eq!(context.warnings.len(), 0);
   }
 
   #[test]
-  pub fn can_not_assign_to_mutable() {
+  pub fn strings_only_compare_for_equality() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: None,
+      ret_type: Type::Unit,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
+    let compare = |op| {
+      Node::Op(
+        Box::new(Node::Str("a".to_string())),
+        op,
+        Box::new(Node::Str("b".to_string())),
+      )
+    };
+    assert_eq!(
+      type_check_expr(&compare(Opcode::Neq), &mut context, &HashMap::new()).unwrap(),
+      Type::Bool
+    );
+    assert!(
+      !type_check_expr(&compare(Opcode::Lneq), &mut context, &HashMap::new()).is_ok()
+    );
+    assert!(
+      !type_check_expr(&compare(Opcode::Add), &mut context, &HashMap::new()).is_ok()
+    );
+  }
+
+  fn generic_func(type_params: Vec<&str>, params: Vec<Type>) -> Func {
+    Func {
+      name: "generic".to_string(),
+      type_params: type_params
+        .iter()
+        .map(|param| (param.to_string(), vec![]))
+        .collect(),
+      params: params
+        .into_iter()
+        .enumerate()
+        .map(|(i, r#type)| FuncParam {
+          name: format!("p{}", i),
+          _type: r#type,
+          mutable: false,
+          location: Location::default(),
+        })
+        .collect(),
+      ret_type: Type::Named("T".to_string()),
+      body_start: Node::Empty,
+      location: Location::default(),
+    }
+  }
+
+  #[test]
+  pub fn infers_type_arguments_of_generic_call() {
+    let param = Type::Named("T".to_string());
+    let func_dec = generic_func(vec!["T"], vec![param.clone(), param]);
     let mut funcs = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec.clone());
-    context.push(Scope::from(func_dec.params));
-    context.insert_type("a", Type::Int, false);
-    assert!(!type_check(
-      &Node::Assign("a".to_string(), Box::new(Node::Number(3)), None),
+    funcs.insert(func_dec.name.clone(), func_dec.clone());
+    let mut context = Context::from(&func_dec);
+
+    let call = |args| Node::FuncCall("generic".to_string(), args, None);
+    assert_eq!(
+      type_check_expr(
+        &call(vec![Node::Char('a'), Node::Char('b')]),
+        &mut context,
+        &funcs
+      )
+      .unwrap(),
+      Type::Char
+    );
+    // The type parameter can not be both i32 and bool
+    assert!(!type_check_expr(
+      &call(vec![Node::Number(1), Node::Bool(true)]),
       &mut context,
       &funcs
     )
@@ -882,99 +3361,269 @@ mod tests {
   }
 
   #[test]
-  pub fn no_return_in_returning_function() {
-    let func_dec = Func {
-      name: "foo".to_string(),
-      params: vec![],
-      ret_type: Some(Type::Int),
-      body_start: Node::Empty,
+  pub fn type_parameter_must_be_inferable() {
+    let func_dec = generic_func(vec!["T", "U"], vec![Type::Named("T".to_string())]);
+    let mut funcs = HashMap::new();
+    funcs.insert(func_dec.name.clone(), func_dec.clone());
+    let mut context = Context::from(&func_dec);
+
+    let call = Node::FuncCall("generic".to_string(), vec![Node::Number(1)], None);
+    assert!(!type_check_expr(&call, &mut context, &funcs).is_ok());
+  }
+
+  #[test]
+  pub fn operators_are_not_defined_on_type_parameters() {
+    let param = Type::Named("T".to_string());
+    let mut func_dec = generic_func(vec!["T"], vec![param.clone(), param]);
+    func_dec.body_start = Node::Return(
+      Box::new(Node::Op(
+        Box::new(Node::Var("p0".to_string())),
+        Opcode::Add,
+        Box::new(Node::Var("p1".to_string())),
+      )),
+      None,
+    );
+    assert!(!type_check_function(
+      &func_dec,
+      &HashMap::new(),
+      &HashMap::new(),
+      &HashMap::new(),
+      &HashMap::new(),
+      &vec![],
+      &vec![]
+    )
+    .is_ok());
+  }
+
+  #[test]
+  pub fn methods_of_type_parameters_come_from_bounds() {
+    let area = TraitDec {
+      name: "Area".to_string(),
+      methods: vec![Func {
+        name: "area".to_string(),
+        type_params: vec![("Self".to_string(), vec!["Area".to_string()])],
+        params: vec![FuncParam {
+          name: "self".to_string(),
+          _type: Type::Named("Self".to_string()),
+          mutable: false,
+          location: Location::default(),
+        }],
+        ret_type: Type::Int,
+        body_start: Node::Empty,
+        location: Location::default(),
+      }],
+      location: Location::default(),
     };
+    let call = Node::MethodCall(
+      Box::new(Node::Var("p0".to_string())),
+      "area".to_string(),
+      vec![],
+    );
+
+    let mut func_dec = generic_func(vec!["T"], vec![Type::Named("T".to_string())]);
     let mut context = Context::from(&func_dec);
-    let mut funcs: HashMap<String, Func> = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec.clone());
+    context.traits.insert(area.name.clone(), area.clone());
     context.push(Scope::from(func_dec.params.clone()));
-    context.insert_type("a", Type::Int, false);
-    assert!(!type_check_function(&func_dec, &funcs).is_ok());
+    assert!(!type_check_expr(&call, &mut context, &HashMap::new()).is_ok());
+
+    func_dec.type_params[0].1.push("Area".to_string());
+    let mut context = Context::from(&func_dec);
+    context.traits.insert(area.name.clone(), area);
+    context.push(Scope::from(func_dec.params.clone()));
+    assert_eq!(
+      type_check_expr(&call, &mut context, &HashMap::new()).unwrap(),
+      Type::Int
+    );
   }
 
   #[test]
-  pub fn empty_function_no_ret_type() {
+  pub fn closures_capture_variables_in_scope() {
     let func_dec = Func {
       name: "foo".to_string(),
-      params: vec![],
-      ret_type: None,
+      type_params: vec![],
+      params: vec![FuncParam {
+        name: "p0".to_string(),
+        _type: Type::Int,
+        mutable: false,
+        location: Location::default(),
+      }],
+      ret_type: Type::Int,
       body_start: Node::Empty,
+      location: Location::default(),
     };
     let mut context = Context::from(&func_dec);
-    let mut funcs: HashMap<String, Func> = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec.clone());
     context.push(Scope::from(func_dec.params.clone()));
-    context.insert_type("a", Type::Int, false);
-    assert!(type_check_function(&func_dec, &funcs).is_ok());
+
+    // |x: i32| x + p0
+    let closure = Node::Closure(
+      vec![FuncParam {
+        name: "x".to_string(),
+        _type: Type::Int,
+        mutable: false,
+        location: Location::default(),
+      }],
+      None,
+      Box::new(Node::Op(
+        Box::new(Node::Var("x".to_string())),
+        Opcode::Add,
+        Box::new(Node::Var("p0".to_string())),
+      )),
+      None,
+    );
+    assert_eq!(
+      type_check_expr(&closure, &mut context, &HashMap::new()).unwrap(),
+      Type::Func(vec![Type::Int], Box::new(Type::Int))
+    );
+    // The parameters of the closure go out of scope at its end
+    assert!(
+      !type_check_expr(&Node::Var("x".to_string()), &mut context, &HashMap::new())
+        .is_ok()
+    );
   }
 
   #[test]
-  pub fn while_non_boolean_condition() {
+  pub fn only_variables_of_function_type_can_be_called() {
+    let func_type = Type::Func(vec![Type::Bool], Box::new(Type::Int));
     let func_dec = Func {
       name: "foo".to_string(),
-      params: vec![],
-      ret_type: None,
-      body_start: Node::While(Box::new(Node::Number(4)), Box::new(Node::Empty), None),
+      type_params: vec![],
+      params: vec![
+        FuncParam {
+          name: "p0".to_string(),
+          _type: func_type,
+          mutable: false,
+          location: Location::default(),
+        },
+        FuncParam {
+          name: "p1".to_string(),
+          _type: Type::Int,
+          mutable: false,
+          location: Location::default(),
+        },
+      ],
+      ret_type: Type::Int,
+      body_start: Node::Empty,
+      location: Location::default(),
     };
-    let mut funcs: HashMap<String, Func> = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec.clone());
-    assert!(!type_check_function(&func_dec, &funcs).is_ok());
+    let mut context = Context::from(&func_dec);
+    context.push(Scope::from(func_dec.params.clone()));
+
+    let call = |func: &str, arg| Node::FuncCall(func.to_string(), vec![arg], None);
+    assert_eq!(
+      type_check_expr(&call("p0", Node::Bool(true)), &mut context, &HashMap::new())
+        .unwrap(),
+      Type::Int
+    );
+    assert!(!type_check_expr(
+      &call("p0", Node::Number(1)),
+      &mut context,
+      &HashMap::new()
+    )
+    .is_ok());
+    assert!(!type_check_expr(
+      &call("p1", Node::Number(1)),
+      &mut context,
+      &HashMap::new()
+    )
+    .is_ok());
+  }
+
+  fn global(name: &str, is_static: bool, expr: Node) -> GlobalDec {
+    GlobalDec {
+      name: name.to_string(),
+      _type: Type::Int,
+      is_static: is_static,
+      mutable: false,
+      expr: expr,
+      location: Location::default(),
+    }
   }
 
   #[test]
-  pub fn test_non_mutability_in_if_statement() {
-    let assign = Box::new(Node::Assign(
-      "a".to_string(),
-      Box::new(Node::Number(3)),
-      None,
-    ));
-    let if_statement = Box::new(Node::If(Box::new(Node::Bool(true)), assign, None, None));
-    let let_statement = Node::Let(
-      "a".to_string(),
-      None,
-      false,
-      Box::new(Node::Number(2)),
-      Some(if_statement),
-    );
-    let func_dec = Func {
-      name: "foo".to_string(),
-      params: vec![],
-      ret_type: None,
-      body_start: let_statement,
-    };
-    let mut funcs: HashMap<String, Func> = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec.clone());
-    assert!(!type_check_function(&func_dec, &funcs).is_ok());
+  pub fn globals_are_initialized_with_constant_expressions() {
+    let var = |name: &str| Node::Var(name.to_string());
+    let globals = vec![
+      global("A", false, var("B")),
+      global(
+        "B",
+        false,
+        Node::Op(Box::new(var("A")), Opcode::Add, Box::new(Node::Number(1))),
+      ),
+      global("C", false, Node::Number(1)),
+      global("S", true, var("C")),
+    ];
+    let funcs = HashMap::new();
+    assert!(check_constant_expr(
+      &Node::Array(vec![var("C"), Node::Number(2)]),
+      &globals,
+      &funcs
+    )
+    .is_ok());
+    assert!(!check_constant_expr(&var("S"), &globals, &funcs).is_ok());
+    assert!(!check_constant_expr(
+      &Node::FuncCall("f".to_string(), vec![], None),
+      &globals,
+      &funcs
+    )
+    .is_ok());
+
+    // A refers to itself through B
+    assert!(refers_to(&globals[0].expr, "A", &globals, &mut vec![]));
+    assert!(!refers_to(&globals[3].expr, "S", &globals, &mut vec![]));
   }
 
   #[test]
-  pub fn test_non_mutability_in_while_statement() {
-    let assign = Box::new(Node::Assign(
-      "a".to_string(),
-      Box::new(Node::Number(3)),
-      None,
-    ));
-    let while_statement = Box::new(Node::While(Box::new(Node::Bool(true)), assign, None));
-    let let_statement = Node::Let(
-      "a".to_string(),
-      None,
-      false,
-      Box::new(Node::Number(2)),
-      Some(while_statement),
-    );
+  pub fn aliases_stand_for_their_types() {
     let func_dec = Func {
       name: "foo".to_string(),
+      type_params: vec![],
       params: vec![],
-      ret_type: None,
-      body_start: let_statement,
+      ret_type: Type::Unit,
+      body_start: Node::Empty,
+      location: Location::default(),
     };
-    let mut funcs: HashMap<String, Func> = HashMap::new();
-    funcs.insert("foo".to_string(), func_dec.clone());
-    assert!(!type_check_function(&func_dec, &funcs).is_ok());
+    let mut context = Context::from(&func_dec);
+    context.push(Scope::from(func_dec.params.clone()));
+    let celsius = Type::Alias("Celsius".to_string(), Box::new(Type::Int));
+
+    let declared = Some(celsius.clone());
+    assert!(type_check_let(
+      "t",
+      &declared,
+      false,
+      &Node::Number(1),
+      &mut context,
+      &HashMap::new()
+    )
+    .is_ok());
+    assert_eq!(context.get_var_type("t"), Some(&(celsius, false)));
+    // The alias is reported as written
+    match type_check_let(
+      "u",
+      &declared,
+      false,
+      &Node::Bool(true),
+      &mut context,
+      &HashMap::new(),
+    ) {
+      Err(errors) => assert_eq!(
+        errors[0].to_string(),
+        "Let statement expected type Celsius because of declaration but received bool"
+      ),
+      Ok(_) => panic!("Bool assigned to an alias of i32"),
+    }
+
+    let mut aliases = HashMap::new();
+    aliases.insert(
+      "A".to_string(),
+      Type::Array(Box::new(Type::Named("B".to_string())), 2),
+    );
+    aliases.insert(
+      "B".to_string(),
+      Type::Tuple(vec![Type::Named("A".to_string())]),
+    );
+    aliases.insert("C".to_string(), Type::Named("A".to_string()));
+    assert!(refers_to_alias(&aliases["A"], "A", &aliases, &mut vec![]));
+    assert!(!refers_to_alias(&aliases["C"], "C", &aliases, &mut vec![]));
   }
 }
@@ -1,18 +1,131 @@
 use crate::types::value::Value;
+use std::collections::HashMap;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Type {
   Bool,
   Int,
+  Char,
+  // &'static str, the type of string literals
+  Str,
+  Unit,
+  // Element type, length
+  Array(Box<Type>, usize),
+  // Element types, there are always at least one
+  Tuple(Vec<Type>),
+  // Name of a struct or enum, or of a type parameter in a generic function
+  Named(String),
+  // Parameter types and return type of a function pointer or closure
+  Func(Vec<Type>, Box<Type>),
+  // Name of a type alias as written in the program, along with the type it stands
+  // for. Aliases are resolved when parsing and are equal to the type they stand for.
+  Alias(String, Box<Type>),
+}
+
+impl PartialEq for Type {
+  fn eq(&self, other: &Type) -> bool {
+    match (self, other) {
+      (Type::Alias(_, r#type), other) => **r#type == *other,
+      (r#type, Type::Alias(_, other)) => *r#type == **other,
+      (Type::Array(left, left_len), Type::Array(right, right_len)) => {
+        left == right && left_len == right_len
+      }
+      (Type::Tuple(left), Type::Tuple(right)) => left == right,
+      (Type::Named(left), Type::Named(right)) => left == right,
+      (Type::Func(left_params, left_ret), Type::Func(right_params, right_ret)) => {
+        left_params == right_params && left_ret == right_ret
+      }
+      // The remaining types have no components
+      _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+    }
+  }
 }
 
 impl Type {
-  pub fn to_str(&self) -> &'static str {
+  // Replaces the type parameters bound in `type_args` by their types
+  pub fn substitute(&self, type_args: &HashMap<String, Type>) -> Type {
+    match self {
+      Type::Array(r#type, len) => {
+        Type::Array(Box::new(r#type.substitute(type_args)), *len)
+      }
+      Type::Tuple(types) => Type::Tuple(
+        types
+          .iter()
+          .map(|r#type| r#type.substitute(type_args))
+          .collect(),
+      ),
+      Type::Named(name) => match type_args.get(name) {
+        Some(r#type) => r#type.clone(),
+        None => self.clone(),
+      },
+      Type::Func(params, ret_type) => Type::Func(
+        params
+          .iter()
+          .map(|r#type| r#type.substitute(type_args))
+          .collect(),
+        Box::new(ret_type.substitute(type_args)),
+      ),
+      _ => self.clone(),
+    }
+  }
+
+  // The type with all aliases replaced by the types they stand for
+  pub fn resolved(&self) -> Type {
     match self {
-      Type::Bool => "bool",
-      Type::Int => "i32",
+      Type::Array(r#type, len) => Type::Array(Box::new(r#type.resolved()), *len),
+      Type::Tuple(types) => {
+        Type::Tuple(types.iter().map(|r#type| r#type.resolved()).collect())
+      }
+      Type::Func(params, ret_type) => Type::Func(
+        params.iter().map(|r#type| r#type.resolved()).collect(),
+        Box::new(ret_type.resolved()),
+      ),
+      Type::Alias(_, r#type) => r#type.resolved(),
+      _ => self.clone(),
     }
   }
+
+  pub fn to_str(&self) -> String {
+    match self {
+      Type::Bool => "bool".to_string(),
+      Type::Int => "i32".to_string(),
+      Type::Char => "char".to_string(),
+      Type::Str => "&str".to_string(),
+      Type::Unit => "()".to_string(),
+      Type::Array(r#type, len) => format!("[{}; {}]", r#type.to_str(), len),
+      Type::Named(name) | Type::Alias(name, _) => name.clone(),
+      // A tuple with one element is written with a trailing comma: (i32,)
+      Type::Tuple(types) if types.len() == 1 => format!("({},)", types[0].to_str()),
+      Type::Tuple(types) => format!(
+        "({})",
+        types
+          .iter()
+          .map(|r#type| r#type.to_str())
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      // The return type of functions returning unit is left out: fn(i32)
+      Type::Func(params, ret_type) => {
+        let params = params
+          .iter()
+          .map(|r#type| r#type.to_str())
+          .collect::<Vec<String>>()
+          .join(", ");
+        match **ret_type {
+          Type::Unit => format!("fn({})", params),
+          ref ret_type => format!("fn({}) -> {}", params, ret_type.to_str()),
+        }
+      }
+    }
+  }
+}
+
+// Maps the name of the struct or enum a literal or pattern refers to, it is kept
+// unless f maps it to another named type
+pub fn map_type_name(name: &mut String, f: &dyn Fn(&Type) -> Type) {
+  if let Type::Named(mapped) = f(&Type::Named(name.clone())) {
+    *name = mapped;
+  }
 }
 
 impl std::convert::From<&Value> for Type {
@@ -20,6 +133,22 @@ impl std::convert::From<&Value> for Type {
     match *val {
       Value::Int(_) => Type::Int,
       Value::Bool(_) => Type::Bool,
+      Value::Char(_) => Type::Char,
+      Value::Str(_) => Type::Str,
+      Value::Unit => Type::Unit,
+      // Array literals can not be empty so the first element is always present
+      Value::Array(ref values) => {
+        Type::Array(Box::new((&values[0]).into()), values.len())
+      }
+      Value::Tuple(ref values) => {
+        Type::Tuple(values.iter().map(|value| value.into()).collect())
+      }
+      Value::Struct(ref name, _) | Value::Variant(ref name, ..) => {
+        Type::Named(name.clone())
+      }
+      Value::Closure(..) => {
+        unreachable!("The return type of a closure is only known to the type checker")
+      }
     }
   }
 }
@@ -1,22 +1,61 @@
-use crate::types::{_type::Type, func::Func, scope::Scope, variable::Variable};
+use crate::errors::type_warning::TypeWarning;
+use crate::types::{
+  _type::Type, enum_dec::EnumDec, func::Func, impl_dec::ImplDec, scope::Scope,
+  struct_dec::StructDec, trait_dec::TraitDec, value::Value, variable::Variable,
+};
+use std::collections::HashMap;
+
+// The variables the compiler introduces, e.g. the operands of assert_eq! or the
+// locals of a macro, have a # in their name. The program can not refer to them and
+// $DEBUG_CONTEXT leaves them out.
+pub fn is_introduced(name: &str) -> bool {
+  name.contains('#')
+}
 
 #[derive(Debug)]
 pub struct Context<T> {
   scopes: Vec<Scope<T>>,
+  // Constants and statics of the program, found below the scopes of the function
+  pub globals: Scope<T>,
   pub current_func: Func,
+  // Struct declarations of the program
+  pub structs: HashMap<String, StructDec>,
+  // Enum declarations of the program
+  pub enums: HashMap<String, EnumDec>,
+  // Trait declarations and impls of the program
+  pub traits: HashMap<String, TraitDec>,
+  pub impls: Vec<ImplDec>,
+  // Warnings found while type checking
+  pub warnings: Vec<TypeWarning>,
+  // Variables declared without a value that have not been assigned one, by the
+  // index of their scope. Only the interpreter declares such variables.
+  unassigned: Vec<(usize, String)>,
 }
 
 impl<T> From<&Func> for Context<T> {
   fn from(func: &Func) -> Self {
     Context {
       scopes: vec![],
+      globals: Scope::new(),
       current_func: (*func).clone(),
+      structs: HashMap::new(),
+      enums: HashMap::new(),
+      traits: HashMap::new(),
+      impls: vec![],
+      warnings: vec![],
+      unassigned: vec![],
     }
   }
 }
 
 impl Context<Variable> {
   pub fn insert_variable(&mut self, var: Variable) {
+    // The variable shadows any variable of the scope declared without a value,
+    // declare_variable marks it unassigned again after inserting it
+    let scope = self.scopes.len().saturating_sub(1);
+    self
+      .unassigned
+      .retain(|unassigned| *unassigned != (scope, var.name.clone()));
     match (*self).scopes.iter_mut().last() {
       Some(scope) => (*scope).elements.insert(
         var.name.clone(),
@@ -29,6 +68,34 @@ impl Context<Variable> {
     };
   }
 
+  // Declares a variable without a value, it holds unit until it is assigned one
+  pub fn declare_variable(&mut self, name: &str) {
+    self.insert_variable(Variable {
+      name: name.to_string(),
+      value: Value::Unit,
+    });
+    let scope = self.scopes.len() - 1;
+    self.unassigned.push((scope, name.to_string()));
+  }
+
+  // Assigns a value to a local variable or a static, returns false if there is no
+  // variable of that name
+  pub fn assign_variable(&mut self, name: &str, value: Value) -> bool {
+    match self.scope_of(name) {
+      Some(scope) => self
+        .unassigned
+        .retain(|unassigned| *unassigned != (scope, name.to_string())),
+      None => (),
+    }
+    match self.get_variable_mut(name) {
+      Some(var) => {
+        var.value = value;
+        true
+      }
+      None => false,
+    }
+  }
+
   // Wrapper for more readable code
   pub fn get_variable(&self, var: &str) -> Option<&Variable> {
     self.get_element(var)
@@ -38,6 +105,27 @@ impl Context<Variable> {
   pub fn get_variable_mut(&mut self, var: &str) -> Option<&mut Variable> {
     self.get_element_mut(var)
   }
+
+  // The local variables in scope sorted by name, those shadowed, those introduced
+  // by the compiler and those not yet assigned a value left out
+  pub fn locals(&self) -> Vec<&Variable> {
+    let mut names: Vec<&String> = vec![];
+    let mut locals: Vec<&Variable> = vec![];
+    for (i, scope) in self.scopes.iter().enumerate().rev() {
+      for var in scope.elements.values() {
+        if names.contains(&&var.name) {
+          continue;
+        }
+        names.push(&var.name);
+        if !is_introduced(&var.name) && !self.unassigned.contains(&(i, var.name.clone()))
+        {
+          locals.push(var);
+        }
+      }
+    }
+    locals.sort_by(|a, b| a.name.cmp(&b.name));
+    locals
+  }
 }
 
 impl Context<(Type, bool)> {
@@ -61,6 +149,24 @@ impl<T> Context<T> {
 
   pub fn pop(&mut self) {
     self.scopes.pop();
+    let scopes = self.scopes.len();
+    self.unassigned.retain(|(scope, _)| *scope < scopes);
+  }
+
+  // Index of the innermost scope declaring the variable
+  fn scope_of(&self, var: &str) -> Option<usize> {
+    self
+      .scopes
+      .iter()
+      .rposition(|scope| scope.elements.contains_key(var))
+  }
+
+  // Whether the variable is declared in the function rather than globally
+  pub fn is_local(&self, var: &str) -> bool {
+    self
+      .scopes
+      .iter()
+      .any(|scope| scope.elements.contains_key(var))
   }
 
   fn get_element(&self, var: &str) -> Option<&T> {
@@ -72,7 +178,7 @@ impl<T> Context<T> {
         None => (),
       };
     }
-    None
+    self.globals.elements.get(var)
   }
 
   fn get_element_mut(&mut self, var: &str) -> Option<&mut T> {
@@ -84,6 +190,6 @@ impl<T> Context<T> {
         None => (),
       };
     }
-    None
+    self.globals.elements.get_mut(var)
   }
 }
@@ -0,0 +1,22 @@
+use crate::types::{_type::Type, location::Location};
+
+#[derive(Debug, Clone)]
+pub struct EnumDec {
+  pub name: String,
+  // Variant names and the types of their fields in declaration order
+  pub variants: Vec<(String, Vec<Type>)>,
+  // Location of the name of the enum
+  pub location: Location,
+}
+
+impl EnumDec {
+  // Returns the tag and field types of a variant
+  pub fn get_variant(&self, variant: &str) -> Option<(usize, &Vec<Type>)> {
+    self
+      .variants
+      .iter()
+      .enumerate()
+      .find(|(_, (name, _))| name == variant)
+      .map(|(i, (_, types))| (i, types))
+  }
+}
@@ -1,54 +1,110 @@
 use std::{collections::HashMap, convert::TryInto};
 
 use crate::{
-  interpreter::eval,
+  errors::runtime_error::{RuntimeError, RuntimeErrorKind},
+  interpreter::{bind, eval, eval_value},
   types::{
-    _type::Type, context::Context, func_param::FuncParam, node::Node, scope::Scope,
-    value::Value, variable::Variable,
+    _type::Type, context::Context, func_param::FuncParam, location::Location, node::Node,
+    scope::Scope, value::Value, variable::Variable,
   },
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Func {
   pub name: String,
+  // Type parameters of a generic function along with the traits bounding them
+  pub type_params: Vec<(String, Vec<String>)>,
   pub params: Vec<FuncParam>,
-  pub ret_type: Option<Type>,
+  pub ret_type: Type,
   pub body_start: Node,
+  // Location of the name of the function
+  pub location: Location,
 }
 
 impl Func {
+  // Whether a type name refers to a type parameter of the function
+  pub fn is_type_param(&self, name: &str) -> bool {
+    self.get_bounds(name).is_some()
+  }
+
+  // Returns the traits bounding a type parameter
+  pub fn get_bounds(&self, name: &str) -> Option<&Vec<String>> {
+    self
+      .type_params
+      .iter()
+      .find(|(param, _)| param == name)
+      .map(|(_, bounds)| bounds)
+  }
+
+  // A closure calling the function, which is how a function is passed as a value
+  pub fn as_closure(&self) -> Node {
+    let args = self
+      .params
+      .iter()
+      .map(|param| Node::Var(param.name.clone()))
+      .collect();
+    Node::Closure(
+      self.params.clone(),
+      Some(self.ret_type.clone()),
+      Box::new(Node::FuncCall(self.name.clone(), args, None)),
+      None,
+    )
+  }
+
+  // Calls the function with the values of the arguments, an error in its body is
+  // traced through a frame named after the function
   pub fn execute(
     &self,
     args: &Vec<Node>,
     funcs: &HashMap<String, Func>,
     context: &mut Context<Variable>,
-  ) -> Option<Value> {
-    self.validate_arguments(args);
+  ) -> Result<Value, RuntimeError> {
+    if let Err(e) = self.validate_arguments(args) {
+      return Err(e);
+    }
 
     // Evaluate argument nodes and push the result to the functions scope
     let mut _args: Vec<Variable> = vec![];
     for (node, param) in (*args).iter().zip(self.params.iter()) {
-      //Convert the node to a value
-      let val: Value = eval(node, context, funcs).try_into().unwrap();
-
+      let value = match eval_value(node, context, funcs) {
+        Ok(value) => value,
+        Err(e) => return Err(e),
+      };
       _args.push(Variable {
         name: param.name.clone(),
-        value: val,
+        value: value,
       });
     }
-    let mut context: Context<Variable> = Context::from(self);
-    context.push(Scope::from(_args));
+    let scope = match bind(_args) {
+      Ok(scope) => scope,
+      Err(e) => return Err(e),
+    };
+    // The globals are handed to the called function and back, as statics keep
+    // their values across calls
+    let mut callee: Context<Variable> = Context::from(self);
+    callee.globals = std::mem::replace(&mut context.globals, Scope::new());
+    callee.push(scope);
+    let res = eval(&self.body_start, &mut callee, &funcs);
+    context.globals = callee.globals;
 
-    // Extract return value (if any)
-    match eval(&self.body_start, &mut context, &funcs) {
-      Node::Number(n) => Some(Value::Int(n)),
-      Node::Bool(b) => Some(Value::Bool(b)),
-      Node::Empty => None,
-      _ => panic!("Unknown return type from function {}", self.name),
+    // Extract return value, a body that runs to completion returns unit
+    match res {
+      Ok(Node::Empty) => Ok(Value::Unit),
+      Ok(node) => match node.try_into() {
+        Ok(value) => Ok(value),
+        Err(_) => Err(RuntimeError::new(
+          RuntimeErrorKind::Internal,
+          format!("Unknown return type from function {}", self.name),
+        )),
+      },
+      Err(mut error) => {
+        error.stack.push(self.name.clone());
+        Err(error)
+      }
     }
   }
 
-  fn validate_arguments(&self, args: &Vec<Node>) {
+  fn validate_arguments(&self, args: &Vec<Node>) -> Result<(), RuntimeError> {
     if args.len() < self.params.len() {
       let mut error_msg =
         "Missing parameter ".to_string() + &self.params[args.len()].name;
@@ -59,9 +115,17 @@ impl Func {
 
       error_msg.push_str(" to function ");
       error_msg += &self.name;
-      panic!(error_msg);
+      Err(RuntimeError::new(
+        RuntimeErrorKind::ArgumentMismatch,
+        error_msg,
+      ))
     } else if args.len() > self.params.len() {
-      panic!("Unexpected argument");
+      Err(RuntimeError::new(
+        RuntimeErrorKind::ArgumentMismatch,
+        format!("Unexpected argument to function {}", self.name),
+      ))
+    } else {
+      Ok(())
     }
   }
 }
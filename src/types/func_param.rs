@@ -1,8 +1,9 @@
-use crate::types::_type::Type;
+use crate::types::{_type::Type, location::Location};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FuncParam {
   pub name: String,
   pub _type: Type,
   pub mutable: bool,
+  pub location: Location,
 }
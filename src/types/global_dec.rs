@@ -0,0 +1,28 @@
+use crate::types::{_type::Type, func::Func, location::Location, node::Node};
+
+// A const or static item. Both are initialized with a constant expression, only
+// statics live at a fixed address and can be mutable.
+#[derive(Debug, Clone)]
+pub struct GlobalDec {
+  pub name: String,
+  pub _type: Type,
+  pub is_static: bool,
+  pub mutable: bool,
+  pub expr: Node,
+  pub location: Location,
+}
+
+impl GlobalDec {
+  // A function returning the value of the initializer, which evaluates it like
+  // the body of any other function
+  pub fn initializer(&self) -> Func {
+    Func {
+      name: self.name.clone(),
+      type_params: vec![],
+      params: vec![],
+      ret_type: self._type.clone(),
+      body_start: Node::Return(Box::new(self.expr.clone()), None),
+      location: self.location,
+    }
+  }
+}
@@ -0,0 +1,48 @@
+use crate::types::{_type::Type, func::Func};
+use std::collections::HashMap;
+
+// An implementation of a trait for a type. The methods are stored in the function
+// table of the program under the name of the impl followed by the method name.
+#[derive(Debug, Clone)]
+pub struct ImplDec {
+  pub r#trait: String,
+  pub r#type: Type,
+  // Names of the implemented methods
+  pub methods: Vec<String>,
+}
+
+impl ImplDec {
+  // Creates an impl from its methods, which become the functions of the impl taking
+  // the receiver as their first argument
+  pub fn new(r#trait: String, r#type: Type, mut methods: Vec<Func>) -> (Self, Vec<Func>) {
+    let r#impl = ImplDec {
+      r#trait: r#trait,
+      r#type: r#type,
+      methods: methods.iter().map(|method| method.name.clone()).collect(),
+    };
+    for method in methods.iter_mut() {
+      method.name = r#impl.method_name(&method.name);
+      method.params[0]._type = r#impl.r#type.clone();
+    }
+    (r#impl, methods)
+  }
+
+  // Name of a method of the impl in the function table, <i32 as Display>::fmt
+  pub fn method_name(&self, method: &str) -> String {
+    format!("<{} as {}>::{}", self.r#type.to_str(), self.r#trait, method)
+  }
+}
+
+// Finds the implementation of a method for a type among the functions of the
+// program, the type checker makes sure that only one trait provides it.
+pub fn find_method<'a>(
+  funcs: &'a HashMap<String, Func>,
+  r#type: &Type,
+  method: &str,
+) -> Option<&'a Func> {
+  let prefix = format!("<{} as ", r#type.to_str());
+  let suffix = format!(">::{}", method);
+  funcs
+    .values()
+    .find(|func| func.name.starts_with(&prefix) && func.name.ends_with(&suffix))
+}
@@ -0,0 +1,28 @@
+use crate::types::{
+  _type::Type, enum_dec::EnumDec, func::Func, global_dec::GlobalDec, macro_dec::MacroDec,
+  struct_dec::StructDec, trait_dec::TraitDec,
+};
+
+// A top level declaration of a file
+#[derive(Debug, Clone)]
+pub enum Item {
+  Func(Func),
+  // A function marked #[test], run by the test runner
+  Test(Func),
+  Struct(StructDec),
+  Enum(EnumDec),
+  Trait(TraitDec),
+  // Trait, type and methods of an impl, which is created once the aliases in its
+  // type are resolved
+  Impl(String, Type, Vec<Func>),
+  Global(GlobalDec),
+  // Name and type of a type alias
+  Alias(String, Type),
+  // Name of a module and its items along with whether they are public, None when
+  // they are in a file of their own
+  Mod(String, Option<Vec<(bool, Item)>>),
+  // Path to an item brought into scope, e.g. shapes::area
+  Use(String),
+  // Macros are expanded when the file they are declared in is loaded
+  Macro(MacroDec),
+}
@@ -0,0 +1,27 @@
+// Line and column of a declaration in the file it is written in, both starting at 1.
+// Declarations made by the compiler rather than written have the default location.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Location {
+  pub line: usize,
+  pub column: usize,
+}
+
+impl Location {
+  // The location of a byte of a file
+  pub fn new(file: &str, offset: usize) -> Self {
+    let line_start = match file[..offset].rfind('\n') {
+      Some(i) => i + 1,
+      None => 0,
+    };
+    Location {
+      line: file[..offset].matches('\n').count() + 1,
+      column: file[line_start..offset].chars().count() + 1,
+    }
+  }
+}
+
+impl std::fmt::Display for Location {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "{}:{}", self.line, self.column)
+  }
+}
@@ -0,0 +1,40 @@
+// A token of the input of a macro, or the tokens between a pair of delimiters
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenTree {
+  Token(String),
+  // Opening delimiter, tokens between the delimiters
+  Group(char, Vec<TokenTree>),
+}
+
+// A macro declared with macro_rules!, the first rule whose matcher matches the
+// tokens a macro is invoked with is expanded to its transcriber
+#[derive(Debug, Clone)]
+pub struct MacroDec {
+  pub name: String,
+  // Matcher and transcriber of each rule, without their outer delimiters
+  pub rules: Vec<(Vec<TokenTree>, Vec<TokenTree>)>,
+  // Byte offsets of the start and end of the declaration
+  pub start: usize,
+  pub end: usize,
+}
+
+impl TokenTree {
+  // The source text of a sequence of tokens
+  pub fn to_source(tokens: &Vec<TokenTree>) -> String {
+    tokens
+      .iter()
+      .map(|token| match token {
+        TokenTree::Token(token) => token.clone(),
+        TokenTree::Group(delimiter, tokens) => {
+          let closing = match delimiter {
+            '(' => ')',
+            '[' => ']',
+            _ => '}',
+          };
+          format!("{}{}{}", delimiter, TokenTree::to_source(tokens), closing)
+        }
+      })
+      .collect::<Vec<String>>()
+      .join(" ")
+  }
+}
@@ -1,10 +1,20 @@
 pub mod context;
+pub mod enum_dec;
 pub mod func;
 pub mod func_param;
+pub mod global_dec;
+pub mod impl_dec;
+pub mod item;
+pub mod location;
+pub mod macro_dec;
 pub mod node;
 pub mod opcode;
+pub mod pattern;
 pub mod program;
 pub mod scope;
+pub mod struct_dec;
+pub mod symbol_table;
+pub mod trait_dec;
 pub mod value;
 pub mod variable;
 pub mod _type;
@@ -1,15 +1,67 @@
-use crate::types::{_type::Type, opcode::Opcode};
+use crate::types::{
+  _type::{map_type_name, Type},
+  func::Func,
+  func_param::FuncParam,
+  macro_dec::TokenTree,
+  opcode::Opcode,
+  pattern::Pattern,
+};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Node {
   Number(i32),
   Bool(bool),
+  Char(char),
+  // Contents of a string literal, with the escapes resolved
+  Str(String),
+  // The unit value ()
+  Unit,
   //Name
   Var(String),
+  // Elements
+  Array(Vec<Node>),
+  // Element, length
+  ArrayRepeat(Box<Node>, usize),
+  // Array, index
+  Index(Box<Node>, Box<Node>),
+  // Elements
+  Tuple(Vec<Node>),
+  // Tuple, field index
+  TupleField(Box<Node>, usize),
+  // Struct name, fields and their values
+  Struct(String, Vec<(String, Node)>),
+  // Struct, field
+  Field(Box<Node>, String),
+  // Enum, variant, fields
+  Variant(String, String, Vec<Node>),
+  // Receiver, method, arguments
+  MethodCall(Box<Node>, String, Vec<Node>),
+  // Parameters, return type (filled in by monomorphization), body, captured variables
+  // and their values (set when the closure is evaluated by the interpreter)
+  Closure(
+    Vec<FuncParam>,
+    Option<Type>,
+    Box<Node>,
+    Option<Vec<(String, Node)>>,
+  ),
   // Variable, type, mutable, expression, next instruction
   Let(String, Option<Type>, bool, Box<Node>, Option<Box<Node>>),
+  // Variable, type, mutable, next instruction. A variable declared without a value
+  // is assigned one later, let x: i32;
+  Declare(String, Type, bool, Option<Box<Node>>),
+  // Variables and their mutability, type, expression, next instruction
+  LetTuple(
+    Vec<(String, bool)>,
+    Option<Type>,
+    Box<Node>,
+    Option<Box<Node>>,
+  ),
   // Variable, expression, next instruction
   Assign(String, Box<Node>, Option<Box<Node>>),
+  // Array, index, expression, next instruction
+  IndexAssign(Box<Node>, Box<Node>, Box<Node>, Option<Box<Node>>),
+  // Struct, field, expression, next instruction
+  FieldAssign(Box<Node>, String, Box<Node>, Option<Box<Node>>),
   // Function, arguments, next instruction
   FuncCall(String, Vec<Node>, Option<Box<Node>>),
   // Expr, operation, Expr
@@ -18,12 +70,40 @@ pub enum Node {
   If(Box<Node>, Box<Node>, Option<Box<Node>>, Option<Box<Node>>),
   // Condition, then body, next instruction
   While(Box<Node>, Box<Node>, Option<Box<Node>>),
+  // Scrutinee, arms (pattern, guard, body), next instruction. In a match expression
+  // the bodies of the arms are expressions, in a match statement they are blocks.
+  Match(
+    Box<Node>,
+    Vec<(Pattern, Option<Node>, Node)>,
+    Option<Box<Node>>,
+  ),
   // Expression, next instruction
   Return(Box<Node>, Option<Box<Node>>),
-  // Expression, next instruction
-  Print(Box<Node>, Option<Box<Node>>),
+  // Text around the placeholders of the format string, arguments, whether a newline
+  // is printed (println!), next instruction
+  Print(Vec<String>, Vec<Node>, bool, Option<Box<Node>>),
+  // Text around the placeholders of the message, arguments, line of the panic!,
+  // assert! or assert_eq! it comes from, next instruction
+  Panic(Vec<String>, Vec<Node>, usize, Option<Box<Node>>),
+  // Condition of a contract, message reported when it does not hold, line of the
+  // contract, next instruction
+  Check(Box<Node>, String, usize, Option<Box<Node>>),
   // Next instruction
   DebugContext(Option<Box<Node>>),
+  // Function declared in a block, next instruction. Nested functions are hoisted
+  // out of the bodies they are declared in when parsing.
+  FuncDec(Box<Func>, Option<Box<Node>>),
+  // Macro, tokens it is invoked with, byte offsets of the start and end of the
+  // invocation, whether it is a statement, next instruction. Macros are expanded
+  // once a file is parsed.
+  MacroCall(
+    String,
+    Vec<TokenTree>,
+    usize,
+    usize,
+    bool,
+    Option<Box<Node>>,
+  ),
   Empty,
 }
 
@@ -35,12 +115,21 @@ impl Node {
   pub fn attach_next_instruction(&mut self, next_instr: Node) {
     match *self {
       Node::Let(.., ref mut right_most)
+      | Node::LetTuple(.., ref mut right_most)
+      | Node::Declare(.., ref mut right_most)
       | Node::FuncCall(.., ref mut right_most)
       | Node::Assign(.., ref mut right_most)
+      | Node::IndexAssign(.., ref mut right_most)
+      | Node::FieldAssign(.., ref mut right_most)
       | Node::If(.., ref mut right_most)
       | Node::Return(.., ref mut right_most)
       | Node::Print(.., ref mut right_most)
+      | Node::Panic(.., ref mut right_most)
+      | Node::Check(.., ref mut right_most)
       | Node::While(.., ref mut right_most)
+      | Node::Match(.., ref mut right_most)
+      | Node::FuncDec(_, ref mut right_most)
+      | Node::MacroCall(.., ref mut right_most)
       | Node::DebugContext(ref mut right_most) => {
         *right_most = Some(Box::new(next_instr))
       }
@@ -51,12 +140,21 @@ impl Node {
   pub fn get_next_instruction(&self) -> Option<&Node> {
     match self {
       Node::Let(.., ref right_most)
+      | Node::LetTuple(.., ref right_most)
+      | Node::Declare(.., ref right_most)
       | Node::FuncCall(.., ref right_most)
       | Node::Assign(.., ref right_most)
+      | Node::IndexAssign(.., ref right_most)
+      | Node::FieldAssign(.., ref right_most)
       | Node::If(.., ref right_most)
       | Node::Return(.., ref right_most)
       | Node::Print(.., ref right_most)
+      | Node::Panic(.., ref right_most)
+      | Node::Check(.., ref right_most)
       | Node::While(.., ref right_most)
+      | Node::Match(.., ref right_most)
+      | Node::FuncDec(_, ref right_most)
+      | Node::MacroCall(.., ref right_most)
       | Node::DebugContext(ref right_most) => match right_most {
         Some(node) => Some(&*node),
         _ => None,
@@ -65,11 +163,422 @@ impl Node {
     }
   }
 
+  // Attaches an instruction after the last instruction of the sequence starting
+  // with the node
+  pub fn append_instruction(&mut self, next_instr: Node) {
+    match self {
+      Node::Let(.., right_most)
+      | Node::LetTuple(.., right_most)
+      | Node::Declare(.., right_most)
+      | Node::FuncCall(.., right_most)
+      | Node::Assign(.., right_most)
+      | Node::IndexAssign(.., right_most)
+      | Node::FieldAssign(.., right_most)
+      | Node::If(.., right_most)
+      | Node::Return(.., right_most)
+      | Node::Print(.., right_most)
+      | Node::Panic(.., right_most)
+      | Node::Check(.., right_most)
+      | Node::While(.., right_most)
+      | Node::Match(.., right_most)
+      | Node::FuncDec(_, right_most)
+      | Node::MacroCall(.., right_most)
+      | Node::DebugContext(right_most) => match right_most {
+        Some(node) => node.append_instruction(next_instr),
+        None => *right_most = Some(Box::new(next_instr)),
+      },
+      _ => panic!("Failed to append instruction (unknown nodetype)!"),
+    }
+  }
+
+  // The nodes right below a node, its subexpressions, the bodies it contains and
+  // the instruction following it
+  pub fn children_mut(&mut self) -> Vec<&mut Node> {
+    let mut children: Vec<&mut Node> = vec![];
+    let next = match self {
+      Node::Array(elements) | Node::Tuple(elements) | Node::Variant(_, _, elements) => {
+        children.extend(elements.iter_mut());
+        return children;
+      }
+      Node::ArrayRepeat(expr, _)
+      | Node::TupleField(expr, _)
+      | Node::Field(expr, _)
+      | Node::Closure(_, _, expr, _) => {
+        children.push(expr);
+        return children;
+      }
+      Node::Index(left, right) | Node::Op(left, _, right) => {
+        children.push(left);
+        children.push(right);
+        return children;
+      }
+      Node::Struct(_, fields) => {
+        children.extend(fields.iter_mut().map(|(_, expr)| expr));
+        return children;
+      }
+      Node::MethodCall(receiver, _, args) => {
+        children.push(receiver);
+        children.extend(args.iter_mut());
+        return children;
+      }
+      Node::Let(.., expr, next)
+      | Node::LetTuple(_, _, expr, next)
+      | Node::Assign(_, expr, next)
+      | Node::Return(expr, next)
+      | Node::Check(expr, _, _, next) => {
+        children.push(expr);
+        next
+      }
+      Node::IndexAssign(array, index, expr, next) => {
+        children.push(array);
+        children.push(index);
+        children.push(expr);
+        next
+      }
+      Node::FieldAssign(r#struct, _, expr, next) => {
+        children.push(r#struct);
+        children.push(expr);
+        next
+      }
+      Node::FuncCall(_, args, next)
+      | Node::Print(_, args, _, next)
+      | Node::Panic(_, args, _, next) => {
+        children.extend(args.iter_mut());
+        next
+      }
+      Node::If(condition, then_body, else_body, next) => {
+        children.push(condition);
+        children.push(then_body);
+        children.extend(else_body.as_deref_mut());
+        next
+      }
+      Node::While(condition, body, next) => {
+        children.push(condition);
+        children.push(body);
+        next
+      }
+      Node::Match(scrutinee, arms, next) => {
+        children.push(scrutinee);
+        for (_, guard, body) in arms.iter_mut() {
+          children.extend(guard.as_mut());
+          children.push(body);
+        }
+        next
+      }
+      Node::FuncDec(func, next) => {
+        children.push(&mut func.body_start);
+        next
+      }
+      Node::DebugContext(next) | Node::MacroCall(.., next) | Node::Declare(.., next) => {
+        next
+      }
+      Node::Number(_)
+      | Node::Bool(_)
+      | Node::Char(_)
+      | Node::Str(_)
+      | Node::Unit
+      | Node::Var(_)
+      | Node::Empty => return children,
+    };
+    children.extend(next.as_deref_mut());
+    children
+  }
+
+  // Names of the variables an expression refers to that are not bound inside of
+  // it, in the order they first appear. The callee of a call is included as it may
+  // be a variable holding a closure.
+  pub fn free_variables(&self) -> Vec<String> {
+    let mut free = vec![];
+    self.collect_free_variables(&mut vec![], &mut free);
+    free
+  }
+
+  fn collect_free_variables(&self, bound: &mut Vec<String>, free: &mut Vec<String>) {
+    match self {
+      Node::Var(name) | Node::FuncCall(name, ..)
+        if !bound.contains(name) && !free.contains(name) =>
+      {
+        free.push(name.clone())
+      }
+      _ => (),
+    };
+    match self {
+      Node::Array(elements)
+      | Node::Tuple(elements)
+      | Node::Variant(_, _, elements)
+      | Node::FuncCall(_, elements, _) => {
+        for element in elements.iter() {
+          element.collect_free_variables(bound, free);
+        }
+      }
+      Node::ArrayRepeat(expr, _) | Node::TupleField(expr, _) | Node::Field(expr, _) => {
+        expr.collect_free_variables(bound, free)
+      }
+      Node::Index(left, right) | Node::Op(left, _, right) => {
+        left.collect_free_variables(bound, free);
+        right.collect_free_variables(bound, free);
+      }
+      Node::Struct(_, fields) => {
+        for (_, expr) in fields.iter() {
+          expr.collect_free_variables(bound, free);
+        }
+      }
+      Node::MethodCall(receiver, _, args) => {
+        receiver.collect_free_variables(bound, free);
+        for arg in args.iter() {
+          arg.collect_free_variables(bound, free);
+        }
+      }
+      Node::Match(scrutinee, arms, _) => {
+        scrutinee.collect_free_variables(bound, free);
+        for (pattern, guard, body) in arms.iter() {
+          let len = bound.len();
+          bound.append(&mut pattern.bindings());
+          if let Some(guard) = guard {
+            guard.collect_free_variables(bound, free);
+          }
+          body.collect_free_variables(bound, free);
+          bound.truncate(len);
+        }
+      }
+      Node::Closure(params, _, body, _) => {
+        let len = bound.len();
+        bound.extend(params.iter().map(|param| param.name.clone()));
+        body.collect_free_variables(bound, free);
+        bound.truncate(len);
+      }
+      _ => (),
+    }
+  }
+
+  // Replaces the types written in the body of a function by f, the types of
+  // variables and closures as well as the structs and enums literals and patterns
+  // are built from. The names of the latter are kept unless f maps them to another
+  // named type.
+  pub fn map_types(&mut self, f: &dyn Fn(&Type) -> Type) {
+    let map = |r#type: &mut Option<Type>| {
+      if let Some(r#type) = r#type {
+        *r#type = f(r#type);
+      }
+    };
+    let next = match self {
+      Node::Let(_, r#type, _, expr, next) | Node::LetTuple(_, r#type, expr, next) => {
+        map(r#type);
+        expr.map_types(f);
+        next
+      }
+      Node::Closure(params, ret_type, body, _) => {
+        for param in params.iter_mut() {
+          param._type = f(&param._type);
+        }
+        map(ret_type);
+        body.map_types(f);
+        return;
+      }
+      Node::Variant(r#enum, _, elements) => {
+        map_type_name(r#enum, f);
+        elements.iter_mut().for_each(|element| element.map_types(f));
+        return;
+      }
+      Node::Array(elements) | Node::Tuple(elements) => {
+        elements.iter_mut().for_each(|element| element.map_types(f));
+        return;
+      }
+      Node::Struct(name, fields) => {
+        map_type_name(name, f);
+        fields.iter_mut().for_each(|(_, expr)| expr.map_types(f));
+        return;
+      }
+      Node::ArrayRepeat(expr, _) | Node::TupleField(expr, _) | Node::Field(expr, _) => {
+        expr.map_types(f);
+        return;
+      }
+      Node::Index(left, right) | Node::Op(left, _, right) => {
+        left.map_types(f);
+        right.map_types(f);
+        return;
+      }
+      Node::MethodCall(receiver, _, args) => {
+        receiver.map_types(f);
+        args.iter_mut().for_each(|arg| arg.map_types(f));
+        return;
+      }
+      Node::Assign(_, expr, next)
+      | Node::Return(expr, next)
+      | Node::Check(expr, _, _, next) => {
+        expr.map_types(f);
+        next
+      }
+      Node::IndexAssign(array, index, expr, next) => {
+        array.map_types(f);
+        index.map_types(f);
+        expr.map_types(f);
+        next
+      }
+      Node::FieldAssign(r#struct, _, expr, next) => {
+        r#struct.map_types(f);
+        expr.map_types(f);
+        next
+      }
+      Node::FuncCall(_, args, next)
+      | Node::Print(_, args, _, next)
+      | Node::Panic(_, args, _, next) => {
+        args.iter_mut().for_each(|arg| arg.map_types(f));
+        next
+      }
+      Node::If(condition, then_body, else_body, next) => {
+        condition.map_types(f);
+        then_body.map_types(f);
+        if let Some(else_body) = else_body {
+          else_body.map_types(f);
+        }
+        next
+      }
+      Node::While(condition, body, next) => {
+        condition.map_types(f);
+        body.map_types(f);
+        next
+      }
+      Node::Match(scrutinee, arms, next) => {
+        scrutinee.map_types(f);
+        for (pattern, guard, body) in arms.iter_mut() {
+          pattern.map_types(f);
+          if let Some(guard) = guard {
+            guard.map_types(f);
+          }
+          body.map_types(f);
+        }
+        next
+      }
+      // Nested functions are hoisted before their types are mapped
+      Node::DebugContext(next) | Node::FuncDec(_, next) => next,
+      Node::Declare(_, r#type, _, next) => {
+        *r#type = f(r#type);
+        next
+      }
+      Node::MacroCall(.., next) => next,
+      Node::Number(_)
+      | Node::Bool(_)
+      | Node::Char(_)
+      | Node::Str(_)
+      | Node::Unit
+      | Node::Var(_)
+      | Node::Empty => return,
+    };
+    if let Some(next) = next {
+      next.map_types(f);
+    }
+  }
+
   pub fn expr_into_string(&self) -> String {
     match self {
       Node::Number(i) => i.to_string(),
       Node::Bool(b) => b.to_string(),
-      Node::Var(name) => name.clone(),
+      // Debug formatting quotes and escapes them the way they are written
+      Node::Char(c) => format!("{:?}", c),
+      Node::Str(s) => format!("{:?}", s),
+      Node::Unit => "()".to_string(),
+      // Variables introduced by the compiler are shown under the name they stand
+      // for, e.g. the left operand of assert_eq! or a local of a macro
+      Node::Var(name) => name.split('#').next().unwrap().to_string(),
+      Node::Array(elements) => format!(
+        "[{}]",
+        elements
+          .iter()
+          .map(|element| element.expr_into_string())
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      Node::ArrayRepeat(element, len) => {
+        format!("[{}; {}]", element.expr_into_string(), len)
+      }
+      Node::Index(array, index) => {
+        format!("{}[{}]", array.expr_into_string(), index.expr_into_string())
+      }
+      Node::Tuple(elements) if elements.len() == 1 => {
+        format!("({},)", elements[0].expr_into_string())
+      }
+      Node::Tuple(elements) => format!(
+        "({})",
+        elements
+          .iter()
+          .map(|element| element.expr_into_string())
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      Node::TupleField(tuple, field) => {
+        format!("{}.{}", tuple.expr_into_string(), field)
+      }
+      Node::Struct(name, fields) => format!(
+        "{} {{ {} }}",
+        name,
+        fields
+          .iter()
+          .map(|(field, expr)| format!("{}: {}", field, expr.expr_into_string()))
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      Node::Field(r#struct, field) => {
+        format!("{}.{}", r#struct.expr_into_string(), field)
+      }
+      Node::Variant(r#enum, variant, fields) if fields.len() == 0 => {
+        format!("{}::{}", r#enum, variant)
+      }
+      Node::Variant(r#enum, variant, fields) => format!(
+        "{}::{}({})",
+        r#enum,
+        variant,
+        fields
+          .iter()
+          .map(|field| field.expr_into_string())
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      Node::Match(scrutinee, arms, _) => format!(
+        "match {} {{ {} }}",
+        scrutinee.expr_into_string(),
+        arms
+          .iter()
+          .map(|(pattern, guard, expr)| match guard {
+            Some(guard) => format!(
+              "{} if {} => {}",
+              pattern.to_str(),
+              guard.expr_into_string(),
+              expr.expr_into_string()
+            ),
+            None => format!("{} => {}", pattern.to_str(), expr.expr_into_string()),
+          })
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      Node::FuncCall(func, args, _) => format!(
+        "{}({})",
+        func,
+        args
+          .iter()
+          .map(|arg| arg.expr_into_string())
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      Node::MethodCall(receiver, method, args) => format!(
+        "{}.{}({})",
+        receiver.expr_into_string(),
+        method,
+        args
+          .iter()
+          .map(|arg| arg.expr_into_string())
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      Node::Closure(params, _, body, _) => format!(
+        "|{}| {}",
+        params
+          .iter()
+          .map(|param| format!("{}: {}", param.name, param._type.to_str()))
+          .collect::<Vec<String>>()
+          .join(", "),
+        body.expr_into_string()
+      ),
       Node::Op(left, op, right) => format!(
         "{} {} {}",
         //If the left side is an operation add parenthesis
@@ -91,50 +600,6 @@ impl Node {
   }
 }
 
-impl std::ops::Add<Node> for Node {
-  type Output = Node;
-
-  fn add(self, other: Node) -> Node {
-    match (self, other) {
-      (Node::Number(n1), Node::Number(n2)) => Node::Number(n1 + n2),
-      _ => panic!("Type error"),
-    }
-  }
-}
-
-impl std::ops::Sub<Node> for Node {
-  type Output = Node;
-
-  fn sub(self, other: Node) -> Node {
-    match (self, other) {
-      (Node::Number(n1), Node::Number(n2)) => Node::Number(n1 - n2),
-      _ => panic!("Type error"),
-    }
-  }
-}
-
-impl std::ops::Mul<Node> for Node {
-  type Output = Node;
-
-  fn mul(self, other: Node) -> Node {
-    match (self, other) {
-      (Node::Number(n1), Node::Number(n2)) => Node::Number(n1 * n2),
-      _ => panic!("Type error"),
-    }
-  }
-}
-
-impl std::ops::Div<Node> for Node {
-  type Output = Node;
-
-  fn div(self, other: Node) -> Node {
-    match (self, other) {
-      (Node::Number(n1), Node::Number(n2)) => Node::Number(n1 / n2),
-      _ => panic!("Type error"),
-    }
-  }
-}
-
 impl std::cmp::PartialOrd<Node> for Node {
   fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
     match (self, other) {
@@ -0,0 +1,78 @@
+use crate::types::_type::{map_type_name, Type};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+  // _, matches anything
+  Wildcard,
+  // Matches anything and binds it to the variable
+  Binding(String),
+  // Enum, variant, patterns of the fields
+  Variant(String, String, Vec<Pattern>),
+  // Integer literal
+  Int(i32),
+  // Boolean literal
+  Bool(bool),
+  // Start and end of an inclusive range of integers, start..=end
+  Range(i32, i32),
+  // Alternatives, matches if any of them match
+  Or(Vec<Pattern>),
+}
+
+impl Pattern {
+  // Names of the variables bound by the pattern, the alternatives of an or-pattern
+  // all bind the same variables
+  pub fn bindings(&self) -> Vec<String> {
+    match self {
+      Pattern::Binding(name) => vec![name.clone()],
+      Pattern::Variant(_, _, fields) => {
+        fields.iter().flat_map(|field| field.bindings()).collect()
+      }
+      Pattern::Or(alternatives) => alternatives[0].bindings(),
+      _ => vec![],
+    }
+  }
+
+  // Maps the names of the enums the pattern matches variants of, see Node::map_types
+  pub fn map_types(&mut self, f: &dyn Fn(&Type) -> Type) {
+    match self {
+      Pattern::Variant(r#enum, _, fields) => {
+        map_type_name(r#enum, f);
+        fields.iter_mut().for_each(|field| field.map_types(f));
+      }
+      Pattern::Or(alternatives) => {
+        alternatives
+          .iter_mut()
+          .for_each(|pattern| pattern.map_types(f));
+      }
+      _ => (),
+    }
+  }
+
+  pub fn to_str(&self) -> String {
+    match self {
+      Pattern::Wildcard => "_".to_string(),
+      Pattern::Binding(name) => name.clone(),
+      Pattern::Variant(r#enum, variant, fields) if fields.len() == 0 => {
+        format!("{}::{}", r#enum, variant)
+      }
+      Pattern::Variant(r#enum, variant, fields) => format!(
+        "{}::{}({})",
+        r#enum,
+        variant,
+        fields
+          .iter()
+          .map(|field| field.to_str())
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      Pattern::Int(n) => n.to_string(),
+      Pattern::Bool(b) => b.to_string(),
+      Pattern::Range(start, end) => format!("{}..={}", start, end),
+      Pattern::Or(alternatives) => alternatives
+        .iter()
+        .map(|alternative| alternative.to_str())
+        .collect::<Vec<String>>()
+        .join(" | "),
+    }
+  }
+}
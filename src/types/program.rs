@@ -1,77 +1,304 @@
-use std::{collections::HashMap, fs::File, io::prelude::*, path::Path};
+use std::{cell::RefCell, collections::HashMap, path::Path};
 
 use crate::{
-  errors::parse_error::ParseError,
-  parsing::file_parser::parse,
-  types::{context::Context, func::Func, value::Value},
+  errors::{
+    parse_error::ParseError,
+    parse_warning::ParseWarning,
+    resolve_error::ResolveError,
+    runtime_error::{RuntimeError, RuntimeErrorKind},
+  },
+  parsing::{
+    aliases::{resolve_func, resolve_type},
+    hoist::{hoist_nested_funcs, resolve_names},
+    modules::{qualify, Modules},
+  },
+  types::{
+    _type::Type,
+    context::Context,
+    enum_dec::EnumDec,
+    func::Func,
+    global_dec::GlobalDec,
+    impl_dec::ImplDec,
+    item::Item,
+    location::Location,
+    scope::Scope,
+    struct_dec::StructDec,
+    symbol_table::{SymbolKind, SymbolTable},
+    trait_dec::TraitDec,
+    value::Value,
+    variable::Variable,
+  },
 };
 
+// The items of a program are known by their path in the modules of the program,
+// see Modules
 pub struct Program {
+  // Functions along with the methods of the impls, see ImplDec::method_name
   pub funcs: HashMap<String, Func>,
-  file: String,
+  pub structs: HashMap<String, StructDec>,
+  pub enums: HashMap<String, EnumDec>,
+  pub traits: HashMap<String, TraitDec>,
+  pub impls: Vec<ImplDec>,
+  // Constants and statics in declaration order
+  pub globals: Vec<GlobalDec>,
+  // Values of the globals, evaluated once by the type checker for every run of the
+  // program
+  pub global_values: RefCell<Scope<Variable>>,
+  // Type aliases and the types they stand for, as written
+  pub aliases: HashMap<String, Type>,
+  // Deprecated syntax found when parsing
+  pub warnings: Vec<ParseWarning>,
+  // Files of the items and modules declared outside of the root file, by path
+  pub files: HashMap<String, String>,
+  // Errors found when resolving paths, along with the module they are used in
+  pub resolve_errors: Vec<(String, ResolveError)>,
+  // Paths of the functions marked #[test], in declaration order
+  pub tests: Vec<String>,
+  // Declaration order of the functions, structs, enums, traits, aliases and globals
+  pub symbols: SymbolTable,
 }
 
 impl std::convert::TryFrom<&Path> for Program {
   type Error = ParseError;
   fn try_from(path: &Path) -> Result<Self, Self::Error> {
-    let mut file = match File::open(path) {
-      Ok(file) => file,
-      Err(e) => panic!("Could not open input file: {}", e),
-    };
-
-    let mut s = String::new();
-    if let Err(e) = file.read_to_string(&mut s) {
-      panic!("Could not read input file: {}", e);
+    let modules = match Modules::load(path) {
+      Ok(modules) => modules,
+      Err(e) => return Err(e),
     };
 
     let mut program = Program {
       funcs: HashMap::new(),
-      file: s,
+      structs: HashMap::new(),
+      enums: HashMap::new(),
+      traits: HashMap::new(),
+      impls: vec![],
+      globals: vec![],
+      global_values: RefCell::new(Scope::new()),
+      aliases: HashMap::new(),
+      warnings: vec![],
+      files: HashMap::new(),
+      resolve_errors: vec![],
+      tests: vec![],
+      symbols: SymbolTable::default(),
     };
-    if let Err(e) = program.parse() {
-      return Err(e);
-    }
+    program.parse(modules);
     Ok(program)
   }
 }
 
 impl Program {
+  // Interprets the program, panicking with the error the program fails with
   #[allow(dead_code)]
-  pub fn interpret(&self) -> Option<Value> {
-    match self.funcs.get("main") {
-      Some(main) => main.execute(&vec![], &self.funcs, &mut Context::from(main)),
-      None => panic!("No main function found"),
+  pub fn interpret(&self) -> Value {
+    match self.try_interpret() {
+      Ok(value) => value,
+      Err(e) => panic!("{}", e),
     }
   }
 
-  fn parse(&mut self) -> Result<(), ParseError> {
-    match parse(&self.file) {
-      Ok(funcs) => {
-        self.funcs = funcs;
-        return Ok(());
+  // The program is type checked first, which evaluates its constants and statics
+  pub fn try_interpret(&self) -> Result<Value, RuntimeError> {
+    match self.funcs.contains_key("main") {
+      true => self.call("main"),
+      false => Err(RuntimeError::new(
+        RuntimeErrorKind::UndefinedFunction,
+        "No main function found".to_string(),
+      )),
+    }
+  }
+
+  // Runs a test function, a failed assertion is returned as an error
+  pub fn run_test(&self, name: &str) -> Result<Value, RuntimeError> {
+    self.call(name)
+  }
+
+  // Calls a function taking no arguments
+  fn call(&self, name: &str) -> Result<Value, RuntimeError> {
+    let func = &self.funcs[name];
+    let mut context = Context::from(func);
+    context.globals = self.global_values.borrow().clone();
+    func.execute(&vec![], &self.funcs, &mut context)
+  }
+
+  // Collects the items of the modules under their paths, with the names they
+  // refer to resolved
+  fn parse(&mut self, mut modules: Modules) {
+    self.warnings = std::mem::replace(&mut modules.warnings, vec![]);
+    let items = std::mem::replace(&mut modules.items, vec![]);
+
+    // Aliases can be used before they are declared
+    for (module, item) in items.iter() {
+      if let Item::Alias(name, aliased) = item {
+        let path = qualify(module, name);
+        if !self.aliases.contains_key(&path) {
+          self
+            .symbols
+            .declare(SymbolKind::Alias, &path, Location::default());
+        }
+        self
+          .aliases
+          .insert(path, modules.resolve_type(module, aliased, &vec![]));
       }
-      Err(e) => Err(e),
     }
+    let aliases = &self.aliases;
+
+    for (module, item) in items {
+      let names = |name: &str| modules.resolve(&module, name);
+      let resolve = |r#type: &mut Type| {
+        let in_module = modules.resolve_type(&module, r#type, &vec![]);
+        *r#type = resolve_type(&in_module, aliases, &vec![]);
+      };
+
+      if let Item::Test(func) = &item {
+        let path = qualify(&module, &func.name);
+        if !self.funcs.contains_key(&path) {
+          self.tests.push(path);
+        }
+      }
+      let mut declared = vec![];
+      match item {
+        Item::Func(mut func) | Item::Test(mut func) => {
+          func.name = qualify(&module, &func.name);
+          for mut func in hoist_nested_funcs(func, &names) {
+            modules.resolve_func(&module, &mut func);
+            resolve_func(&mut func, aliases);
+            declared.push(func.name.clone());
+            if self
+              .symbols
+              .declare(SymbolKind::Func, &func.name, func.location)
+            {
+              self.funcs.insert(func.name.clone(), func);
+            }
+          }
+        }
+        Item::Struct(mut r#struct) => {
+          r#struct.name = qualify(&module, &r#struct.name);
+          r#struct
+            .fields
+            .iter_mut()
+            .for_each(|(_, r#type)| resolve(r#type));
+          declared.push(r#struct.name.clone());
+          // The first declaration is kept, later ones are reported as duplicates
+          let location = r#struct.location;
+          if self
+            .symbols
+            .declare(SymbolKind::Struct, &r#struct.name, location)
+          {
+            self.structs.insert(r#struct.name.clone(), r#struct);
+          }
+        }
+        Item::Enum(mut r#enum) => {
+          r#enum.name = qualify(&module, &r#enum.name);
+          r#enum
+            .variants
+            .iter_mut()
+            .flat_map(|(_, types)| types)
+            .for_each(resolve);
+          declared.push(r#enum.name.clone());
+          let location = r#enum.location;
+          if self
+            .symbols
+            .declare(SymbolKind::Enum, &r#enum.name, location)
+          {
+            self.enums.insert(r#enum.name.clone(), r#enum);
+          }
+        }
+        Item::Trait(mut r#trait) => {
+          r#trait.name = qualify(&module, &r#trait.name);
+          for method in r#trait.methods.iter_mut() {
+            modules.resolve_func(&module, method);
+            resolve_func(method, aliases);
+          }
+          declared.push(r#trait.name.clone());
+          let location = r#trait.location;
+          if self
+            .symbols
+            .declare(SymbolKind::Trait, &r#trait.name, location)
+          {
+            self.traits.insert(r#trait.name.clone(), r#trait);
+          }
+        }
+        // An impl for an alias is an impl for the type it stands for
+        Item::Impl(r#trait, mut r#type, methods) => {
+          let r#trait = modules.resolve(&module, &r#trait).unwrap_or(r#trait);
+          resolve(&mut r#type);
+          let (r#impl, methods) = ImplDec::new(r#trait, r#type.resolved(), methods);
+          for method in methods {
+            for mut method in hoist_nested_funcs(method, &names) {
+              modules.resolve_func(&module, &mut method);
+              resolve_func(&mut method, aliases);
+              declared.push(method.name.clone());
+              let location = method.location;
+              if self
+                .symbols
+                .declare(SymbolKind::Func, &method.name, location)
+              {
+                self.funcs.insert(method.name.clone(), method);
+              }
+            }
+          }
+          self.impls.push(r#impl);
+        }
+        Item::Global(mut global) => {
+          global.name = qualify(&module, &global.name);
+          resolve(&mut global._type);
+          global.expr = resolve_names(&global.expr, &names);
+          global
+            .expr
+            .map_types(&|r#type| modules.resolve_type(&module, r#type, &vec![]));
+          declared.push(global.name.clone());
+          let location = global.location;
+          if self
+            .symbols
+            .declare(SymbolKind::Global, &global.name, location)
+          {
+            self.globals.push(global);
+          }
+        }
+        Item::Alias(name, _) => declared.push(qualify(&module, &name)),
+        // Modules and use declarations are resolved when loading the modules, and
+        // macros expanded
+        Item::Mod(..) | Item::Use(_) | Item::Macro(_) => (),
+      }
+
+      if let Some(file) = modules.file_of(&module) {
+        for name in declared {
+          self.files.insert(name, file.clone());
+        }
+      }
+    }
+
+    for module in modules.paths() {
+      if let Some(file) = modules.file_of(module) {
+        self.files.insert(module.clone(), file);
+      }
+    }
+    self.resolve_errors = modules.take_errors();
   }
 }
 
 #[cfg(test)]
 mod tests {
-  use super::{Path, Program, Value};
-  use crate::type_checker::type_check_program;
+  use super::{Path, Program, RuntimeError, RuntimeErrorKind, Value};
+  use crate::{output, type_checker::type_check_program};
   use std::convert::TryFrom;
 
   #[test]
-  #[should_panic]
   fn test_missing_main() {
     let program = Program::try_from(Path::new("tests/samples/missing_main.rs")).unwrap();
-    program.interpret();
+    assert_eq!(
+      program.try_interpret(),
+      Err(RuntimeError::new(
+        RuntimeErrorKind::UndefinedFunction,
+        "No main function found".to_string()
+      ))
+    )
   }
 
   #[test]
   fn test_empty_main() {
     let program = Program::try_from(Path::new("tests/samples/empty_main.rs")).unwrap();
-    assert!(program.interpret().is_none());
+    assert_eq!(program.interpret(), Value::Unit);
   }
 
   #[test]
@@ -79,11 +306,8 @@ mod tests {
     let program =
       Program::try_from(Path::new("tests/samples/return_in_main.rs")).unwrap();
     assert!(match program.interpret() {
-      Some(value) => match value {
-        Value::Int(3982) => true,
-        _ => false,
-      },
-      None => false,
+      Value::Int(3982) => true,
+      _ => false,
     })
   }
 
@@ -92,21 +316,21 @@ mod tests {
     let program =
       Program::try_from(Path::new("tests/samples/if_statement_true.rs")).unwrap();
     type_check_program(&program).unwrap();
-    assert_eq!(program.interpret().unwrap(), Value::Int(5))
+    assert_eq!(program.interpret(), Value::Int(5))
   }
 
   #[test]
   fn test_if_else() {
     let program = Program::try_from(Path::new("tests/samples/if_else.rs")).unwrap();
     type_check_program(&program).unwrap();
-    assert_eq!(program.interpret().unwrap(), Value::Int(2))
+    assert_eq!(program.interpret(), Value::Int(2))
   }
 
   #[test]
   fn test_assign() {
     let program = Program::try_from(Path::new("tests/samples/assign.rs")).unwrap();
     type_check_program(&program).unwrap();
-    assert_eq!(program.interpret().unwrap(), Value::Int(6))
+    assert_eq!(program.interpret(), Value::Int(6))
   }
 
   #[test]
@@ -116,7 +340,7 @@ mod tests {
     if let Err(e) = type_check_program(&program) {
       panic!("{:?}", e);
     }
-    assert_eq!(program.interpret().unwrap(), Value::Int(34))
+    assert_eq!(program.interpret(), Value::Int(34))
   }
 
   #[test]
@@ -124,7 +348,7 @@ mod tests {
     let program =
       Program::try_from(Path::new("tests/samples/nested_function_calls.rs")).unwrap();
     type_check_program(&program).unwrap();
-    assert_eq!(program.interpret().unwrap(), Value::Int(6))
+    assert_eq!(program.interpret(), Value::Int(6))
   }
 
   #[test]
@@ -132,7 +356,7 @@ mod tests {
     let program =
       Program::try_from(Path::new("tests/samples/type_inference_i32.rs")).unwrap();
     type_check_program(&program).unwrap();
-    assert_eq!(program.interpret().unwrap(), Value::Int(34))
+    assert_eq!(program.interpret(), Value::Int(34))
   }
 
   #[test]
@@ -140,7 +364,7 @@ mod tests {
     let program =
       Program::try_from(Path::new("tests/samples/type_inference_bool.rs")).unwrap();
     type_check_program(&program).unwrap();
-    assert_eq!(program.interpret().unwrap(), Value::Bool(true))
+    assert_eq!(program.interpret(), Value::Bool(true))
   }
 
   #[test]
@@ -148,7 +372,7 @@ mod tests {
     let program =
       Program::try_from(Path::new("tests/samples/shadowing_return_shadowed.rs")).unwrap();
     type_check_program(&program).unwrap();
-    assert_eq!(program.interpret().unwrap(), Value::Int(4))
+    assert_eq!(program.interpret(), Value::Int(4))
   }
 
   #[test]
@@ -156,7 +380,502 @@ mod tests {
     let program =
       Program::try_from(Path::new("tests/samples/shadowing_return_original.rs")).unwrap();
     type_check_program(&program).unwrap();
-    assert_eq!(program.interpret().unwrap(), Value::Int(14))
+    assert_eq!(program.interpret(), Value::Int(14))
+  }
+
+  #[test]
+  fn unit_function_let() {
+    let program =
+      Program::try_from(Path::new("tests/samples/unit_function_let.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(4))
+  }
+
+  #[test]
+  fn unit_function_return() {
+    let program =
+      Program::try_from(Path::new("tests/samples/unit_function_return.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(5))
+  }
+
+  #[test]
+  fn array_sum() {
+    let program = Program::try_from(Path::new("tests/samples/array_sum.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(10))
+  }
+
+  #[test]
+  fn array_index_assign() {
+    let program =
+      Program::try_from(Path::new("tests/samples/array_index_assign.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(7))
+  }
+
+  #[test]
+  fn array_out_of_bounds() {
+    let program =
+      Program::try_from(Path::new("tests/samples/array_out_of_bounds.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    let error = program.try_interpret().unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::IndexOutOfBounds);
+    assert_eq!(
+      error.to_string(),
+      "panicked at index out of bounds: the len is 3 but the index is 3, \
+       in main"
+    )
+  }
+
+  #[test]
+  fn tuple_return() {
+    let program = Program::try_from(Path::new("tests/samples/tuple_return.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(32))
+  }
+
+  #[test]
+  fn tuple_nested() {
+    let program = Program::try_from(Path::new("tests/samples/tuple_nested.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(242))
+  }
+
+  #[test]
+  fn struct_fields() {
+    let program = Program::try_from(Path::new("tests/samples/struct_fields.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(50))
+  }
+
+  #[test]
+  fn struct_array() {
+    let program = Program::try_from(Path::new("tests/samples/struct_array.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(7))
+  }
+
+  #[test]
+  fn enum_match() {
+    let program = Program::try_from(Path::new("tests/samples/enum_match.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(124))
+  }
+
+  #[test]
+  fn match_int_bool() {
+    let program =
+      Program::try_from(Path::new("tests/samples/match_int_bool.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(72))
+  }
+
+  #[test]
+  fn strings() {
+    let program = Program::try_from(Path::new("tests/samples/strings.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(127))
+  }
+
+  #[test]
+  fn formatted_output() {
+    let program =
+      Program::try_from(Path::new("tests/samples/formatted_output.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(15))
+  }
+
+  #[test]
+  fn debug_context() {
+    let program = Program::try_from(Path::new("tests/samples/debug_context.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    let (value, output) = output::capture(|| program.interpret());
+    assert_eq!(value, Value::Int(15));
+    // total is left out until it is assigned a value
+    assert_eq!(
+      output,
+      "count is 3
+$DEBUG_CONTEXT
+  add = <closure>
+  count = 3
+  letters = [a, b]
+  name = unit
+  pair = (true, 7)
+  single = (1,)
+$DEBUG_CONTEXT
+  shape = Shape::Dot
+$DEBUG_CONTEXT
+  center = Point { x: 1, y: 2 }
+  radius = 2
+  shape = Shape::Circle(Point { x: 1, y: 2 }, 2)
+$DEBUG_CONTEXT
+  add = <closure>
+  count = 2
+  letters = [a, b]
+  name = unit
+  pair = (true, 7)
+  single = (1,)
+  total = 15
+"
+    )
+  }
+
+  #[test]
+  fn debug_context_shadowing() {
+    let program =
+      Program::try_from(Path::new("tests/samples/debug_context_shadowing.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    let (value, output) = output::capture(|| program.interpret());
+    assert_eq!(value, Value::Int(12));
+    // A variable shadowing one declared without a value is dumped, one declared
+    // without a value is left out until it is assigned one
+    assert_eq!(
+      output,
+      "$DEBUG_CONTEXT
+  x = 5
+$DEBUG_CONTEXT
+  x = 5
+$DEBUG_CONTEXT
+  x = 5
+  y = 7
+"
+    )
+  }
+
+  #[test]
+  fn generics() {
+    let program = Program::try_from(Path::new("tests/samples/generics.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(45))
+  }
+
+  #[test]
+  fn closures() {
+    let program = Program::try_from(Path::new("tests/samples/closures.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(46))
+  }
+
+  #[test]
+  fn nested_functions() {
+    let program =
+      Program::try_from(Path::new("tests/samples/nested_functions.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(1136))
+  }
+
+  #[test]
+  fn globals() {
+    let program = Program::try_from(Path::new("tests/samples/globals.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(154))
+  }
+
+  #[test]
+  fn type_aliases() {
+    let program = Program::try_from(Path::new("tests/samples/type_aliases.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(97))
+  }
+
+  #[test]
+  fn modules() {
+    let program = Program::try_from(Path::new("tests/samples/modules/main.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(37))
+  }
+
+  #[test]
+  fn macros() {
+    let program = Program::try_from(Path::new("tests/samples/macros.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(36))
+  }
+
+  #[test]
+  fn assertions() {
+    let program = Program::try_from(Path::new("tests/samples/assertions.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.try_interpret(), Ok(Value::Int(7)))
+  }
+
+  #[test]
+  fn assertion_failed() {
+    let program =
+      Program::try_from(Path::new("tests/samples/assertion_failed.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(
+      program.try_interpret(),
+      Err(RuntimeError {
+        kind: RuntimeErrorKind::Panic,
+        message: "assertion `left == right` failed\n  left: 4\n right: 5".to_string(),
+        line: Some(8),
+        stack: vec!["main".to_string()],
+      })
+    )
+  }
+
+  #[test]
+  fn assert_eq_evaluates_operands_once() {
+    let program =
+      Program::try_from(Path::new("tests/samples/assert_eq_once.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    let (result, output) = output::capture(|| program.try_interpret());
+    assert_eq!(output, "next(1)\nnext(2)\nnext(3)\n");
+    assert_eq!(
+      result.unwrap_err().message,
+      "assertion `left == right` failed\n  left: 3\n right: 4"
+    );
+  }
+
+  #[test]
+  fn panic() {
+    let program = Program::try_from(Path::new("tests/samples/panic.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(
+      program.try_interpret().unwrap_err().to_string(),
+      "panicked at attempt to divide 7 by zero, line 3, in checked_div <- main"
+    )
+  }
+
+  #[test]
+  fn runtime_error() {
+    let program = Program::try_from(Path::new("tests/samples/runtime_error.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    let error = program.try_interpret().unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::DivisionByZero);
+    assert_eq!(
+      error.to_string(),
+      "panicked at attempt to divide by zero, in ratio <- <closure> <- main"
+    )
+  }
+
+  #[test]
+  fn overflow() {
+    let program = Program::try_from(Path::new("tests/samples/overflow.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    let error = program.try_interpret().unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::Overflow);
+    assert_eq!(
+      error.to_string(),
+      "panicked at attempt to add with overflow, in sum <- main"
+    )
+  }
+
+  #[test]
+  fn divide_overflow() {
+    let program =
+      Program::try_from(Path::new("tests/samples/divide_overflow.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    let error = program.try_interpret().unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::Overflow);
+    assert_eq!(
+      error.to_string(),
+      "panicked at attempt to divide with overflow, in quotient <- main"
+    )
+  }
+
+  #[test]
+  fn contracts() {
+    let program = Program::try_from(Path::new("tests/samples/contracts.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.try_interpret(), Ok(Value::Int(28)))
+  }
+
+  #[test]
+  fn contract_violated() {
+    let program =
+      Program::try_from(Path::new("tests/samples/contract_violated.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(
+      program.try_interpret(),
+      Err(RuntimeError {
+        kind: RuntimeErrorKind::ContractViolation,
+        message: "postcondition violated: result > a && result > b".to_string(),
+        line: Some(1),
+        stack: vec!["max".to_string(), "main".to_string()],
+      })
+    )
+  }
+
+  #[test]
+  fn invariant_violated() {
+    let program =
+      Program::try_from(Path::new("tests/samples/invariant_violated.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(
+      program.try_interpret(),
+      Err(RuntimeError {
+        kind: RuntimeErrorKind::ContractViolation,
+        message: "loop invariant violated: sum < 10".to_string(),
+        line: Some(4),
+        stack: vec!["main".to_string()],
+      })
+    )
+  }
+
+  #[test]
+  fn deferred_let() {
+    let program = Program::try_from(Path::new("tests/samples/deferred_let.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(16))
+  }
+
+  #[test]
+  fn uninitialized_variables() {
+    let program = Program::try_from(Path::new("tests/samples/uninitialized.rs")).unwrap();
+    let errors: Vec<String> = match type_check_program(&program) {
+      Ok(_) => panic!("Expected definite assignment errors"),
+      Err(errors) => errors.iter().map(|error| error.to_string()).collect(),
+    };
+    assert_eq!(
+      errors,
+      vec![
+        "Variable x is used before being assigned a value",
+        "Variable x is possibly used before being assigned a value",
+        "Cannot assign twice to immutable variable x",
+        "Cannot assign twice to immutable variable x",
+        "Variable x is used before being assigned a value",
+        "Variable x is immutable",
+      ]
+    );
+  }
+
+  #[test]
+  fn branch_returns() {
+    let program =
+      Program::try_from(Path::new("tests/samples/branch_returns.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(26))
+  }
+
+  #[test]
+  fn if_else_returns() {
+    let program =
+      Program::try_from(Path::new("tests/samples/if_else_returns.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(215))
+  }
+
+  #[test]
+  fn missing_return() {
+    let program =
+      Program::try_from(Path::new("tests/samples/missing_return.rs")).unwrap();
+    let errors: Vec<String> = match type_check_program(&program) {
+      Ok(_) => panic!("Expected missing returns"),
+      Err(errors) => errors.iter().map(|error| error.to_string()).collect(),
+    };
+    assert_eq!(
+      errors,
+      vec![
+        "Missing return statement in function sign, expected to return type i32: \
+         no value is returned when n < 0 is false and n > 0 is false",
+        "Missing return statement in function first_square, expected to return type i32: \
+         no value is returned when the while i < n loop ends",
+        "Missing return statement in function pick_one, expected to return type i32: \
+         no value is returned when flag is false",
+        "Missing return statement in function countdown, expected to return type i32: \
+         no value is returned when the while n > 0 loop ends",
+      ]
+    );
+  }
+
+  #[test]
+  fn failing_initializer() {
+    let program =
+      Program::try_from(Path::new("tests/samples/constant_divide_by_zero.rs")).unwrap();
+    let errors: Vec<String> = match type_check_program(&program) {
+      Ok(_) => panic!("Expected the initializer of N to fail"),
+      Err(errors) => errors.iter().map(|error| error.to_string()).collect(),
+    };
+    assert_eq!(
+      errors,
+      vec!["The initializer of N can not be evaluated: attempt to divide by zero"]
+    );
+  }
+
+  #[test]
+  fn duplicates() {
+    let program = Program::try_from(Path::new("tests/samples/duplicates.rs")).unwrap();
+    // The first declarations of a function and a global are kept
+    assert_eq!(program.funcs["area"].params.len(), 2);
+    assert_eq!(program.globals.len(), 1);
+    assert!(!program.globals[0].is_static);
+    let errors: Vec<String> = match type_check_program(&program) {
+      Ok(_) => panic!("Expected duplicate declarations"),
+      Err(errors) => errors.iter().map(|error| error.to_string()).collect(),
+    };
+    assert_eq!(
+      errors,
+      vec![
+        "Function area is declared more than once, at 1:4 and 9:4",
+        "Global LIMIT is declared more than once, at 18:7 and 20:8",
+        "Parameter n of function scale is declared more than once, at 5:10 and 5:31",
+        "Parameter x of closure is declared more than once, at 14:16 and 14:24",
+      ]
+    );
+  }
+
+  #[test]
+  fn duplicate_types() {
+    let program =
+      Program::try_from(Path::new("tests/samples/duplicate_types.rs")).unwrap();
+    // The first declarations of a struct and an enum are kept
+    assert_eq!(program.structs["Point"].fields.len(), 2);
+    assert_eq!(program.enums["Shape"].variants.len(), 2);
+    let errors: Vec<String> = match type_check_program(&program) {
+      Ok(_) => panic!("Expected duplicate declarations"),
+      Err(errors) => errors.iter().map(|error| error.to_string()).collect(),
+    };
+    assert_eq!(
+      errors,
+      vec![
+        "Struct Point is declared more than once, at 1:8 and 11:8",
+        "Enum Shape is declared more than once, at 6:6 and 15:6",
+      ]
+    );
+  }
+
+  #[test]
+  fn private_items() {
+    let program =
+      Program::try_from(Path::new("tests/samples/modules/private.rs")).unwrap();
+    let errors: Vec<String> = match type_check_program(&program) {
+      Ok(_) => panic!("Private function called outside of its module"),
+      Err(errors) => errors.iter().map(|error| error.to_string()).collect(),
+    };
+    assert_eq!(
+      errors,
+      vec![
+        "Unresolved import vault::missing, no item or module has that path",
+        "vault::code is private and can not be used outside of its module",
+      ]
+    );
+  }
+
+  #[test]
+  fn traits() {
+    let program = Program::try_from(Path::new("tests/samples/traits.rs")).unwrap();
+    type_check_program(&program).unwrap();
+    assert_eq!(program.interpret(), Value::Int(50))
+  }
+
+  #[test]
+  fn trait_errors() {
+    let program = Program::try_from(Path::new("tests/samples/trait_errors.rs")).unwrap();
+    let errors: Vec<String> = match type_check_program(&program) {
+      Ok(_) => panic!("Expected type errors"),
+      Err(errors) => errors.iter().map(|error| error.to_string()).collect(),
+    };
+    for expected in vec![
+      "Method scaled of trait Area is missing in its impl for type i32",
+      "Method perimeter is not a member of trait Area",
+      "Method area in the impl of trait Area for type bool does not match its signature in the trait",
+      "Trait Area is not implemented for type char",
+    ] {
+      assert!(errors.contains(&expected.to_string()), "{:?}", errors);
+    }
   }
 
   #[test]
@@ -164,7 +883,7 @@ mod tests {
     let program =
       Program::try_from(Path::new("tests/samples/while_count_to_10.rs")).unwrap();
     type_check_program(&program).unwrap();
-    assert_eq!(program.interpret().unwrap(), Value::Int(10))
+    assert_eq!(program.interpret(), Value::Int(10))
   }
 
   #[test]
@@ -174,7 +893,7 @@ mod tests {
     ))
     .unwrap();
     type_check_program(&program).unwrap();
-    assert_eq!(program.interpret().unwrap(), Value::Int(4))
+    assert_eq!(program.interpret(), Value::Int(4))
   }
 
   #[test]
@@ -184,26 +903,28 @@ mod tests {
     ))
     .unwrap();
     type_check_program(&program).unwrap();
-    assert_eq!(program.interpret().unwrap(), Value::Int(4))
+    assert_eq!(program.interpret(), Value::Int(4))
   }
 
   #[test]
-  #[should_panic]
   fn while_should_not_access_var_in_loop_from_outside() {
     let program = Program::try_from(Path::new(
       "tests/samples/while_should_not_access_var_in_loop_from_outside.rs",
     ))
     .unwrap();
-    program.interpret();
+    let error = program.try_interpret().unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::UndefinedVariable);
+    assert_eq!(error.message, "Undefined variable b");
   }
 
   #[test]
-  #[should_panic]
   fn while_should_not_access_var_in_loop_from_previous_iteration() {
     let program = Program::try_from(Path::new(
       "tests/samples/while_should_not_access_variable_from_previous_iteration.rs",
     ))
     .unwrap();
-    program.interpret();
+    let error = program.try_interpret().unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::UndefinedVariable);
+    assert_eq!(error.message, "Undefined variable b");
   }
 }
@@ -1,22 +1,26 @@
-use crate::types::{func_param::FuncParam, _type::Type, variable::Variable};
-use std::collections::HashMap;
+use crate::types::{
+  func_param::FuncParam, global_dec::GlobalDec, _type::Type, variable::Variable,
+};
+use std::{collections::HashMap, convert::TryFrom};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Scope<T> {
   pub elements: HashMap<String, T>,
 }
 
-impl From<Vec<Variable>> for Scope<Variable> {
-  fn from(mut vars: Vec<Variable>) -> Self {
+impl TryFrom<Vec<Variable>> for Scope<Variable> {
+  type Error = String;
+
+  fn try_from(mut vars: Vec<Variable>) -> Result<Self, Self::Error> {
     let mut map = HashMap::new();
     map.reserve(vars.len());
     for var in vars.drain(..) {
       if map.contains_key(&var.name) {
-        panic!("Duplicate argument");
+        return Err(format!("Duplicate variable {} in scope", var.name));
       }
       map.insert(var.name.clone(), var);
     }
-    Scope { elements: map }
+    Ok(Scope { elements: map })
   }
 }
 
@@ -25,8 +29,10 @@ impl From<Vec<FuncParam>> for Scope<(Type, bool)> {
     let mut map = HashMap::new();
     map.reserve(params.len());
     for param in params.drain(..) {
+      // The type checker rejects duplicate parameters before it declares them, see
+      // check_duplicate_params
       if map.contains_key(&param.name) {
-        panic!("Duplicate argument");
+        unreachable!("Duplicate parameter {}", param.name);
       }
       map.insert(param.name.clone(), (param._type, param.mutable));
     }
@@ -34,6 +40,17 @@ impl From<Vec<FuncParam>> for Scope<(Type, bool)> {
   }
 }
 
+impl From<&Vec<GlobalDec>> for Scope<(Type, bool)> {
+  fn from(globals: &Vec<GlobalDec>) -> Self {
+    Scope {
+      elements: globals
+        .iter()
+        .map(|global| (global.name.clone(), (global._type.clone(), global.mutable)))
+        .collect(),
+    }
+  }
+}
+
 impl<T> Scope<T> {
   pub fn new() -> Self {
     Self {
@@ -0,0 +1,22 @@
+use crate::types::{_type::Type, location::Location};
+
+#[derive(Debug, Clone)]
+pub struct StructDec {
+  pub name: String,
+  // Field names and types in declaration order
+  pub fields: Vec<(String, Type)>,
+  // Location of the name of the struct
+  pub location: Location,
+}
+
+impl StructDec {
+  // Returns the position and type of a field
+  pub fn get_field(&self, field: &str) -> Option<(usize, &Type)> {
+    self
+      .fields
+      .iter()
+      .enumerate()
+      .find(|(_, (name, _))| name == field)
+      .map(|(i, (_, r#type))| (i, r#type))
+  }
+}
@@ -0,0 +1,67 @@
+use crate::types::location::Location;
+
+// The kinds of items a program keeps by path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+  Func,
+  Struct,
+  Enum,
+  Trait,
+  Alias,
+  // Constants and statics, which share their names
+  Global,
+}
+
+impl SymbolKind {
+  pub fn to_str(&self) -> &'static str {
+    match self {
+      SymbolKind::Func => "Function",
+      SymbolKind::Struct => "Struct",
+      SymbolKind::Enum => "Enum",
+      SymbolKind::Trait => "Trait",
+      SymbolKind::Alias => "Type alias",
+      SymbolKind::Global => "Global",
+    }
+  }
+}
+
+// The paths of the items of a program in the order they are declared in, which is
+// the order they are checked and their errors reported in
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+  symbols: Vec<(SymbolKind, String, Location)>,
+  // Items declared more than once, along with the location of the declaration
+  // kept and of the one left out
+  pub duplicates: Vec<(SymbolKind, String, Location, Location)>,
+}
+
+impl SymbolTable {
+  // Returns false if an item of the kind is already declared under the path, the
+  // declaration is then recorded as a duplicate and should be left out
+  pub fn declare(&mut self, kind: SymbolKind, path: &str, location: Location) -> bool {
+    let declared = self
+      .symbols
+      .iter()
+      .find(|(declared, name, _)| *declared == kind && name == path);
+    match declared {
+      Some((_, _, first)) => {
+        let duplicate = (kind, path.to_string(), *first, location);
+        self.duplicates.push(duplicate);
+        false
+      }
+      None => {
+        self.symbols.push((kind, path.to_string(), location));
+        true
+      }
+    }
+  }
+
+  // Paths of the items of a kind in declaration order
+  pub fn paths(&self, kind: SymbolKind) -> impl Iterator<Item = &String> {
+    self
+      .symbols
+      .iter()
+      .filter(move |(declared, _, _)| *declared == kind)
+      .map(|(_, path, _)| path)
+  }
+}
@@ -0,0 +1,16 @@
+use crate::types::{func::Func, location::Location};
+
+#[derive(Debug, Clone)]
+pub struct TraitDec {
+  pub name: String,
+  // Signatures of the methods, the type of self is the type parameter Self
+  pub methods: Vec<Func>,
+  // Location of the name of the trait
+  pub location: Location,
+}
+
+impl TraitDec {
+  pub fn get_method(&self, method: &str) -> Option<&Func> {
+    self.methods.iter().find(|func| func.name == method)
+  }
+}
@@ -1,9 +1,20 @@
-use crate::types::node::Node;
+use crate::types::{func_param::FuncParam, node::Node};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Value {
   Bool(bool),
   Int(i32),
+  Char(char),
+  Str(String),
+  Unit,
+  Array(Vec<Value>),
+  Tuple(Vec<Value>),
+  // Name of the struct, fields and their values
+  Struct(String, Vec<(String, Value)>),
+  // Enum, variant, fields
+  Variant(String, String, Vec<Value>),
+  // Parameters, body and the captured variables along with their values
+  Closure(Vec<FuncParam>, Box<Node>, Vec<(String, Value)>),
 }
 
 impl std::convert::From<&Value> for String {
@@ -11,6 +22,94 @@ impl std::convert::From<&Value> for String {
     match val {
       Value::Bool(b) => b.to_string(),
       Value::Int(i) => i.to_string(),
+      Value::Char(c) => c.to_string(),
+      Value::Str(s) => s.clone(),
+      Value::Unit => "()".to_string(),
+      Value::Array(values) => format!(
+        "[{}]",
+        values
+          .iter()
+          .map(|value| value.into())
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      Value::Tuple(values) if values.len() == 1 => {
+        format!("({},)", String::from(&values[0]))
+      }
+      Value::Tuple(values) => format!(
+        "({})",
+        values
+          .iter()
+          .map(|value| value.into())
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      Value::Struct(name, fields) => format!(
+        "{} {{ {} }}",
+        name,
+        fields
+          .iter()
+          .map(|(field, value)| format!("{}: {}", field, String::from(value)))
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      Value::Variant(r#enum, variant, fields) if fields.len() == 0 => {
+        format!("{}::{}", r#enum, variant)
+      }
+      Value::Variant(r#enum, variant, fields) => format!(
+        "{}::{}({})",
+        r#enum,
+        variant,
+        fields
+          .iter()
+          .map(|value| value.into())
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      Value::Closure(params, body, _) => {
+        Node::Closure(params.clone(), None, body.clone(), None).expr_into_string()
+      }
+    }
+  }
+}
+
+impl std::convert::From<Value> for Node {
+  fn from(val: Value) -> Self {
+    match val {
+      Value::Bool(b) => Node::Bool(b),
+      Value::Int(i) => Node::Number(i),
+      Value::Char(c) => Node::Char(c),
+      Value::Str(s) => Node::Str(s),
+      Value::Unit => Node::Unit,
+      Value::Array(values) => {
+        Node::Array(values.into_iter().map(|value| value.into()).collect())
+      }
+      Value::Tuple(values) => {
+        Node::Tuple(values.into_iter().map(|value| value.into()).collect())
+      }
+      Value::Struct(name, fields) => Node::Struct(
+        name,
+        fields
+          .into_iter()
+          .map(|(field, value)| (field, value.into()))
+          .collect(),
+      ),
+      Value::Variant(r#enum, variant, fields) => Node::Variant(
+        r#enum,
+        variant,
+        fields.into_iter().map(|value| value.into()).collect(),
+      ),
+      Value::Closure(params, body, captures) => Node::Closure(
+        params,
+        None,
+        body,
+        Some(
+          captures
+            .into_iter()
+            .map(|(name, value)| (name, value.into()))
+            .collect(),
+        ),
+      ),
     }
   }
 }
@@ -22,6 +121,45 @@ impl std::convert::TryFrom<Node> for Value {
     match node {
       Node::Bool(b) => Ok(Value::Bool(b)),
       Node::Number(i) => Ok(Value::Int(i)),
+      Node::Char(c) => Ok(Value::Char(c)),
+      Node::Str(s) => Ok(Value::Str(s)),
+      Node::Unit => Ok(Value::Unit),
+      Node::Array(nodes) => {
+        let mut values = vec![];
+        for node in nodes {
+          values.push(Value::try_from(node)?);
+        }
+        Ok(Value::Array(values))
+      }
+      Node::Tuple(nodes) => {
+        let mut values = vec![];
+        for node in nodes {
+          values.push(Value::try_from(node)?);
+        }
+        Ok(Value::Tuple(values))
+      }
+      Node::Struct(name, fields) => {
+        let mut values = vec![];
+        for (field, node) in fields {
+          values.push((field, Value::try_from(node)?));
+        }
+        Ok(Value::Struct(name, values))
+      }
+      Node::Variant(r#enum, variant, nodes) => {
+        let mut values = vec![];
+        for node in nodes {
+          values.push(Value::try_from(node)?);
+        }
+        Ok(Value::Variant(r#enum, variant, values))
+      }
+      // Only evaluated closures hold the values of the variables they capture
+      Node::Closure(params, _, body, Some(captures)) => {
+        let mut values = vec![];
+        for (name, node) in captures {
+          values.push((name, Value::try_from(node)?));
+        }
+        Ok(Value::Closure(params, body, values))
+      }
       _ => Err("Cannot convert node to value"),
     }
   }
@@ -1,6 +1,6 @@
 use crate::types::value::Value;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Variable {
   pub name: String,
   pub value: Value,
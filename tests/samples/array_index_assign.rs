@@ -0,0 +1,7 @@
+fn main() -> i32 {
+  let mut grid = [[0; 3]; 2];
+  grid[1][2] = 5;
+  let mut row = grid[1];
+  row[0] = 2;
+  return grid[1][2] + row[0] + grid[1][0];
+}
@@ -0,0 +1,8 @@
+fn main() -> i32 {
+  let xs = [1, 2, 3];
+  let mut i = 0;
+  while i < 4 {
+    i = i + 1;
+  }
+  return xs[i - 1];
+}
@@ -0,0 +1,14 @@
+fn sum(xs: [i32; 4]) -> i32 {
+  let mut total = 0;
+  let mut i = 0;
+  while i < xs.len() {
+    total = total + xs[i];
+    i = i + 1;
+  }
+  return total;
+}
+
+fn main() -> i32 {
+  let xs = [1, 2, 3, 4];
+  return sum(xs);
+}
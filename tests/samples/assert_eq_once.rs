@@ -0,0 +1,10 @@
+fn next(n: i32) -> i32 {
+  println!("next({})", n);
+  return n + 1;
+}
+
+fn main() -> i32 {
+  assert_eq!(next(1), 2);
+  assert_eq!(next(2), next(3));
+  return 0;
+}
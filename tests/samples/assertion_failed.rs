@@ -0,0 +1,10 @@
+fn sum(a: i32, b: i32) -> i32 {
+  return a + b;
+}
+
+fn main() -> i32 {
+  let total = sum(2, 2);
+  assert!(total > 0);
+  assert_eq!(total, 5);
+  return total;
+}
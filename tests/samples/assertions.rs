@@ -0,0 +1,27 @@
+fn factorial(n: i32) -> i32 {
+  assert!(n >= 0);
+  let mut result = 1;
+  let mut i = 2;
+  while i <= n {
+    result = result * i;
+    i = i + 1;
+  }
+  return result;
+}
+
+fn sign(n: i32) -> i32 {
+  if n < 0 {
+    return -1;
+  }
+  if n > 0 {
+    return 1;
+  }
+  panic!("sign of zero");
+}
+
+fn main() -> i32 {
+  assert_eq!(factorial(4), 24);
+  assert!(sign(-5) == -1 && sign(5) == 1);
+  assert_eq!("done", "done");
+  return factorial(3) + sign(7);
+}
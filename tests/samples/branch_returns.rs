@@ -0,0 +1,29 @@
+fn clamp(n: i32, low: i32, high: i32) -> i32 {
+  if n < low {
+    return low;
+  } else {
+    if n > high {
+      return high;
+    } else {
+      return n;
+    }
+  }
+}
+
+fn classify(n: i32) -> i32 {
+  if n == 0 {
+    return 0;
+  }
+  let mut i = 0;
+  while i < n {
+    if i * i == n {
+      return 2;
+    }
+    i = i + 1;
+  }
+  return 1;
+}
+
+fn main() -> i32 {
+  return clamp(15, 0, 10) + clamp(-4, 0, 10) + clamp(7, 0, 10) * 2 + classify(9);
+}
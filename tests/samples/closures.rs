@@ -0,0 +1,49 @@
+fn add(a: i32, b: i32) -> i32 {
+  return a + b;
+}
+
+fn map(xs: [i32; 4], f: impl Fn(i32) -> i32) -> [i32; 4] {
+  let mut ys = xs;
+  let mut i = 0;
+  while i < xs.len() {
+    ys[i] = f(xs[i]);
+    i = i + 1;
+  }
+  return ys;
+}
+
+fn fold(xs: [i32; 4], init: i32, f: fn(i32, i32) -> i32) -> i32 {
+  let mut acc = init;
+  let mut i = 0;
+  while i < xs.len() {
+    acc = f(acc, xs[i]);
+    i = i + 1;
+  }
+  return acc;
+}
+
+fn make_adder(k: i32) -> impl Fn(i32) -> i32 {
+  return move |x: i32| x + k;
+}
+
+fn apply_twice<T>(f: fn(T) -> T, x: T) -> T {
+  return f(f(x));
+}
+
+fn main() -> i32 {
+  let k = 3;
+  let triple = |x: i32| x * k;
+  let k = 100;
+  let xs = map([1, 2, 3, 4], triple);
+  let total = fold(map(xs, make_adder(1)), 0, add);
+  let limit = 10;
+  let clamp = move |n: i32| match n {
+    m if m > limit => limit,
+    m => m,
+  };
+  let answer = || 2;
+  if apply_twice(|b: bool| b == false, true) && k == 100 {
+    return total + clamp(triple(4)) + answer();
+  }
+  return 0;
+}
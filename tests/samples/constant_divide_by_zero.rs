@@ -0,0 +1,6 @@
+const ZERO: i32 = 0;
+const N: i32 = 1 / ZERO;
+
+fn main() -> i32 {
+  return N;
+}
@@ -0,0 +1,11 @@
+#[ensures(result > a && result > b)]
+fn max(a: i32, b: i32) -> i32 {
+  if a > b {
+    return a;
+  }
+  return a;
+}
+
+fn main() -> i32 {
+  return max(5, 3) + max(2, 7);
+}
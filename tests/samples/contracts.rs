@@ -0,0 +1,35 @@
+#[requires(n >= 0)]
+#[ensures(result >= 1)]
+fn factorial(n: i32) -> i32 {
+  let mut result = 1;
+  let mut i = 1;
+  #[invariant(i <= (n + 1))]
+  #[invariant(result >= 1)]
+  while i <= n {
+    result = result * i;
+    i = i + 1;
+  }
+  return result;
+}
+
+#[requires(b != 0)]
+#[ensures((result * b) <= a)]
+fn divide(a: i32, b: i32) -> i32 {
+  if a < b {
+    return 0;
+  }
+  return a / b;
+}
+
+#[ensures(n > 0)]
+fn check_positive(n: i32) {
+  if n == 1 {
+    return;
+  }
+}
+
+fn main() -> i32 {
+  check_positive(1);
+  check_positive(2);
+  return factorial(4) + divide(9, 2);
+}
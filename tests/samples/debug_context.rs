@@ -0,0 +1,40 @@
+struct Point {
+  x: i32,
+  y: i32,
+}
+
+enum Shape {
+  Dot,
+  Circle(Point, i32),
+}
+
+fn area(shape: Shape) -> i32 {
+  match shape {
+    Shape::Dot => {
+      $DEBUG_CONTEXT
+      return 0;
+    }
+    Shape::Circle(center, radius) => {
+      $DEBUG_CONTEXT
+      return 3 * radius * radius;
+    }
+  }
+}
+
+fn main() -> i32 {
+  let name = "unit";
+  let letters = ['a', 'b'];
+  let pair = (true, 7);
+  let single = (1,);
+  let add = |n: i32| n + 1;
+  let count = 2;
+  let total: i32;
+  if count > 1 {
+    let count = 3;
+    print!("count is {}\n", count);
+    $DEBUG_CONTEXT
+  }
+  total = area(Shape::Dot) + area(Shape::Circle(Point { x: 1, y: 2 }, 2)) + add(count);
+  $DEBUG_CONTEXT
+  return total;
+}
@@ -0,0 +1,12 @@
+fn main() -> i32 {
+  let x: i32;
+  let x = 5;
+  let y: i32;
+  $DEBUG_CONTEXT
+  y = x + 1;
+  let y: i32;
+  $DEBUG_CONTEXT
+  y = 7;
+  $DEBUG_CONTEXT
+  return x + y;
+}
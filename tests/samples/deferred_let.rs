@@ -0,0 +1,35 @@
+fn sign(n: i32) -> i32 {
+  let sign: i32;
+  if n < 0 {
+    sign = -1;
+  } else {
+    if n == 0 {
+      return 0;
+    }
+    sign = 1;
+  }
+  return sign;
+}
+
+fn main() -> i32 {
+  let total: i32;
+  let mut steps: i32;
+  steps = 0;
+  let mut i = 0;
+  while i < 4 {
+    steps = steps + i;
+    i = i + 1;
+  }
+  let parity: i32;
+  let rem = steps - steps / 2 * 2;
+  match rem {
+    0 => {
+      parity = 10;
+    }
+    _ => {
+      parity = 20;
+    }
+  }
+  total = steps + parity + sign(-5) + sign(0) + sign(7);
+  return total;
+}
@@ -0,0 +1,7 @@
+fn quotient(n: i32, d: i32) -> i32 {
+  return n / d;
+}
+
+fn main() -> i32 {
+  return quotient(-2147483648, -1);
+}
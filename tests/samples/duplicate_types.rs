@@ -0,0 +1,23 @@
+struct Point {
+  x: i32,
+  y: i32,
+}
+
+enum Shape {
+  Circle(i32),
+  Square(i32),
+}
+
+struct Point {
+  x: i32,
+}
+
+enum Shape {
+  Dot,
+}
+
+fn main() -> i32 {
+  let p = Point { x: 1, y: 2 };
+  let s = Shape::Square(3);
+  return p.x + p.y;
+}
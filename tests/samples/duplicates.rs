@@ -0,0 +1,20 @@
+fn area(width: i32, height: i32) -> i32 {
+  return width * height;
+}
+
+fn scale(n: i32, factor: i32, n: i32) -> i32 {
+  return n * factor;
+}
+
+fn area(side: i32) -> i32 {
+  return side * side;
+}
+
+fn main() -> i32 {
+  let twice = |x: i32, x: i32| x + x;
+  return area(2, 3) + scale(1, 2, 3);
+}
+
+const LIMIT: i32 = 10;
+
+static LIMIT: i32 = 20;
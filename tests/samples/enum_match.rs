@@ -0,0 +1,71 @@
+enum Shape {
+  Circle(i32),
+  Rect(i32, i32),
+  Empty,
+}
+
+enum Slot {
+  Filled(Shape),
+  Free,
+}
+
+enum Direction {
+  North,
+  East,
+  South,
+  West,
+}
+
+fn area(shape: Shape) -> i32 {
+  match shape {
+    Shape::Circle(r) => {
+      return 3 * r * r;
+    }
+    Shape::Rect(w, h) => {
+      return w * h;
+    }
+    Shape::Empty => {
+      return 0;
+    }
+  }
+}
+
+fn slot_area(slot: Slot) -> i32 {
+  return match slot {
+    Slot::Filled(Shape::Empty) => 0,
+    Slot::Filled(shape) => area(shape),
+    Slot::Free => 0,
+  };
+}
+
+fn turn(direction: Direction) -> Direction {
+  return match direction {
+    Direction::North => Direction::East,
+    Direction::East => Direction::South,
+    Direction::South => Direction::West,
+    Direction::West => Direction::North,
+  };
+}
+
+fn main() -> i32 {
+  let slots = [
+    Slot::Filled(Shape::Circle(2)),
+    Slot::Filled(Shape::Rect(3, 4)),
+    Slot::Free,
+    Slot::Filled(Shape::Empty),
+  ];
+  let mut total = 0;
+  let mut i = 0;
+  while i < slots.len() {
+    total = total + slot_area(slots[i]);
+    i = i + 1;
+  }
+  let direction = turn(turn(Direction::West));
+  match direction {
+    Direction::East => {
+      total = total + 100;
+    }
+    _ => {}
+  }
+  return total;
+}
@@ -1,4 +1,4 @@
-fn fib_rec(n: mut i32) -> i32{
+fn fib_rec(mut n: i32) -> i32{
     if n == 0{
         return 0;
     }
@@ -0,0 +1,26 @@
+fn describe(n: i32) {
+  match n {
+    0 => {
+      println!("{} is zero", n);
+    }
+    1..=9 => {
+      println!("{} has one digit", n);
+    }
+    _ => {
+      println!("{} has more digits", n);
+    }
+  }
+}
+
+fn main() -> i32 {
+  let mut i = 0;
+  while i < 12 {
+    describe(i);
+    i = i + 5;
+  }
+  print!("{{braces}} ");
+  print!("{} {} {}", true, 'c', "str");
+  println!();
+  println!("{}+{}={}", 2, -3, 2 + -3,);
+  return i;
+}
@@ -0,0 +1,37 @@
+struct Point {
+  x: i32,
+  y: i32,
+}
+
+fn pick<T>(first: bool, a: T, b: T) -> T {
+  if first {
+    return a;
+  }
+  return b;
+}
+
+fn swap<A, B>(pair: (A, B)) -> (B, A) {
+  let (a, b) = pair;
+  return (b, a);
+}
+
+fn identity<T>(x: T) -> T {
+  let y: T = x;
+  return y;
+}
+
+fn nested<T>(x: T) -> T {
+  return identity(identity(x));
+}
+
+fn main() -> i32 {
+  let a = pick(true, 40, 2);
+  let b = pick(false, 'a', 'b');
+  let (d, c) = swap((true, 2));
+  let s = nested("str");
+  let p = identity(Point { x: 1, y: 2 });
+  if b == 'b' && c && s.len() == 3 {
+    return a + d + p.x + p.y;
+  }
+  return 0;
+}
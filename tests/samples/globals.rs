@@ -0,0 +1,36 @@
+const AREA: i32 = LIMIT * LIMIT;
+const LIMIT: i32 = 12;
+const PRIMES: [i32; 4] = [2, 3, 5, 7];
+const ORIGIN: Point = Point { x: 1, y: LIMIT / 6 };
+static GREETING: &str = "hi";
+static mut COUNTER: i32 = 0;
+
+struct Point {
+  x: i32,
+  y: i32,
+}
+
+fn bump(by: i32) -> i32 {
+  COUNTER = COUNTER + by;
+  return COUNTER;
+}
+
+fn main() -> i32 {
+  bump(3);
+  bump(4);
+  let read = || COUNTER + LIMIT;
+  bump(1);
+  let mut sum = 0;
+  let mut i = 0;
+  while i < 4 {
+    sum = sum + PRIMES[i];
+    i = i + 1;
+  }
+  if GREETING == "hi" {
+    sum = sum + ORIGIN.x;
+  }
+  if COUNTER == 8 {
+    sum = sum + 10;
+  }
+  return AREA - read() + sum + ORIGIN.y;
+}
@@ -0,0 +1,34 @@
+enum Coin {
+  Heads,
+  Tails,
+}
+
+fn pick(flag: bool) -> i32 {
+  if flag {
+    return 1;
+  } else {
+    return 2;
+  }
+}
+
+fn positive(n: i32) -> i32 {
+  if n > 0 {
+    return n;
+  }
+  panic!("{} is not positive", n);
+}
+
+fn value(coin: Coin) -> i32 {
+  match coin {
+    Coin::Heads => {
+      return 100;
+    }
+    Coin::Tails => {
+      return 200;
+    }
+  }
+}
+
+fn main() -> i32 {
+  return pick(true) * 10 + pick(false) + positive(3) + value(Coin::Tails);
+}
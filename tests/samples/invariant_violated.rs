@@ -0,0 +1,10 @@
+fn main() -> i32 {
+  let mut sum = 0;
+  let mut i = 0;
+  #[invariant(sum < 10)]
+  while i < 5 {
+    sum = sum + i;
+    i = i + 1;
+  }
+  return sum;
+}
@@ -0,0 +1,53 @@
+struct Point {
+  x: i32,
+  y: i32,
+}
+
+macro_rules! square {
+  ($x:expr) => {
+    $x * $x
+  };
+}
+
+macro_rules! swap {
+  ($a:ident, $b:ident) => {
+    let t = $a;
+    $a = $b;
+    $b = t;
+  };
+}
+
+macro_rules! sum {
+  () => { 0 };
+  ($x:expr $(, $rest:expr)*) => {
+    $x + sum!($($rest),*)
+  };
+}
+
+macro_rules! max {
+  ($a:expr, $b:expr) => {
+    match $a > $b {
+      true => $a,
+      false => $b,
+    }
+  };
+}
+
+macro_rules! shift {
+  ($p:ident, $dx:expr) => {
+    let x = $p.x + $dx;
+    let y = $p.y;
+    $p = Point { x: x, y };
+  };
+}
+
+fn main() -> i32 {
+  let t = 3;
+  let mut u = 4;
+  let mut v = t;
+  swap!(u, v);
+  let squared = square!(u + 1);
+  let mut p = Point { x: 1, y: 2 };
+  shift!(p, 5);
+  return sum!(squared, v, t * 2, max!(v, 10)) - square!(2) + p.x - p.y;
+}
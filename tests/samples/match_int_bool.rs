@@ -0,0 +1,50 @@
+fn days_in_month(month: i32, leap: bool) -> i32 {
+  return match month {
+    2 if leap => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    1..=12 => 31,
+    _ => 0,
+  };
+}
+
+fn sign(n: i32) -> i32 {
+  match n {
+    0 => {
+      return 0;
+    }
+    1..=2147483647 => {
+      return 1;
+    }
+    _ => {
+      return -1;
+    }
+  }
+}
+
+fn main() -> i32 {
+  let mut total = 0;
+  let mut month = 0;
+  while month < 14 {
+    total = total + days_in_month(month, true);
+    month = month + 1;
+  }
+  let leap = total == 366;
+  let bonus = match leap {
+    true => 100,
+    false => 0,
+  };
+  let mut signs = 0;
+  let mut n = -2;
+  while n < 3 {
+    signs = signs + sign(n) * n;
+    n = n + 1;
+  }
+  match signs {
+    x if x > 5 => {
+      total = total + x;
+    }
+    _ => {}
+  }
+  return total + bonus - 400;
+}
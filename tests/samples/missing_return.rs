@@ -0,0 +1,35 @@
+fn sign(n: i32) -> i32 {
+  if n < 0 {
+    return -1;
+  } else {
+    if n > 0 {
+      return 1;
+    }
+  }
+}
+
+fn first_square(n: i32) -> i32 {
+  let mut i = 0;
+  while i < n {
+    if i * i == n {
+      return i;
+    }
+    i = i + 1;
+  }
+}
+
+fn pick_one(flag: bool) -> i32 {
+  if flag {
+    return 1;
+  }
+}
+
+fn countdown(n: i32) -> i32 {
+  while n > 0 {
+    return n;
+  }
+}
+
+fn main() -> i32 {
+  return sign(3) + first_square(9) + pick_one(true) + countdown(2);
+}
@@ -0,0 +1,39 @@
+pub struct Point {
+  pub x: i32,
+  pub y: i32,
+}
+
+pub enum Shape {
+  Square(i32),
+  Dot,
+}
+
+pub trait Size {
+  fn size(self) -> i32;
+}
+
+impl Size for Point {
+  fn size(self) -> i32 {
+    return self.x + self.y;
+  }
+}
+
+pub fn origin() -> Point {
+  return Point { x: 1, y: 2 };
+}
+
+pub fn square(n: i32) -> i32 {
+  return n * n;
+}
+
+pub mod shapes {
+  use super::Point;
+
+  pub fn area(p: Point) -> i32 {
+    return super::square(p.x) + offset(p.y);
+  }
+
+  fn offset(n: i32) -> i32 {
+    return n + 1;
+  }
+}
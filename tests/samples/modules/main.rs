@@ -0,0 +1,25 @@
+mod geometry;
+mod util;
+
+use geometry::shapes::area;
+use geometry::Point;
+use geometry::Size;
+
+fn scale(p: Point, by: i32) -> Point {
+  return Point {
+    x: p.x * by,
+    y: p.y * by,
+  };
+}
+
+fn main() -> i32 {
+  let p = scale(geometry::origin(), 3);
+  let total = area(p) + util::LIMIT;
+  let shape = geometry::Shape::Square(2);
+  let side = match shape {
+    geometry::Shape::Square(side) => side,
+    geometry::Shape::Dot => 0,
+  };
+  let q = geometry::origin();
+  return total + util::double(side) + geometry::square(side) + q.size();
+}
@@ -0,0 +1,15 @@
+mod vault {
+  pub fn open() -> i32 {
+    return code();
+  }
+
+  fn code() -> i32 {
+    return 7;
+  }
+}
+
+use vault::missing;
+
+fn main() -> i32 {
+  return vault::open() + vault::code();
+}
@@ -0,0 +1,12 @@
+use crate::geometry;
+
+pub const LIMIT: i32 = BASE * 2;
+const BASE: i32 = 5;
+
+pub fn double(n: i32) -> i32 {
+  return helper(n) + geometry::square(0);
+}
+
+fn helper(n: i32) -> i32 {
+  return n * 2;
+}
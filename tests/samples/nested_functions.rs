@@ -0,0 +1,39 @@
+fn helper() -> i32 {
+  return 1;
+}
+
+mod main {
+  pub fn helper() -> i32 {
+    return 1000;
+  }
+}
+
+fn main() -> i32 {
+  let a = helper();
+  let b = first(20, 0);
+  let mut c = 0;
+  if a == 10 {
+    fn helper() -> i32 {
+      return 100;
+    }
+    c = helper();
+  }
+  let d = outer();
+  fn helper() -> i32 {
+    return square(3) + 1;
+  }
+  fn square(x: i32) -> i32 {
+    return x * x;
+  }
+  fn first<T>(x: T, y: T) -> T {
+    return x;
+  }
+  fn outer() -> i32 {
+    fn inner() -> i32 {
+      return 5;
+    }
+    return inner();
+  }
+  let square = 1;
+  return a + b + c + d + square + main::helper();
+}
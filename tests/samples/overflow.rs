@@ -0,0 +1,13 @@
+fn sum(xs: [i32; 3]) -> i32 {
+  let mut total = 0;
+  let mut i = 0;
+  while i < xs.len() {
+    total = total + xs[i];
+    i = i + 1;
+  }
+  return total;
+}
+
+fn main() -> i32 {
+  return sum([2147483000, 600, 100]);
+}
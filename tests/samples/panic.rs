@@ -0,0 +1,10 @@
+fn checked_div(a: i32, b: i32) -> i32 {
+  if b == 0 {
+    panic!("attempt to divide {} by zero", a);
+  }
+  return a / b;
+}
+
+fn main() -> i32 {
+  return checked_div(10, 2) + checked_div(7, 0);
+}
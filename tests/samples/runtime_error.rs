@@ -0,0 +1,15 @@
+fn fib_rec(n: i32) -> i32 {
+  if n < 2 {
+    return n;
+  }
+  return fib_rec(n - 1) + fib_rec(n - 2);
+}
+
+fn ratio(n: i32) -> i32 {
+  return fib_rec(n + 1) / fib_rec(n);
+}
+
+fn main() -> i32 {
+  let doubled = |n: i32| ratio(n) * 2;
+  return doubled(5) + doubled(0);
+}
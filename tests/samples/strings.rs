@@ -0,0 +1,32 @@
+fn greeting(formal: bool) -> &'static str {
+  if formal {
+    return "Good evening";
+  }
+  return "Hi";
+}
+
+fn is_vowel(c: char) -> bool {
+  return c == 'a' || c == 'e' || c == 'i' || c == 'o' || c == 'u';
+}
+
+fn main() -> i32 {
+  let name: &str = "Wörld";
+  let hello = greeting(false);
+  println!("{}", hello);
+  println!("tab\there \"quoted\" \\ done");
+  println!("{}", 'x');
+  println!("{}", '\n' == '\n');
+  let letters = ['r', 'u', 's', 't'];
+  let mut vowels = 0;
+  let mut i = 0;
+  while i < letters.len() {
+    if is_vowel(letters[i]) {
+      vowels = vowels + 1;
+    }
+    i = i + 1;
+  }
+  if greeting(true) != hello && hello == "Hi" {
+    return greeting(true).len() * 10 + vowels + name.len();
+  }
+  return 0;
+}
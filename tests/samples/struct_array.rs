@@ -0,0 +1,15 @@
+struct Point {
+  x: i32,
+  y: i32,
+}
+
+fn origin() -> Point {
+  return Point { x: 0, y: 0 };
+}
+
+fn main() -> i32 {
+  let mut points = [origin(); 3];
+  points[1].x = 5;
+  points[2] = Point { x: 1, y: 2 };
+  return points[1].x + points[2].y + points[0].x;
+}
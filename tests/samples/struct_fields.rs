@@ -0,0 +1,28 @@
+struct Point {
+  x: i32,
+  y: i32,
+}
+
+struct Reading {
+  sensor: i32,
+  position: Point,
+  valid: bool,
+}
+
+fn manhattan(p: Point) -> i32 {
+  return p.x + p.y;
+}
+
+fn main() -> i32 {
+  let x = 3;
+  let mut reading = Reading {
+    valid: true,
+    sensor: 7,
+    position: Point { x, y: 4 },
+  };
+  reading.position.y = reading.position.y * 10;
+  if reading.valid {
+    return manhattan(reading.position) + reading.sensor;
+  }
+  return 0;
+}
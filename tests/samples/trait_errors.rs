@@ -0,0 +1,32 @@
+trait Area {
+  fn area(self) -> i32;
+  fn scaled(self, factor: i32) -> Self;
+}
+
+impl Area for i32 {
+  fn area(self) -> i32 {
+    return self;
+  }
+
+  fn perimeter(self) -> i32 {
+    return self;
+  }
+}
+
+impl Area for bool {
+  fn area(self) -> bool {
+    return self;
+  }
+
+  fn scaled(self, factor: i32) -> bool {
+    return self;
+  }
+}
+
+fn area_of<T: Area>(shape: T) -> i32 {
+  return shape.area();
+}
+
+fn main() -> i32 {
+  return area_of('c');
+}
@@ -0,0 +1,64 @@
+struct Rect {
+  width: i32,
+  height: i32,
+}
+
+trait Area {
+  fn area(self) -> i32;
+  fn scaled(self, factor: i32) -> Self;
+}
+
+impl Area for Rect {
+  fn area(self) -> i32 {
+    return self.width * self.height;
+  }
+
+  fn scaled(self, factor: i32) -> Rect {
+    return Rect {
+      width: self.width * factor,
+      height: self.height * factor,
+    };
+  }
+}
+
+impl Area for i32 {
+  fn area(self) -> i32 {
+    return self * self;
+  }
+
+  fn scaled(self, factor: i32) -> i32 {
+    return self * factor;
+  }
+}
+
+trait Describe {
+  fn describe(self) -> &'static str;
+}
+
+impl Describe for bool {
+  fn describe(self) -> &'static str {
+    if self {
+      return "yes";
+    }
+    return "no";
+  }
+}
+
+fn doubled_area<T: Area>(shape: T) -> i32 {
+  return shape.scaled(2).area();
+}
+
+fn main() -> i32 {
+  let rect = Rect {
+    width: 2,
+    height: 3,
+  };
+  let a = rect.area();
+  let b = doubled_area(Rect {
+    width: 1,
+    height: 2,
+  });
+  let c = doubled_area(3);
+  println!("{}", true.describe());
+  return a + b + c;
+}
@@ -0,0 +1,9 @@
+fn main() -> i32 {
+  let t = ((1, 2), [3, 4], true);
+  let (mut inner, xs, flag) = t;
+  inner = (inner.1, inner.0);
+  if flag {
+    return inner.0 * 100 + xs[1] * 10 + t.0.1;
+  }
+  return 0;
+}
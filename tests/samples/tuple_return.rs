@@ -0,0 +1,12 @@
+fn div_rem(a: i32, b: i32) -> (i32, i32) {
+  return (a / b, a - (a / b) * b);
+}
+
+fn main() -> i32 {
+  let (q, r) = div_rem(17, 5);
+  let pair: (i32, bool) = (q, r == 2);
+  if pair.1 {
+    return pair.0 * 10 + r;
+  }
+  return 0;
+}
@@ -0,0 +1,42 @@
+type Celsius = i32;
+type Reading = (Celsius, bool);
+type Readings = [Reading; 3];
+type Convert = fn(Celsius) -> Fahrenheit;
+type Fahrenheit = i32;
+type Meters = i32;
+
+trait Double {
+  fn double(self) -> Self;
+}
+
+impl Double for Meters {
+  fn double(self) -> Meters {
+    return self * 2;
+  }
+}
+
+fn to_fahrenheit(c: Celsius) -> Fahrenheit {
+  return c * 9 / 5 + 32;
+}
+
+fn warmest(readings: Readings) -> Celsius {
+  let mut warmest: Celsius = -273;
+  let mut i = 0;
+  while i < 3 {
+    let (temperature, valid) = readings[i];
+    if valid {
+      if temperature > warmest {
+        warmest = temperature;
+      }
+    }
+    i = i + 1;
+  }
+  return warmest;
+}
+
+fn main() -> i32 {
+  let readings: Readings = [(20, true), (35, false), (25, true)];
+  let convert: Convert = to_fahrenheit;
+  let height: Meters = 10;
+  return convert(warmest(readings)) + height.double();
+}
@@ -0,0 +1,43 @@
+fn unassigned() -> i32 {
+  let x: i32;
+  return x;
+}
+
+fn assigned_on_one_branch(c: bool) -> i32 {
+  let x: i32;
+  if c {
+    x = 1;
+  }
+  return x;
+}
+
+fn assigned_in_loop(n: i32) -> i32 {
+  let x: i32;
+  let mut i = 0;
+  while i < n {
+    x = i;
+    i = i + 1;
+  }
+  return 0;
+}
+
+fn assigned_twice(c: bool) -> i32 {
+  let x: i32;
+  x = 1;
+  if c {
+    x = 2;
+  }
+  return x;
+}
+
+fn shadowed() -> i32 {
+  let x: i32;
+  x = 3;
+  let x: [i32; 2];
+  x[0] = 1;
+  return 0;
+}
+
+fn main() -> i32 {
+  return 0;
+}
@@ -0,0 +1,9 @@
+fn foo(a: i32) -> () {
+  let b = a;
+}
+
+fn main() -> i32 {
+  let x = foo(3);
+  let y: () = x;
+  return 4;
+}
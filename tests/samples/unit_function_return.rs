@@ -0,0 +1,12 @@
+fn check(a: i32) {
+  if a == 2 {
+    return;
+  }
+  let b = a;
+}
+
+fn main() -> i32 {
+  check(2);
+  check(3);
+  return 5;
+}
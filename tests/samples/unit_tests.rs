@@ -0,0 +1,42 @@
+fn add(a: i32, b: i32) -> i32 {
+  return a + b;
+}
+
+fn first_missing(xs: [i32; 2]) -> i32 {
+  return xs[2];
+}
+
+#[test]
+fn adds_numbers() {
+  assert_eq!(add(2, 3), 5);
+  assert!(add(-1, 1) == 0);
+}
+
+#[test]
+fn counts_to_three() {
+  let mut counter = 0;
+  while counter < 3 {
+    counter = counter + 1;
+  }
+  assert_eq!(counter, 3);
+}
+
+#[test]
+fn indexes_out_of_bounds() {
+  first_missing([1, 2]);
+}
+
+mod math {
+  pub fn half(n: i32) -> i32 {
+    return n / 2;
+  }
+
+  #[test]
+  fn halves() {
+    assert_eq!(half(7), 4);
+  }
+}
+
+fn main() -> i32 {
+  return add(1, 2);
+}